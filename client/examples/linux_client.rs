@@ -27,9 +27,9 @@ use eui48::MacAddress;
 use tokio::prelude::*;
 use tokio::reactor::Handle;
 
-use dhcp_client::{Client, Command};
+use dhcp_client::{Client, ClientBuilder, Command};
 use dhcp_framed::{DhcpFramed, DhcpSinkItem, DhcpStreamItem};
-use dhcp_protocol::{DHCP_PORT_CLIENT, SIZE_MESSAGE_MINIMAL};
+use dhcp_protocol::{HardwareAddress, HardwareType, DHCP_PORT_CLIENT, SIZE_MESSAGE_MINIMAL};
 use ifcontrol::Iface;
 use net2::UdpBuilder;
 use tokio::net::UdpSocket;
@@ -106,25 +106,17 @@ fn main() {
         .expect("Socket binding error")
         .split();
 
-    let server_address = None;
-    let client_address = None;
-    let address_request = Some(Ipv4Addr::new(192, 168, 0, 60));
-    let address_time = Some(60);
-    let max_message_size = Some(SIZE_MESSAGE_MINIMAL as u16);
-
-    let client = SuperClient::new(Client::new(
+    let mut builder = ClientBuilder::new(
         stream,
         sink,
-        MacAddress::new([0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]),
-        None,
-        None,
-        server_address,
-        client_address,
-        address_request,
-        address_time,
-        max_message_size,
-        false,
-    ));
+        HardwareType::Ethernet,
+        HardwareAddress::from(MacAddress::new([0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])),
+    );
+    builder
+        .with_address_request(Ipv4Addr::new(192, 168, 0, 60))
+        .with_address_time(60)
+        .with_max_message_size(SIZE_MESSAGE_MINIMAL as u16);
+    let client = SuperClient::new(builder.finish());
 
     let future = client.map_err(|error| error!("Error: {}", error));
 
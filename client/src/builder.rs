@@ -2,35 +2,57 @@
 
 use std::net::Ipv4Addr;
 
-use eui48::{EUI48LEN, MacAddress};
-
 use dhcp_protocol::*;
 
 /// Builds common client messages with some parameters.
 pub struct MessageBuilder {
-    /// Mandatory `MAC-48` address.
-    client_hardware_address: MacAddress,
+    /// The hardware type of the `client_hardware_address`.
+    hardware_type: HardwareType,
+    /// Mandatory hardware address (zero-length for some hardware types like InfiniBand).
+    client_hardware_address: HardwareAddress,
     /// Is set explicitly by user or defaulted to `client_hardware_address` bytes.
     client_id: Vec<u8>,
     /// The optional machine hostname.
     hostname: Option<String>,
+    /// The optional FQDN the client sends to let the server update DNS.
+    client_fqdn: Option<ClientFqdn>,
+    /// The optional vendor class identifier the server may choose vendor options by.
+    class_id: Option<Vec<u8>>,
+    /// The optional user classes the server may choose the configuration by.
+    user_class: Option<UserClass>,
     /// The optional maximum DHCP message size the client will accept.
     max_message_size: Option<u16>,
+    /// Whether the client is prepared for the `DHCPDISCOVER`-`DHCPACK` exchange.
+    rapid_commit: bool,
+    /// Whether the client requests the IPv6-Only Preferred option.
+    ipv6_only: bool,
 }
 
 impl MessageBuilder {
     /// Creates a builder with message parameters which will not be changed.
     pub fn new(
-        client_hardware_address: MacAddress,
+        hardware_type: HardwareType,
+        client_hardware_address: HardwareAddress,
         client_id: Vec<u8>,
         hostname: Option<String>,
+        client_fqdn: Option<ClientFqdn>,
+        class_id: Option<Vec<u8>>,
+        user_class: Option<UserClass>,
         max_message_size: Option<u16>,
+        rapid_commit: bool,
+        ipv6_only: bool,
     ) -> Self {
         MessageBuilder {
+            hardware_type,
             client_hardware_address,
             client_id,
             hostname,
+            client_fqdn,
+            class_id,
+            user_class,
             max_message_size,
+            rapid_commit,
+            ipv6_only,
         }
     }
 
@@ -47,14 +69,20 @@ impl MessageBuilder {
 
         options.dhcp_message_type = Some(MessageType::DhcpDiscover);
         options.dhcp_max_message_size = self.max_message_size;
-        options.parameter_list = Some(Self::parameter_list());
+        if self.rapid_commit {
+            options.rapid_commit = Some(());
+        }
+        options.parameter_list = Some(self.parameter_list());
+        options.forcerenew_nonce_capable = Some(vec![AUTHENTICATION_ALGORITHM_HMAC_MD5]);
+        options.client_fqdn = self.client_fqdn.to_owned();
+        options.class_id = self.class_id.to_owned();
+        options.user_class = self.user_class.to_owned();
         options.address_request = address_request;
         options.address_time = address_time;
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -66,7 +94,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -89,14 +117,17 @@ impl MessageBuilder {
         options.dhcp_message_type = Some(MessageType::DhcpRequest);
         options.dhcp_max_message_size = self.max_message_size;
         options.dhcp_server_id = Some(dhcp_server_id);
-        options.parameter_list = Some(Self::parameter_list());
+        options.parameter_list = Some(self.parameter_list());
+        options.forcerenew_nonce_capable = Some(vec![AUTHENTICATION_ALGORITHM_HMAC_MD5]);
+        options.client_fqdn = self.client_fqdn.to_owned();
+        options.class_id = self.class_id.to_owned();
+        options.user_class = self.user_class.to_owned();
         options.address_request = Some(address_request);
         options.address_time = address_time;
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -108,7 +139,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -129,14 +160,17 @@ impl MessageBuilder {
 
         options.dhcp_message_type = Some(MessageType::DhcpRequest);
         options.dhcp_max_message_size = self.max_message_size;
-        options.parameter_list = Some(Self::parameter_list());
+        options.parameter_list = Some(self.parameter_list());
+        options.forcerenew_nonce_capable = Some(vec![AUTHENTICATION_ALGORITHM_HMAC_MD5]);
+        options.client_fqdn = self.client_fqdn.to_owned();
+        options.class_id = self.class_id.to_owned();
+        options.user_class = self.user_class.to_owned();
         options.address_request = Some(address_request);
         options.address_time = address_time;
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -148,7 +182,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -169,13 +203,16 @@ impl MessageBuilder {
 
         options.dhcp_message_type = Some(MessageType::DhcpRequest);
         options.dhcp_max_message_size = self.max_message_size;
-        options.parameter_list = Some(Self::parameter_list());
+        options.parameter_list = Some(self.parameter_list());
+        options.forcerenew_nonce_capable = Some(vec![AUTHENTICATION_ALGORITHM_HMAC_MD5]);
+        options.client_fqdn = self.client_fqdn.to_owned();
+        options.class_id = self.class_id.to_owned();
+        options.user_class = self.user_class.to_owned();
         options.address_time = address_time;
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -187,7 +224,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -207,12 +244,13 @@ impl MessageBuilder {
 
         options.dhcp_message_type = Some(MessageType::DhcpInform);
         options.dhcp_max_message_size = self.max_message_size;
-        options.parameter_list = Some(Self::parameter_list());
+        options.parameter_list = Some(self.parameter_list());
+        options.class_id = self.class_id.to_owned();
+        options.user_class = self.user_class.to_owned();
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -224,7 +262,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -233,6 +271,8 @@ impl MessageBuilder {
     }
 
     /// Creates a general `DHCPRELEASE` message.
+    ///
+    /// The hostname is not appended, since RFC 2131 table 5 forbids it in `DHCPRELEASE`.
     pub fn release(
         &self,
         transaction_id: u32,
@@ -241,7 +281,7 @@ impl MessageBuilder {
         dhcp_message: Option<String>,
     ) -> Message {
         let mut options = Options::default();
-        self.append_default_options(&mut options);
+        options.client_id = Some(self.client_id.to_owned());
 
         options.dhcp_message_type = Some(MessageType::DhcpRelease);
         options.dhcp_server_id = Some(dhcp_server_id);
@@ -249,8 +289,7 @@ impl MessageBuilder {
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -262,7 +301,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -271,6 +310,8 @@ impl MessageBuilder {
     }
 
     /// Creates a general `DHCPDECLINE` message.
+    ///
+    /// The hostname is not appended, since RFC 2131 table 5 forbids it in `DHCPDECLINE`.
     pub fn decline(
         &self,
         transaction_id: u32,
@@ -279,7 +320,7 @@ impl MessageBuilder {
         dhcp_message: Option<String>,
     ) -> Message {
         let mut options = Options::default();
-        self.append_default_options(&mut options);
+        options.client_id = Some(self.client_id.to_owned());
 
         options.dhcp_message_type = Some(MessageType::DhcpDecline);
         options.dhcp_server_id = Some(dhcp_server_id);
@@ -288,8 +329,7 @@ impl MessageBuilder {
 
         Message {
             operation_code: OperationCode::BootRequest,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: EUI48LEN as u8,
+            hardware_type: self.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id,
@@ -301,7 +341,7 @@ impl MessageBuilder {
             server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
             gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
 
-            client_hardware_address: self.client_hardware_address.to_owned(),
+            client_hardware_address: self.client_hardware_address,
             server_name: Default::default(),
             boot_filename: Default::default(),
 
@@ -314,10 +354,14 @@ impl MessageBuilder {
         options.client_id = Some(self.client_id.to_owned());
     }
 
-    fn parameter_list() -> Vec<u8> {
-        vec![
+    fn parameter_list(&self) -> Vec<u8> {
+        let mut parameter_list = vec![
             OptionTag::SubnetMask as u8,
             OptionTag::DomainNameServers as u8,
+            OptionTag::DomainSearch as u8,
+            OptionTag::SipServers as u8,
+            OptionTag::CaptivePortal as u8,
+            OptionTag::VendorSpecific as u8,
             /*
             RFC 3442
             DHCP clients that support this option and send a parameter request
@@ -330,6 +374,11 @@ impl MessageBuilder {
             OptionTag::ClasslessStaticRoutes as u8,
             OptionTag::Routers as u8,
             OptionTag::StaticRoutes as u8,
-        ]
+        ];
+        // IPv6-only capable clients request the IPv6-Only Preferred option (RFC 8925 §3.2)
+        if self.ipv6_only {
+            parameter_list.push(OptionTag::Ipv6OnlyPreferred as u8);
+        }
+        parameter_list
     }
 }
@@ -2,12 +2,15 @@
 
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
-use eui48::MacAddress;
 use futures::StartSend;
 use hostname;
 use tokio::{io, prelude::*};
 
-use dhcp_protocol::{Message, MessageType, DHCP_PORT_SERVER};
+use dhcp_protocol::{
+    Authentication, AuthenticationMode, ClientFqdn, DomainName, HardwareAddress, HardwareType,
+    KeyStore, Message, MessageType, OptionTag, ReplayDetection, SipServers, UserClass,
+    VendorInformation, VendorRegistry, DHCP_PORT_SERVER,
+};
 
 use builder::MessageBuilder;
 use state::{DhcpState, State};
@@ -18,6 +21,10 @@ struct RequestOptions {
     address_request: Option<Ipv4Addr>,
     /// Explicit lease time request.
     address_time: Option<u32>,
+    /// Explicit `DHCPDISCOVER`-`DHCPACK` exchange request.
+    rapid_commit: bool,
+    /// Whether the client stops DHCPv4 if the server prefers IPv6-only mode.
+    ipv6_only: bool,
 }
 
 /// The `Client` future result type.
@@ -28,8 +35,15 @@ pub struct Configuration {
     pub subnet_mask: Option<Ipv4Addr>,
     pub routers: Option<Vec<Ipv4Addr>>,
     pub domain_name_servers: Option<Vec<Ipv4Addr>>,
+    pub domain_search: Option<Vec<DomainName>>,
+    pub sip_servers: Option<SipServers>,
+    pub captive_portal: Option<String>,
     pub static_routes: Option<Vec<(Ipv4Addr, Ipv4Addr)>>,
     pub classless_static_routes: Option<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>>,
+    pub vendor_specific: Option<Vec<u8>>,
+    /// Set if the server prefers the client to operate in IPv6-only mode (RFC 8925).
+    /// No address is bound then and DHCPv4 is stopped for this number of seconds.
+    pub ipv6_only_wait: Option<u32>,
 }
 
 impl Configuration {
@@ -42,9 +56,9 @@ impl Configuration {
         option and a Static Routes option, the DHCP client MUST ignore the
         Static Routes option.
         */
-        if response.options.classless_static_routes.is_some() {
-            response.options.routers = None;
-            response.options.static_routes = None;
+        if response.options.contains(OptionTag::ClasslessStaticRoutes) {
+            response.options.remove(OptionTag::Routers);
+            response.options.remove(OptionTag::StaticRoutes);
         }
 
         Configuration {
@@ -53,8 +67,39 @@ impl Configuration {
             subnet_mask: response.options.subnet_mask,
             routers: response.options.routers,
             domain_name_servers: response.options.domain_name_servers,
+            domain_search: response.options.domain_search,
+            sip_servers: response.options.sip_servers,
             static_routes: response.options.static_routes,
             classless_static_routes: response.options.classless_static_routes,
+            vendor_specific: response.options.vendor_specific,
+            captive_portal: response.options.captive_portal,
+            ipv6_only_wait: None,
+        }
+    }
+
+    /// Reports the IPv6-only mode instead of the offered address.
+    fn ipv6_only(response: Message, ipv6_only_wait: u32) -> Self {
+        let mut configuration = Self::from_response(response);
+        configuration.your_ip_address = Ipv4Addr::new(0, 0, 0, 0);
+        configuration.ipv6_only_wait = Some(ipv6_only_wait);
+        configuration
+    }
+
+    /// Decodes the vendor-specific information sent for the client vendor class identifier.
+    ///
+    /// Returns `None` if the server has not sent the information
+    /// or the registry has no decoder for the vendor class.
+    ///
+    /// # Errors
+    /// `io::Error` if the information is invalid.
+    pub fn vendor_information(
+        &self,
+        registry: &VendorRegistry,
+        class_id: &[u8],
+    ) -> io::Result<Option<Box<dyn VendorInformation>>> {
+        match self.vendor_specific {
+            Some(ref vendor_specific) => registry.decode(class_id, vendor_specific),
+            None => Ok(None),
         }
     }
 }
@@ -74,28 +119,42 @@ pub enum Command {
     },
 }
 
-type DhcpStreamItem = (SocketAddr, Message);
-type DhcpSinkItem = (SocketAddr, (Message, Option<u16>));
+type DhcpStreamItem = (SocketAddr, (Message, Vec<u8>));
+type DhcpSinkItem = (SocketAddr, (Message, Option<u16>, Option<Vec<u8>>));
 
-/// The struct implementing the `Future` trait.
-pub struct Client<I, O>
+/// Most of the client options are optional, so the builder pattern is used.
+pub struct ClientBuilder<I, O>
 where
     I: Stream<Item = DhcpStreamItem, Error = io::Error> + Send + Sync,
     O: Sink<SinkItem = DhcpSinkItem, SinkError = io::Error> + Send + Sync,
 {
     stream: I,
     sink: O,
-    builder: MessageBuilder,
-    state: State,
-    options: RequestOptions,
+    hardware_type: HardwareType,
+    client_hardware_address: HardwareAddress,
+    client_id: Option<Vec<u8>>,
+    hostname: Option<String>,
+    client_fqdn: Option<ClientFqdn>,
+    class_id: Option<Vec<u8>>,
+    user_class: Option<UserClass>,
+    server_address: Option<Ipv4Addr>,
+    client_address: Option<Ipv4Addr>,
+    address_request: Option<Ipv4Addr>,
+    address_time: Option<u32>,
+    max_message_size: Option<u16>,
+    broadcast: bool,
+    rapid_commit: bool,
+    ipv6_only: bool,
+    authentication_mode: AuthenticationMode,
+    authentication_keys: KeyStore,
 }
 
-impl<I, O> Client<I, O>
+impl<I, O> ClientBuilder<I, O>
 where
     I: Stream<Item = DhcpStreamItem, Error = io::Error> + Send + Sync,
     O: Sink<SinkItem = DhcpSinkItem, SinkError = io::Error> + Send + Sync,
 {
-    /// Creates a client future
+    /// Builds a client future.
     ///
     /// * `stream`
     /// The external socket `Stream` part.
@@ -103,81 +162,233 @@ where
     /// * `sink`
     /// The external socket `Sink` part.
     ///
+    /// * `hardware_type`
+    /// The client hardware type (usually `Ethernet`).
+    ///
     /// * `client_hardware_address`
-    /// The mandatory client MAC address.
+    /// The mandatory client hardware address.
+    /// It is empty for hardware types whose addresses do not fit the message like InfiniBand.
+    ///
+    pub fn new(
+        stream: I,
+        sink: O,
+        hardware_type: HardwareType,
+        client_hardware_address: HardwareAddress,
+    ) -> Self {
+        ClientBuilder {
+            stream,
+            sink,
+            hardware_type,
+            client_hardware_address,
+            client_id: None,
+            hostname: None,
+            client_fqdn: None,
+            class_id: None,
+            user_class: None,
+            server_address: None,
+            client_address: None,
+            address_request: None,
+            address_time: None,
+            max_message_size: None,
+            broadcast: false,
+            rapid_commit: false,
+            ipv6_only: false,
+            authentication_mode: AuthenticationMode::Ignore,
+            authentication_keys: KeyStore::new(),
+        }
+    }
+
+    /// Sets the client identifier.
+    /// It must be set if the hardware address is empty (RFC 4390).
     ///
-    /// * `client_id`
-    /// The optional client identifier.
-    /// If `None`, is defaulted to the 6-byte MAC address.
+    /// If not called during building, is defaulted to the hardware address bytes.
+    pub fn with_client_id(&mut self, client_id: Vec<u8>) -> &mut Self {
+        self.client_id = Some(client_id);
+        self
+    }
+
+    /// Sets the client hostname.
     ///
-    /// * `hostname`
-    /// May be explicitly set by a client user.
-    /// Otherwise it is defaulted to the machine hostname.
+    /// If not called during building, is defaulted to the machine hostname.
     /// If the hostname cannot be get, remains unset.
+    pub fn with_hostname(&mut self, hostname: String) -> &mut Self {
+        self.hostname = Some(hostname);
+        self
+    }
+
+    /// Sets the Client FQDN option value.
+    /// Set it if you want the server to update DNS with your domain name.
     ///
-    /// * `server_address`
-    /// The DHCP server address.
-    /// Set it if your know the server address.
-    /// If set, the client communicates with the server using unicast.
-    /// Otherwise, broadcasting to 255.255.255.255 is used.
+    /// If not called during building, the option is not sent.
+    pub fn with_client_fqdn(&mut self, client_fqdn: ClientFqdn) -> &mut Self {
+        self.client_fqdn = Some(client_fqdn);
+        self
+    }
+
+    /// Sets the vendor class identifier.
+    /// Set it if you want the server to send you vendor-specific information.
     ///
-    /// * `client_address`
-    /// The previous client address.
-    /// Set it if you want to reacquire your previous network address.
-    /// If set, the client is started in INIT-REBOOT state.
-    /// If unset, the client is started in INIT state.
+    /// If not called during building, the option is not sent.
+    pub fn with_class_id(&mut self, class_id: Vec<u8>) -> &mut Self {
+        self.class_id = Some(class_id);
+        self
+    }
+
+    /// Sets the user classes (RFC 3004).
+    /// Set it if you want the server to choose your configuration by them.
     ///
-    /// * `address_request`
-    /// The requested network address.
-    /// Set it if you want to request a specific network address.
-    /// If not set, a server will give you either
-    /// your current or previous address, or an address from its dynamic pool.
+    /// If not called during building, the option is not sent.
+    pub fn with_user_class(&mut self, user_class: UserClass) -> &mut Self {
+        self.user_class = Some(user_class);
+        self
+    }
+
+    /// Sets the DHCP server address, so the client communicates with the server using unicast.
+    ///
+    /// If not called during building, broadcasting to 255.255.255.255 is used.
+    pub fn with_server_address(&mut self, server_address: Ipv4Addr) -> &mut Self {
+        self.server_address = Some(server_address);
+        self
+    }
+
+    /// Sets the previous client address, so the client reacquires it in INIT-REBOOT state.
     ///
-    /// * `address_time`
-    /// The requested lease time.
-    /// If not set, a server will choose the lease time by itself.
+    /// If not called during building, the client is started in INIT state.
+    pub fn with_client_address(&mut self, client_address: Ipv4Addr) -> &mut Self {
+        self.client_address = Some(client_address);
+        self
+    }
+
+    /// Sets the requested network address.
+    ///
+    /// If not called during building, a server will give you either
+    /// your current or previous address, or an address from its dynamic pool.
+    pub fn with_address_request(&mut self, address_request: Ipv4Addr) -> &mut Self {
+        self.address_request = Some(address_request);
+        self
+    }
+
+    /// Sets the requested lease time.
     /// The server may lease the address for different amount of time if it decides so.
     ///
-    /// * `max_message_size`
-    /// The maximum DHCP message size.
+    /// If not called during building, a server will choose the lease time by itself.
+    pub fn with_address_time(&mut self, address_time: u32) -> &mut Self {
+        self.address_time = Some(address_time);
+        self
+    }
+
+    /// Sets the maximum DHCP message size.
     ///
-    /// * `broadcast`
-    /// If true, the client will ask DHCP server to use broadcasting.
+    /// If not called during building, the option is not sent.
+    pub fn with_max_message_size(&mut self, max_message_size: u16) -> &mut Self {
+        self.max_message_size = Some(max_message_size);
+        self
+    }
+
+    /// Sets whether the client asks DHCP servers to use broadcasting.
     ///
-    pub fn new(
-        stream: I,
-        sink: O,
-        client_hardware_address: MacAddress,
-        client_id: Option<Vec<u8>>,
-        hostname: Option<String>,
-        server_address: Option<Ipv4Addr>,
-        client_address: Option<Ipv4Addr>,
-        address_request: Option<Ipv4Addr>,
-        address_time: Option<u32>,
-        max_message_size: Option<u16>,
-        broadcast: bool,
-    ) -> Self {
-        let hostname: Option<String> = if hostname.is_none() {
+    /// If not called during building, broadcasting is only asked for
+    /// if the hardware address is empty.
+    pub fn with_broadcast(&mut self, broadcast: bool) -> &mut Self {
+        self.broadcast = broadcast;
+        self
+    }
+
+    /// Sets whether the client asks DHCP servers to commit an address right
+    /// after `DHCPDISCOVER` (RFC 4039). Servers not supporting it still reply
+    /// with `DHCPOFFER`, so the full exchange is performed with them.
+    ///
+    /// If not called during building, the full exchange is always performed.
+    pub fn with_rapid_commit(&mut self, rapid_commit: bool) -> &mut Self {
+        self.rapid_commit = rapid_commit;
+        self
+    }
+
+    /// Sets whether the client is IPv6-only capable and requests the IPv6-Only Preferred
+    /// option (RFC 8925). If a server returns it, no address is bound, but a `Configuration`
+    /// with `ipv6_only_wait` is yielded and DHCPv4 is stopped for that number of seconds.
+    ///
+    /// If not called during building, the option is not requested.
+    pub fn with_ipv6_only(&mut self, ipv6_only: bool) -> &mut Self {
+        self.ipv6_only = ipv6_only;
+        self
+    }
+
+    /// Sets the delayed authentication (RFC 3118) mode and the keys shared with servers.
+    ///
+    /// If the mode is not `Ignore`, the client requests the authentication from servers
+    /// and signs its messages with the key chosen by the server.
+    ///
+    /// If not called during building, the authentication option is ignored.
+    pub fn with_authentication(&mut self, mode: AuthenticationMode, keys: KeyStore) -> &mut Self {
+        self.authentication_mode = mode;
+        self.authentication_keys = keys;
+        self
+    }
+
+    /// Consumes the builder and returns the built client.
+    pub fn finish(self) -> Client<I, O> {
+        Client::new(self)
+    }
+}
+
+/// The struct implementing the `Future` trait.
+pub struct Client<I, O>
+where
+    I: Stream<Item = DhcpStreamItem, Error = io::Error> + Send + Sync,
+    O: Sink<SinkItem = DhcpSinkItem, SinkError = io::Error> + Send + Sync,
+{
+    stream: I,
+    sink: O,
+    builder: MessageBuilder,
+    state: State,
+    options: RequestOptions,
+    /// How the delayed authentication is treated.
+    authentication_mode: AuthenticationMode,
+    /// The delayed authentication shared keys.
+    authentication_keys: KeyStore,
+    /// The last replay detection values of the servers by their secret IDs.
+    replay_detection: ReplayDetection<u32>,
+}
+
+impl<I, O> Client<I, O>
+where
+    I: Stream<Item = DhcpStreamItem, Error = io::Error> + Send + Sync,
+    O: Sink<SinkItem = DhcpSinkItem, SinkError = io::Error> + Send + Sync,
+{
+    /// Creates a client future from the builder.
+    fn new(builder: ClientBuilder<I, O>) -> Self {
+        let hostname: Option<String> = if builder.hostname.is_none() {
             hostname::get_hostname()
         } else {
             None
         };
 
-        let client_id = client_id.unwrap_or(client_hardware_address.as_bytes().to_vec());
+        let client_id = builder
+            .client_id
+            .unwrap_or(builder.client_hardware_address.as_bytes().to_vec());
 
-        let builder = MessageBuilder::new(
-            client_hardware_address,
+        let message_builder = MessageBuilder::new(
+            builder.hardware_type,
+            builder.client_hardware_address,
             client_id,
             hostname,
-            max_message_size,
+            builder.client_fqdn,
+            builder.class_id,
+            builder.user_class,
+            builder.max_message_size,
+            builder.rapid_commit,
+            builder.ipv6_only,
         );
 
         let mut options = RequestOptions {
-            address_request,
-            address_time,
+            address_request: builder.address_request,
+            address_time: builder.address_time,
+            rapid_commit: builder.rapid_commit,
+            ipv6_only: builder.ipv6_only,
         };
 
-        let dhcp_state = match client_address {
+        let dhcp_state = match builder.client_address {
             Some(ip) => {
                 options.address_request = Some(ip);
                 DhcpState::InitReboot
@@ -185,14 +396,19 @@ where
             None => DhcpState::Init,
         };
 
-        let state = State::new(dhcp_state, server_address, broadcast);
+        // servers cannot unicast to clients without hardware addresses (RFC 4390)
+        let broadcast = builder.broadcast || builder.client_hardware_address.is_empty();
+        let state = State::new(dhcp_state, builder.server_address, broadcast);
 
         Client {
-            stream,
-            sink,
-            builder,
+            stream: builder.stream,
+            sink: builder.sink,
+            builder: message_builder,
             state,
             options,
+            authentication_mode: builder.authentication_mode,
+            authentication_keys: builder.authentication_keys,
+            replay_detection: ReplayDetection::new(),
         }
     }
 
@@ -222,13 +438,36 @@ where
         }
     }
 
+    /// Sets the delayed authentication option of a request and returns the key
+    /// to sign the request with or only requests the authentication
+    /// if the server has not chosen a key yet.
+    fn authenticate(&self, request: &mut Message) -> Option<Vec<u8>> {
+        if let AuthenticationMode::Ignore = self.authentication_mode {
+            return None;
+        }
+        let replay_detection = Authentication::monotonic_counter();
+
+        if let Some(secret_id) = self.state.secret_id() {
+            if let Some(key) = self.authentication_keys.get(secret_id) {
+                request.options.authentication = Some(Authentication::delayed_hmac_md5(
+                    replay_detection,
+                    secret_id,
+                ));
+                return Some(key.to_vec());
+            }
+        }
+        request.options.authentication = Some(Authentication::delayed_request(replay_detection));
+        None
+    }
+
     /// Sends a request.
-    fn send_request(&mut self, request: Message) -> io::Result<()> {
+    fn send_request(&mut self, mut request: Message) -> io::Result<()> {
+        let key = self.authenticate(&mut request);
         let destination = self.destination();
         log_send!(request, destination);
 
         let destination = SocketAddr::new(IpAddr::V4(destination), DHCP_PORT_SERVER);
-        start_send!(self.sink, destination, (request, None));
+        start_send!(self.sink, destination, (request, None, key));
         Ok(())
     }
 }
@@ -325,7 +564,7 @@ where
                         .transcend(current, DhcpState::SelectingSent, None);
                 }
                 current @ DhcpState::SelectingSent => {
-                    let (addr, response) = match self.stream.poll() {
+                    let (addr, (response, packet)) = match self.stream.poll() {
                         Ok(Async::Ready(Some(data))) => data,
                         Ok(Async::Ready(None)) => {
                             warn!("Received an invalid packet");
@@ -345,7 +584,31 @@ where
                     let dhcp_message_type = validate!(response, addr);
                     log_receive!(response, addr.ip());
                     check_xid!(self.state.xid(), response.transaction_id);
+                    check_authentication!(
+                        response,
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        self.replay_detection,
+                        addr
+                    );
+
+                    // a DHCPACK with the Rapid Commit option is processed immediately (RFC 4039)
+                    if let MessageType::DhcpAck = dhcp_message_type {
+                        if !self.options.rapid_commit || response.options.rapid_commit.is_none() {
+                            warn!("Got an unexpected DHCP message type {}", dhcp_message_type);
+                            continue;
+                        }
+                        check_ipv6_only!(self, current, response);
+                        self.state
+                            .transcend(current, DhcpState::Bound, Some(&response));
+                        return Ok(Async::Ready(Some(Configuration::from_response(response))));
+                    }
+
                     check_message_type!(dhcp_message_type, MessageType::DhcpOffer);
+                    // the offered address is not requested if the server prefers IPv6-only
+                    // mode, and DHCPv4 is stopped for V6ONLY_WAIT seconds (RFC 8925 §3.2)
+                    check_ipv6_only!(self, current, response);
                     self.state
                         .transcend(current, DhcpState::Requesting, Some(&response));
                 }
@@ -369,7 +632,7 @@ where
                         .transcend(current, DhcpState::RequestingSent, None);
                 }
                 current @ DhcpState::RequestingSent => {
-                    let (addr, response) = match self.stream.poll() {
+                    let (addr, (response, packet)) = match self.stream.poll() {
                         Ok(Async::Ready(Some(data))) => data,
                         Ok(Async::Ready(None)) => {
                             warn!("Received an invalid packet");
@@ -393,6 +656,14 @@ where
                     let dhcp_message_type = validate!(response, addr);
                     log_receive!(response, addr.ip());
                     check_xid!(self.state.xid(), response.transaction_id);
+                    check_authentication!(
+                        response,
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        self.replay_detection,
+                        addr
+                    );
 
                     match dhcp_message_type {
                         MessageType::DhcpNak => {
@@ -442,7 +713,7 @@ where
                         .transcend(current, DhcpState::RebootingSent, None);
                 }
                 current @ DhcpState::RebootingSent => {
-                    let (addr, response) = match self.stream.poll() {
+                    let (addr, (response, packet)) = match self.stream.poll() {
                         Ok(Async::Ready(Some(data))) => data,
                         Ok(Async::Ready(None)) => {
                             warn!("Received an invalid packet");
@@ -466,6 +737,14 @@ where
                     let dhcp_message_type = validate!(response, addr);
                     log_receive!(response, addr.ip());
                     check_xid!(self.state.xid(), response.transaction_id);
+                    check_authentication!(
+                        response,
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        self.replay_detection,
+                        addr
+                    );
 
                     match dhcp_message_type {
                         MessageType::DhcpNak => {
@@ -480,6 +759,9 @@ where
                         }
                     }
 
+                    // the DHCPACK with the IPv6-Only Preferred option also stops DHCPv4
+                    // for V6ONLY_WAIT seconds in INIT-REBOOT state (RFC 8925 §3.2)
+                    check_ipv6_only!(self, current, response);
                     self.state
                         .transcend(current, DhcpState::Bound, Some(&response));
                     return Ok(Async::Ready(Some(Configuration::from_response(response))));
@@ -497,6 +779,31 @@ where
                     message.
                     */
 
+                    // an authenticated DHCPFORCERENEW starts renewing at once (RFC 3203)
+
+                    match self.stream.poll() {
+                        Ok(Async::Ready(Some((addr, (response, packet))))) => {
+                            let dhcp_message_type = validate!(response, addr);
+                            log_receive!(response, addr.ip());
+                            check_message_type!(dhcp_message_type, MessageType::DhcpForceRenew);
+                            if !self.state.authenticate_forcerenew(&response, &packet) {
+                                warn!("Got an unauthenticated DHCPFORCERENEW from {}", addr);
+                                continue;
+                            }
+                            self.state.transcend(current, DhcpState::Renewing, None);
+                            continue;
+                        }
+                        Ok(Async::Ready(None)) => {
+                            warn!("Received an invalid packet");
+                            continue;
+                        }
+                        Ok(Async::NotReady) => {}
+                        Err(error) => {
+                            warn!("Socket error: {}", error);
+                            continue;
+                        }
+                    }
+
                     poll_delay!(self.state.timer_renewal);
                     self.state.transcend(current, DhcpState::Renewing, None);
                 }
@@ -521,7 +828,7 @@ where
                     self.state.transcend(current, DhcpState::RenewingSent, None);
                 }
                 current @ DhcpState::RenewingSent => {
-                    let (addr, response) = match self.stream.poll() {
+                    let (addr, (response, packet)) = match self.stream.poll() {
                         Ok(Async::Ready(Some(data))) => data,
                         Ok(Async::Ready(None)) => {
                             warn!("Received an invalid packet");
@@ -545,6 +852,14 @@ where
                     let dhcp_message_type = validate!(response, addr);
                     log_receive!(response, addr.ip());
                     check_xid!(self.state.xid(), response.transaction_id);
+                    check_authentication!(
+                        response,
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        self.replay_detection,
+                        addr
+                    );
                     check_message_type!(dhcp_message_type, MessageType::DhcpAck);
 
                     self.state
@@ -576,7 +891,7 @@ where
                         .transcend(current, DhcpState::RebindingSent, None);
                 }
                 current @ DhcpState::RebindingSent => {
-                    let (addr, response) = match self.stream.poll() {
+                    let (addr, (response, packet)) = match self.stream.poll() {
                         Ok(Async::Ready(Some(data))) => data,
                         Ok(Async::Ready(None)) => {
                             warn!("Received an invalid packet");
@@ -600,12 +915,27 @@ where
                     let dhcp_message_type = validate!(response, addr);
                     log_receive!(response, addr.ip());
                     check_xid!(self.state.xid(), response.transaction_id);
+                    check_authentication!(
+                        response,
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        self.replay_detection,
+                        addr
+                    );
                     check_message_type!(dhcp_message_type, MessageType::DhcpAck);
 
                     self.state
                         .transcend(current, DhcpState::Bound, Some(&response));
                     return Ok(Async::Ready(Some(Configuration::from_response(response))));
                 }
+
+                current @ DhcpState::Ipv6Only => {
+                    // DHCPv4 is restarted after V6ONLY_WAIT seconds (RFC 8925 §3.2)
+
+                    poll_delay!(self.state.timer_ipv6_only);
+                    self.state.transcend(current, DhcpState::Init, None);
+                }
             }
         }
     }
@@ -624,7 +954,7 @@ where
         &mut self,
         command: Self::SinkItem,
     ) -> StartSend<Self::SinkItem, Self::SinkError> {
-        let (request, destination) = match command {
+        let (mut request, destination) = match command {
             Command::Release { ref message } => {
                 let dhcp_server_id = match self.state.dhcp_server_id() {
                     Some(dhcp_server_id) => dhcp_server_id,
@@ -684,8 +1014,9 @@ where
             }
         };
 
+        let key = self.authenticate(&mut request);
         log_send!(request, destination);
-        match self.sink.start_send((destination, (request, None))) {
+        match self.sink.start_send((destination, (request, None, key))) {
             Ok(AsyncSink::Ready) => Ok(AsyncSink::Ready),
             Ok(AsyncSink::NotReady(_item)) => Ok(AsyncSink::NotReady(command)),
             Err(error) => Err(error),
@@ -702,3 +1033,269 @@ where
         self.poll_complete()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    use futures::{future, sink::SinkMapErr, stream::MapErr, sync::mpsc};
+    use tokio::{io, prelude::*, runtime::current_thread};
+
+    use dhcp_protocol::*;
+
+    use super::{Client, ClientBuilder, Configuration, DhcpSinkItem, DhcpStreamItem};
+    use state::DhcpState;
+
+    type TestClient = Client<
+        MapErr<mpsc::UnboundedReceiver<DhcpStreamItem>, fn(()) -> io::Error>,
+        SinkMapErr<
+            mpsc::UnboundedSender<DhcpSinkItem>,
+            fn(mpsc::SendError<DhcpSinkItem>) -> io::Error,
+        >,
+    >;
+
+    /// The server end of the channels the client is connected to.
+    struct Server {
+        responses: mpsc::UnboundedSender<DhcpStreamItem>,
+        requests: mpsc::UnboundedReceiver<DhcpSinkItem>,
+    }
+
+    impl Server {
+        fn address() -> Ipv4Addr {
+            Ipv4Addr::new(192, 168, 0, 2)
+        }
+
+        /// Takes the request the client has sent.
+        fn request(&mut self) -> Message {
+            match self.requests.poll() {
+                Ok(Async::Ready(Some((_, (request, _, _))))) => request,
+                _ => panic!("The client has not sent a request"),
+            }
+        }
+
+        /// Sends the response along with its packet signed with the key if it is specified.
+        fn send(&mut self, response: Message, key: Option<&[u8]>) {
+            let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+            let amount = match key {
+                Some(key) => response.to_bytes_signed(&mut buffer, None, key).unwrap(),
+                None => response.to_bytes(&mut buffer, None).unwrap(),
+            };
+            buffer.truncate(amount);
+            let source = SocketAddr::new(IpAddr::V4(Self::address()), DHCP_PORT_SERVER);
+            self.responses
+                .unbounded_send((source, (response, buffer)))
+                .unwrap();
+        }
+
+        fn respond(&mut self, response: Message) {
+            self.send(response, None);
+        }
+
+        fn respond_signed(&mut self, response: Message, key: &[u8]) {
+            self.send(response, Some(key));
+        }
+
+        fn reply(request: &Message, dhcp_message_type: MessageType) -> Message {
+            let mut options = Options::default();
+            options.dhcp_message_type = Some(dhcp_message_type);
+            options.dhcp_server_id = Some(Self::address());
+            options.address_time = Some(3600);
+            Message {
+                operation_code: OperationCode::BootReply,
+                hardware_type: request.hardware_type,
+                hardware_options: 0,
+                transaction_id: request.transaction_id,
+                seconds: 0,
+                is_broadcast: request.is_broadcast,
+                client_ip_address: request.client_ip_address,
+                your_ip_address: Ipv4Addr::new(192, 168, 0, 100),
+                server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+                gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+                client_hardware_address: request.client_hardware_address,
+                server_name: Vec::new(),
+                boot_filename: Vec::new(),
+                options,
+            }
+        }
+    }
+
+    fn stream_error(_: ()) -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "The stream is closed")
+    }
+
+    fn sink_error(_: mpsc::SendError<DhcpSinkItem>) -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "The sink is closed")
+    }
+
+    fn client(rapid_commit: bool, ipv6_only: bool) -> (TestClient, Server) {
+        let (responses, stream) = mpsc::unbounded();
+        let (sink, requests) = mpsc::unbounded();
+        let mut builder = ClientBuilder::new(
+            stream.map_err(stream_error as fn(()) -> io::Error),
+            sink.sink_map_err(sink_error as fn(mpsc::SendError<DhcpSinkItem>) -> io::Error),
+            HardwareType::Ethernet,
+            HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]).unwrap(),
+        );
+        builder
+            .with_hostname("client".to_owned())
+            .with_rapid_commit(rapid_commit)
+            .with_ipv6_only(ipv6_only);
+        let client = builder.finish();
+        (
+            client,
+            Server {
+                responses,
+                requests,
+            },
+        )
+    }
+
+    /// Runs the test within a runtime providing the timers.
+    fn run<F: FnOnce()>(test: F) {
+        current_thread::block_on_all(future::lazy(|| {
+            test();
+            Ok::<(), io::Error>(())
+        }))
+        .unwrap();
+    }
+
+    fn configuration(client: &mut TestClient) -> Configuration {
+        match client.poll().unwrap() {
+            Async::Ready(Some(configuration)) => configuration,
+            _ => panic!("The client has not yielded a configuration"),
+        }
+    }
+
+    #[test]
+    fn ipv6_only_preferred_offer_stops_dhcpv4() {
+        run(|| {
+            let (mut client, mut server) = client(false, true);
+            assert!(client.poll().unwrap().is_not_ready());
+            let discover = server.request();
+            let parameter_list = discover.options.parameter_list.as_ref().unwrap();
+            assert!(parameter_list.contains(&(OptionTag::Ipv6OnlyPreferred as u8)));
+
+            let mut offer = Server::reply(&discover, MessageType::DhcpOffer);
+            offer.options.ipv6_only_preferred = Some(60);
+            server.respond(offer);
+
+            // the wait time is raised to the minimal one
+            let configuration = configuration(&mut client);
+            assert_eq!(configuration.ipv6_only_wait, Some(V6ONLY_WAIT_MINIMAL));
+            assert!(matches!(client.state.dhcp_state(), DhcpState::Ipv6Only));
+
+            // the offered address is not requested while waiting
+            assert!(client.poll().unwrap().is_not_ready());
+            assert!(server.requests.poll().unwrap().is_not_ready());
+        });
+    }
+
+    #[test]
+    fn ipv6_only_preferred_is_ignored_if_not_capable() {
+        run(|| {
+            let (mut client, mut server) = client(false, false);
+            assert!(client.poll().unwrap().is_not_ready());
+            let discover = server.request();
+            let parameter_list = discover.options.parameter_list.as_ref().unwrap();
+            assert!(!parameter_list.contains(&(OptionTag::Ipv6OnlyPreferred as u8)));
+
+            let mut offer = Server::reply(&discover, MessageType::DhcpOffer);
+            offer.options.ipv6_only_preferred = Some(60);
+            server.respond(offer);
+
+            assert!(client.poll().unwrap().is_not_ready());
+            let request = server.request();
+            assert_eq!(
+                request.options.dhcp_message_type,
+                Some(MessageType::DhcpRequest)
+            );
+            assert_eq!(
+                request.options.address_request,
+                Some(Ipv4Addr::new(192, 168, 0, 100))
+            );
+        });
+    }
+
+    #[test]
+    fn authenticated_forcerenew_moves_to_renewing() {
+        run(|| {
+            let (mut client, mut server) = client(false, false);
+            let key = vec![0x42; SIZE_HMAC_MD5];
+
+            assert!(client.poll().unwrap().is_not_ready());
+            let discover = server.request();
+            server.respond(Server::reply(&discover, MessageType::DhcpOffer));
+            assert!(client.poll().unwrap().is_not_ready());
+            let request = server.request();
+            let mut ack = Server::reply(&request, MessageType::DhcpAck);
+            ack.options.authentication = Some(Authentication::reconfigure_key(1, &key));
+            server.respond(ack);
+            configuration(&mut client);
+            assert!(matches!(client.state.dhcp_state(), DhcpState::Bound));
+
+            // a DHCPFORCERENEW signed with another key is discarded
+            let mut forcerenew = Server::reply(&request, MessageType::DhcpForceRenew);
+            forcerenew.options.address_time = None;
+            forcerenew.client_ip_address = Ipv4Addr::new(192, 168, 0, 100);
+            forcerenew.your_ip_address = Ipv4Addr::new(0, 0, 0, 0);
+            forcerenew.options.authentication = Some(Authentication::reconfigure_hmac_md5(2));
+            server.respond_signed(forcerenew.clone(), &[0x24; SIZE_HMAC_MD5]);
+            assert!(client.poll().unwrap().is_not_ready());
+            assert!(matches!(client.state.dhcp_state(), DhcpState::Bound));
+            assert!(server.requests.poll().unwrap().is_not_ready());
+
+            server.respond_signed(forcerenew, &key);
+            assert!(client.poll().unwrap().is_not_ready());
+            assert!(matches!(client.state.dhcp_state(), DhcpState::RenewingSent));
+            let renew = server.request();
+            assert_eq!(
+                renew.options.dhcp_message_type,
+                Some(MessageType::DhcpRequest)
+            );
+            assert_eq!(renew.client_ip_address, Ipv4Addr::new(192, 168, 0, 100));
+            assert!(renew.options.dhcp_server_id.is_none());
+        });
+    }
+
+    #[test]
+    fn rapid_commit_ack_binds_at_once() {
+        run(|| {
+            let (mut client, mut server) = client(true, false);
+            assert!(client.poll().unwrap().is_not_ready());
+            let discover = server.request();
+            assert!(discover.options.rapid_commit.is_some());
+
+            let mut ack = Server::reply(&discover, MessageType::DhcpAck);
+            ack.options.rapid_commit = Some(());
+            server.respond(ack);
+
+            let configuration = configuration(&mut client);
+            assert_eq!(
+                configuration.your_ip_address,
+                Ipv4Addr::new(192, 168, 0, 100)
+            );
+            assert!(matches!(client.state.dhcp_state(), DhcpState::Bound));
+            assert!(server.requests.poll().unwrap().is_not_ready());
+        });
+    }
+
+    #[test]
+    fn rapid_commit_ack_is_ignored_if_not_requested() {
+        run(|| {
+            let (mut client, mut server) = client(false, false);
+            assert!(client.poll().unwrap().is_not_ready());
+            let discover = server.request();
+            assert!(discover.options.rapid_commit.is_none());
+
+            let mut ack = Server::reply(&discover, MessageType::DhcpAck);
+            ack.options.rapid_commit = Some(());
+            server.respond(ack);
+
+            assert!(client.poll().unwrap().is_not_ready());
+            assert!(matches!(
+                client.state.dhcp_state(),
+                DhcpState::SelectingSent
+            ));
+        });
+    }
+}
@@ -22,4 +22,4 @@ extern crate rand;
 extern crate dhcp_framed;
 extern crate dhcp_protocol;
 
-pub use self::client::{Client, Command, Configuration};
+pub use self::client::{Client, ClientBuilder, Command, Configuration};
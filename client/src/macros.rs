@@ -59,6 +59,23 @@ macro_rules! validate (
     );
 );
 
+/// Just to move some code from the overwhelmed `poll` method.
+macro_rules! check_authentication (
+    ($message:expr, $packet:expr, $mode:expr, $keys:expr, $replay_detection:expr, $address:expr) => (
+        let secret_id = $message.options.authentication.as_ref()
+            .and_then(|authentication| authentication.delayed_secret_id());
+        let last = secret_id.and_then(|secret_id| $replay_detection.last(&secret_id));
+        if $message.is_authentic($packet, $mode, $keys, last) {
+            if let Some(secret_id) = secret_id {
+                $replay_detection.record(secret_id, &$message);
+            }
+        } else {
+            warn!("Got an unauthenticated response from {}", $address);
+            continue;
+        }
+    );
+);
+
 /// Just to move some code from the overwhelmed `poll` method.
 macro_rules! check_xid (
     ($yours:expr, $response:expr) => (
@@ -79,6 +96,19 @@ macro_rules! check_message_type (
     );
 );
 
+/// Stops DHCPv4 instead of binding if the client is IPv6-only capable and
+/// the server prefers IPv6-only mode (RFC 8925).
+macro_rules! check_ipv6_only (
+    ($client:expr, $current:expr, $response:expr) => (
+        if $client.options.ipv6_only && $response.options.ipv6_only_preferred.is_some() {
+            $client.state.transcend($current, DhcpState::Ipv6Only, Some(&$response));
+            let ipv6_only_wait = $client.state.ipv6_only_wait();
+            info!("Stopping DHCPv4 for {} seconds in IPv6-only mode", ipv6_only_wait);
+            return Ok(Async::Ready(Some(Configuration::ipv6_only($response, ipv6_only_wait))));
+        }
+    );
+);
+
 /// Just to move some code from the overwhelmed `poll` method.
 macro_rules! poll_delay (
     ($delay:expr) => (
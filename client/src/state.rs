@@ -1,7 +1,7 @@
 //! The DHCP client state module.
 
 use std::{
-    fmt,
+    cmp, fmt,
     net::Ipv4Addr,
     time::{Duration, Instant},
 };
@@ -10,7 +10,7 @@ use chrono::prelude::*;
 use rand;
 use tokio::timer::Delay;
 
-use dhcp_protocol::Message;
+use dhcp_protocol::{Message, V6ONLY_WAIT_MINIMAL};
 
 use backoff::Backoff;
 use forthon::Forthon;
@@ -30,6 +30,7 @@ const REBINDING_TIME_FACTOR: f64 = 0.875;
 ///
 /// The ones end with `Sent` are not described in RFC 2131 and
 /// are just substates to tell if the request has been sent or not.
+/// `Ipv6Only` is the RFC 8925 pause of DHCPv4 for `V6ONLY_WAIT` seconds.
 #[derive(Clone, Copy)]
 pub enum DhcpState {
     Init,
@@ -45,6 +46,7 @@ pub enum DhcpState {
     RenewingSent,
     Rebinding,
     RebindingSent,
+    Ipv6Only,
 }
 
 impl fmt::Display for DhcpState {
@@ -64,6 +66,7 @@ impl fmt::Display for DhcpState {
             RenewingSent => write!(f, "RENEWING_SENT"),
             Rebinding => write!(f, "REBINDING"),
             RebindingSent => write!(f, "REBINDING_SENT"),
+            Ipv6Only => write!(f, "IPV6_ONLY"),
         }
     }
 }
@@ -84,6 +87,12 @@ pub struct State {
     dhcp_server_id: Option<Ipv4Addr>,
     /// Recorded by the client from the `DhcpAck`.
     assigned_address: Ipv4Addr,
+    /// Recorded by the client from the `DhcpAck` to authenticate `DHCPFORCERENEW` messages.
+    reconfigure_key: Option<Vec<u8>>,
+    /// The last replay detection value received from the server.
+    replay_detection: u64,
+    /// The delayed authentication secret ID chosen by the server.
+    secret_id: Option<u32>,
 
     /// Recorded by the client right before sending the `DhcpRequest`.
     requested_at: i64,
@@ -93,6 +102,8 @@ pub struct State {
     rebinding_after: u64,
     /// Seconds from `REBINDING` till lease expiration.
     expiration_after: u64,
+    /// Seconds from `IPV6_ONLY` till `INIT` state (so called V6ONLY_WAIT in RFC 8925).
+    ipv6_only_wait: u32,
 
     /// DHCPOFFER receive deadline.
     pub timer_offer: Option<Backoff>,
@@ -104,6 +115,8 @@ pub struct State {
     pub timer_rebinding: Option<Forthon>,
     /// Lease expiration timer.
     pub timer_expiration: Option<Forthon>,
+    /// DHCPv4 restart timer after the server has preferred IPv6-only mode.
+    pub timer_ipv6_only: Option<Delay>,
 }
 
 impl State {
@@ -121,17 +134,22 @@ impl State {
             offered_time: 0u32,
             dhcp_server_id: server_address,
             assigned_address: Ipv4Addr::new(0, 0, 0, 0),
+            reconfigure_key: None,
+            replay_detection: 0u64,
+            secret_id: None,
 
             requested_at: 0i64,
             renewal_after: 0u64,
             rebinding_after: 0u64,
             expiration_after: 0u64,
+            ipv6_only_wait: 0u32,
 
             timer_offer: None,
             timer_ack: None,
             timer_renewal: None,
             timer_rebinding: None,
             timer_expiration: None,
+            timer_ipv6_only: None,
         }
     }
 
@@ -147,6 +165,7 @@ impl State {
             Init => match to {
                 next @ Selecting => {
                     self.set_dhcp_server_id(None);
+                    self.secret_id = None;
                     self.run_timer_offer();
                     self.dhcp_state = next;
                 }
@@ -154,6 +173,7 @@ impl State {
             },
             Selecting => match to {
                 next @ SelectingSent => {
+                    self.record_request_time();
                     self.dhcp_state = next;
                 }
                 _ => panic_state!(from, to),
@@ -165,9 +185,29 @@ impl State {
                     self.set_dhcp_server_id(Some(expect!(offer.options.dhcp_server_id)));
                     self.set_offered_address(offer.your_ip_address);
                     self.set_offered_time(expect!(offer.options.address_time));
+                    self.record_secret_id(offer);
                     self.run_timer_ack();
                     self.dhcp_state = next;
                 }
+                next @ Bound => {
+                    let ack = expect!(response);
+                    self.set_assigned_address(ack.your_ip_address);
+                    self.set_dhcp_server_id(Some(expect!(ack.options.dhcp_server_id)));
+                    self.set_times(
+                        ack.options.renewal_time,
+                        ack.options.rebinding_time,
+                        expect!(ack.options.address_time),
+                    );
+                    self.record_secret_id(ack);
+                    self.record_reconfigure_key(ack);
+                    self.run_timer_renewal();
+                    self.dhcp_state = next;
+                }
+                next @ Ipv6Only => {
+                    self.set_ipv6_only_wait(expect!(response));
+                    self.run_timer_ipv6_only();
+                    self.dhcp_state = next;
+                }
                 _ => panic_state!(from, to),
             },
             Requesting => match to {
@@ -188,6 +228,8 @@ impl State {
                         ack.options.rebinding_time,
                         expect!(ack.options.address_time),
                     );
+                    self.record_secret_id(ack);
+                    self.record_reconfigure_key(ack);
                     self.run_timer_renewal();
                     self.dhcp_state = next;
                 }
@@ -220,9 +262,16 @@ impl State {
                         ack.options.rebinding_time,
                         expect!(ack.options.address_time),
                     );
+                    self.record_secret_id(ack);
+                    self.record_reconfigure_key(ack);
                     self.run_timer_renewal();
                     self.dhcp_state = next;
                 }
+                next @ Ipv6Only => {
+                    self.set_ipv6_only_wait(expect!(response));
+                    self.run_timer_ipv6_only();
+                    self.dhcp_state = next;
+                }
                 _ => panic_state!(from, to),
             },
 
@@ -250,6 +299,8 @@ impl State {
                         ack.options.rebinding_time,
                         expect!(ack.options.address_time),
                     );
+                    self.record_secret_id(ack);
+                    self.record_reconfigure_key(ack);
                     self.run_timer_renewal();
                     self.dhcp_state = next;
                 }
@@ -279,12 +330,19 @@ impl State {
                         ack.options.rebinding_time,
                         expect!(ack.options.address_time),
                     );
+                    self.record_secret_id(ack);
+                    self.record_reconfigure_key(ack);
                     self.run_timer_renewal();
                     self.dhcp_state = next;
                 }
                 next @ Rebinding => self.dhcp_state = next,
                 _ => panic_state!(from, to),
             },
+
+            Ipv6Only => match to {
+                next @ Init => self.dhcp_state = next,
+                _ => panic_state!(from, to),
+            },
         }
     }
 
@@ -316,6 +374,40 @@ impl State {
         self.assigned_address.to_owned()
     }
 
+    pub fn secret_id(&self) -> Option<u32> {
+        self.secret_id
+    }
+
+    pub fn ipv6_only_wait(&self) -> u32 {
+        self.ipv6_only_wait
+    }
+
+    /// Checks if the `DHCPFORCERENEW` message is sent by the current server
+    /// and signed with the reconfigure key (RFC 6704).
+    ///
+    /// The replay detection value must be greater than the previous one.
+    pub fn authenticate_forcerenew(&mut self, message: &Message, packet: &[u8]) -> bool {
+        if message.options.dhcp_server_id.is_none()
+            || message.options.dhcp_server_id != self.dhcp_server_id
+        {
+            return false;
+        }
+        let replay_detection = match message.options.authentication {
+            Some(ref authentication) if authentication.replay_detection > self.replay_detection => {
+                authentication.replay_detection
+            }
+            _ => return false,
+        };
+        let is_valid = match self.reconfigure_key {
+            Some(ref key) => message.verify_reconfigure(packet, key),
+            None => false,
+        };
+        if is_valid {
+            self.replay_detection = replay_detection;
+        }
+        is_valid
+    }
+
     #[allow(dead_code)]
     fn set_broadcast(&mut self, value: bool) {
         self.is_broadcast = value;
@@ -337,6 +429,29 @@ impl State {
         self.assigned_address = value;
     }
 
+    /// The option value is raised to `V6ONLY_WAIT_MINIMAL` (RFC 8925 §3.2).
+    fn set_ipv6_only_wait(&mut self, response: &Message) {
+        let value = expect!(response.options.ipv6_only_preferred);
+        self.ipv6_only_wait = cmp::max(value, V6ONLY_WAIT_MINIMAL);
+    }
+
+    fn record_secret_id(&mut self, response: &Message) {
+        if let Some(ref authentication) = response.options.authentication {
+            if let Some(secret_id) = authentication.delayed_secret_id() {
+                self.secret_id = Some(secret_id);
+            }
+        }
+    }
+
+    fn record_reconfigure_key(&mut self, ack: &Message) {
+        if let Some(ref authentication) = ack.options.authentication {
+            if let Some(key) = authentication.reconfigure_key_value() {
+                self.reconfigure_key = Some(key.to_vec());
+                self.replay_detection = authentication.replay_detection;
+            }
+        }
+    }
+
     fn record_request_time(&mut self) {
         self.requested_at = Utc::now().timestamp();
     }
@@ -392,4 +507,10 @@ impl State {
             Duration::from_secs(FORTHON_TIMEOUT_MINIMAL),
         ));
     }
+
+    fn run_timer_ipv6_only(&mut self) {
+        self.timer_ipv6_only = Some(Delay::new(
+            Instant::now() + Duration::from_secs(u64::from(self.ipv6_only_wait)),
+        ));
+    }
 }
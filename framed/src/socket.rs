@@ -26,8 +26,10 @@ pub struct DhcpFramed {
     pending: Option<(SocketAddr, usize)>,
 }
 
-pub type DhcpStreamItem = (SocketAddr, Message);
-pub type DhcpSinkItem = (SocketAddr, (Message, Option<u16>));
+/// The message is received with the packet it has been decoded from to verify its digest.
+pub type DhcpStreamItem = (SocketAddr, (Message, Vec<u8>));
+/// The message is sent with its maximal size and signed with the key if it is specified.
+pub type DhcpSinkItem = (SocketAddr, (Message, Option<u16>, Option<Vec<u8>>));
 
 impl DhcpFramed {
     /// Binds to `addr` and returns a `Stream+Sink` UDP socket abstraction.
@@ -57,8 +59,9 @@ impl Stream for DhcpFramed {
     /// `io::Error` on a socket error.
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
         let (amount, addr) = try_ready!(self.socket.poll_recv_from(&mut self.buf_read));
-        match Message::from_bytes(&self.buf_read[..amount]) {
-            Ok(frame) => Ok(Async::Ready(Some((addr, frame)))),
+        let packet = &self.buf_read[..amount];
+        match Message::from_bytes(packet) {
+            Ok(frame) => Ok(Async::Ready(Some((addr, (frame, packet.to_vec()))))),
             Err(_) => Ok(Async::Ready(None)),
         }
     }
@@ -79,8 +82,11 @@ impl Sink for DhcpFramed {
             return Ok(AsyncSink::NotReady(item));
         }
 
-        let (addr, (message, max_size)) = item;
-        let amount = message.to_bytes(&mut self.buf_write, max_size)?;
+        let (addr, (message, max_size, key)) = item;
+        let amount = match key {
+            Some(ref key) => message.to_bytes_signed(&mut self.buf_write, max_size, key)?,
+            None => message.to_bytes(&mut self.buf_write, max_size)?,
+        };
         self.pending = Some((addr, amount));
 
         Ok(AsyncSink::Ready)
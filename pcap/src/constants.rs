@@ -0,0 +1,27 @@
+//! Capture file format constants.
+
+/// The classic pcap magic number of microsecond timestamps.
+pub const PCAP_MAGIC_MICROSECONDS: u32 = 0xa1b2_c3d4;
+/// The classic pcap magic number of nanosecond timestamps.
+pub const PCAP_MAGIC_NANOSECONDS: u32 = 0xa1b2_3c4d;
+pub const PCAP_VERSION_MAJOR: u16 = 2;
+pub const PCAP_VERSION_MINOR: u16 = 4;
+/// The default snapshot length of `libpcap`.
+pub const PCAP_SNAPLEN: u32 = 0x0004_0000;
+pub const SIZE_PCAP_HEADER: usize = 24;
+pub const SIZE_PCAP_RECORD_HEADER: usize = 16;
+
+pub const PCAPNG_BLOCK_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+pub const PCAPNG_BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+pub const PCAPNG_BLOCK_SIMPLE_PACKET: u32 = 0x0000_0003;
+pub const PCAPNG_BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+pub const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+pub const PCAPNG_OPTION_END: u16 = 0;
+pub const PCAPNG_OPTION_IF_TSRESOL: u16 = 9;
+/// The block type and the two block length fields.
+pub const SIZE_PCAPNG_BLOCK_OVERHEAD: usize = 12;
+/// The block overhead, the byte order magic, the version and the section length.
+pub const SIZE_PCAPNG_SECTION_HEADER: usize = 28;
+
+/// Frames and blocks above the size are treated as corrupted.
+pub const SIZE_RECORD_MAX: usize = 0x0100_0000;
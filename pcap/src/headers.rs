@@ -0,0 +1,220 @@
+//! The link, network and transport headers module.
+
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddrV4},
+};
+
+use bytes::{Buf, BufMut};
+
+use dhcp_protocol::{Message, DHCP_PORT_CLIENT, SIZE_HEADER_IP, SIZE_HEADER_UDP};
+
+/// IEEE 802.3 Ethernet.
+pub const LINKTYPE_ETHERNET: u32 = 1;
+/// Raw IPv4 or IPv6 packets.
+pub const LINKTYPE_RAW: u32 = 101;
+/// Linux "cooked" capture encapsulation.
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+/// Raw IPv4 packets.
+pub const LINKTYPE_IPV4: u32 = 228;
+/// Linux "cooked" capture encapsulation v2.
+pub const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const SIZE_HEADER_ETHERNET: usize = 14;
+const SIZE_HEADER_VLAN: usize = 4;
+const SIZE_HEADER_SLL: usize = 16;
+const SIZE_HEADER_SLL2: usize = 20;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+/// IEEE 802.1Q, IEEE 802.1ad and the legacy QinQ tags.
+const ETHERTYPES_VLAN: [u16; 3] = [0x8100, 0x88a8, 0x9100];
+
+const IP_PROTOCOL_UDP: u8 = 17;
+const IP_TIME_TO_LIVE: u8 = 64;
+/// The `More Fragments` flag and the fragment offset.
+const IP_FRAGMENT_MASK: u16 = 0x3fff;
+
+const MAC_BROADCAST: [u8; 6] = [0xff; 6];
+const MAC_UNSPECIFIED: [u8; 6] = [0x00; 6];
+
+/// Strips the link, IPv4 and UDP headers.
+///
+/// Returns the UDP endpoints and the payload or `None` if the frame is not
+/// a complete unfragmented IPv4 UDP datagram.
+pub(crate) fn strip(link_type: u32, frame: &[u8]) -> Option<(SocketAddrV4, SocketAddrV4, &[u8])> {
+    let packet = match link_type {
+        LINKTYPE_ETHERNET => strip_ethernet(frame)?,
+        LINKTYPE_LINUX_SLL if frame.len() >= SIZE_HEADER_SLL => {
+            if u16_at(frame, 14) != ETHERTYPE_IPV4 {
+                return None;
+            }
+            &frame[SIZE_HEADER_SLL..]
+        }
+        LINKTYPE_LINUX_SLL2 if frame.len() >= SIZE_HEADER_SLL2 => {
+            if u16_at(frame, 0) != ETHERTYPE_IPV4 {
+                return None;
+            }
+            &frame[SIZE_HEADER_SLL2..]
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 => frame,
+        _ => return None,
+    };
+    strip_ipv4_udp(packet)
+}
+
+/// Wraps the serialized message into UDP, IPv4 and Ethernet headers.
+///
+/// The DHCP client side gets the client hardware address if it is a MAC-48 one,
+/// the broadcast destination gets the broadcast MAC address and the other side
+/// gets the unspecified one.
+pub(crate) fn synthesize(
+    source: SocketAddrV4,
+    destination: SocketAddrV4,
+    message: &Message,
+    payload: &[u8],
+) -> io::Result<Vec<u8>> {
+    let udp_length = SIZE_HEADER_UDP + payload.len();
+    let ip_length = SIZE_HEADER_IP + udp_length;
+    if ip_length > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Message does not fit an IPv4 packet",
+        ));
+    }
+
+    let client_mac = match message.client_hardware_address.as_bytes() {
+        bytes if bytes.len() == MAC_UNSPECIFIED.len() => bytes,
+        _ => &MAC_UNSPECIFIED[..],
+    };
+    let mac = |address: &SocketAddrV4| {
+        if address.ip().is_broadcast() {
+            &MAC_BROADCAST[..]
+        } else if address.port() == DHCP_PORT_CLIENT {
+            client_mac
+        } else {
+            &MAC_UNSPECIFIED[..]
+        }
+    };
+
+    let mut frame = Vec::with_capacity(SIZE_HEADER_ETHERNET + ip_length);
+    frame.put_slice(mac(&destination));
+    frame.put_slice(mac(&source));
+    frame.put_u16_be(ETHERTYPE_IPV4);
+
+    let ip_offset = frame.len();
+    frame.put_u8(0x45); // version 4, 5 words header
+    frame.put_u8(0);
+    frame.put_u16_be(ip_length as u16);
+    frame.put_u16_be(0); // identification
+    frame.put_u16_be(0); // flags and fragment offset
+    frame.put_u8(IP_TIME_TO_LIVE);
+    frame.put_u8(IP_PROTOCOL_UDP);
+    frame.put_u16_be(0); // checksum
+    frame.put_slice(&source.ip().octets());
+    frame.put_slice(&destination.ip().octets());
+    let ip_checksum = checksum(0, &frame[ip_offset..]);
+    frame[ip_offset + 10] = (ip_checksum >> 8) as u8;
+    frame[ip_offset + 11] = ip_checksum as u8;
+
+    let udp_offset = frame.len();
+    frame.put_u16_be(source.port());
+    frame.put_u16_be(destination.port());
+    frame.put_u16_be(udp_length as u16);
+    frame.put_u16_be(0); // checksum
+    frame.put_slice(payload);
+    let mut pseudo_header = Vec::with_capacity(12);
+    pseudo_header.put_slice(&source.ip().octets());
+    pseudo_header.put_slice(&destination.ip().octets());
+    pseudo_header.put_u8(0);
+    pseudo_header.put_u8(IP_PROTOCOL_UDP);
+    pseudo_header.put_u16_be(udp_length as u16);
+    // the zero checksum means that it is absent
+    let udp_checksum = match checksum(sum(0, &pseudo_header), &frame[udp_offset..]) {
+        0 => 0xffff,
+        checksum => checksum,
+    };
+    frame[udp_offset + 6] = (udp_checksum >> 8) as u8;
+    frame[udp_offset + 7] = udp_checksum as u8;
+
+    Ok(frame)
+}
+
+/// Skips the Ethernet header and the VLAN tags.
+fn strip_ethernet(frame: &[u8]) -> Option<&[u8]> {
+    if frame.len() < SIZE_HEADER_ETHERNET {
+        return None;
+    }
+    let mut offset = SIZE_HEADER_ETHERNET - 2;
+    let mut ethertype = u16_at(frame, offset);
+    while ETHERTYPES_VLAN.contains(&ethertype) {
+        offset += SIZE_HEADER_VLAN;
+        if frame.len() < offset + 2 {
+            return None;
+        }
+        ethertype = u16_at(frame, offset);
+    }
+    if ethertype != ETHERTYPE_IPV4 {
+        return None;
+    }
+    Some(&frame[offset + 2..])
+}
+
+fn strip_ipv4_udp(packet: &[u8]) -> Option<(SocketAddrV4, SocketAddrV4, &[u8])> {
+    if packet.len() < SIZE_HEADER_IP || packet[0] >> 4 != 4 {
+        return None;
+    }
+    let header_length = ((packet[0] & 0x0f) as usize) * 4;
+    let total_length = u16_at(packet, 2) as usize;
+    if header_length < SIZE_HEADER_IP
+        || total_length < header_length + SIZE_HEADER_UDP
+        || total_length > packet.len()
+        || u16_at(packet, 6) & IP_FRAGMENT_MASK != 0
+        || packet[9] != IP_PROTOCOL_UDP
+    {
+        return None;
+    }
+    let source = Ipv4Addr::from(io::Cursor::new(&packet[12..]).get_u32_be());
+    let destination = Ipv4Addr::from(io::Cursor::new(&packet[16..]).get_u32_be());
+
+    let datagram = &packet[header_length..total_length];
+    let udp_length = u16_at(datagram, 4) as usize;
+    if udp_length < SIZE_HEADER_UDP || udp_length > datagram.len() {
+        return None;
+    }
+    Some((
+        SocketAddrV4::new(source, u16_at(datagram, 0)),
+        SocketAddrV4::new(destination, u16_at(datagram, 2)),
+        &datagram[SIZE_HEADER_UDP..udp_length],
+    ))
+}
+
+fn u16_at(src: &[u8], offset: usize) -> u16 {
+    io::Cursor::new(&src[offset..]).get_u16_be()
+}
+
+/// The Internet checksum of the data.
+///
+/// [RFC 1071](https://tools.ietf.org/html/rfc1071)
+fn checksum(initial: u32, data: &[u8]) -> u16 {
+    !fold(sum(initial, data))
+}
+
+fn sum(initial: u32, data: &[u8]) -> u32 {
+    let mut sum = initial;
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 {
+            u16::from(chunk[0]) << 8 | u16::from(chunk[1])
+        } else {
+            u16::from(chunk[0]) << 8
+        };
+        sum += u32::from(word);
+    }
+    sum
+}
+
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
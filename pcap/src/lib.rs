@@ -0,0 +1,26 @@
+//! Reading DHCP messages from packet captures and writing them back.
+//!
+//! Both the classic pcap and the pcapng formats are read,
+//! the classic pcap format with Ethernet framing is written.
+
+mod constants;
+mod headers;
+mod reader;
+mod writer;
+
+extern crate bytes;
+
+extern crate dhcp_protocol;
+
+use std::{net::SocketAddrV4, time::SystemTime};
+
+use dhcp_protocol::Message;
+
+pub use headers::{
+    LINKTYPE_ETHERNET, LINKTYPE_IPV4, LINKTYPE_LINUX_SLL, LINKTYPE_LINUX_SLL2, LINKTYPE_RAW,
+};
+pub use reader::CaptureReader;
+pub use writer::CaptureWriter;
+
+/// The capture time, the source, the destination and the message.
+pub type CaptureItem = (SystemTime, SocketAddrV4, SocketAddrV4, Message);
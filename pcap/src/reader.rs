@@ -0,0 +1,575 @@
+//! The capture file reader module.
+
+use std::{
+    io::{self, Read},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Buf;
+
+use dhcp_protocol::{Message, DHCP_PORT_CLIENT, DHCP_PORT_SERVER};
+
+use constants::*;
+use headers;
+use CaptureItem;
+
+/// The pcapng default timestamp resolution.
+const UNITS_PER_SECOND_DEFAULT: u64 = 1_000_000;
+const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Pcap {
+        link_type: u32,
+        is_nanoseconds: bool,
+    },
+    Pcapng,
+}
+
+/// A pcapng interface description.
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    link_type: u32,
+    snaplen: u32,
+    units_per_second: u64,
+}
+
+/// A frame with its capture time and link type.
+type Frame = (SystemTime, u32, Vec<u8>);
+
+/// Reads the DHCP messages from a pcap or pcapng capture.
+///
+/// The format and the byte order are detected by the magic number.
+/// Ethernet (with any number of VLAN tags), Linux cooked and raw IPv4 frames
+/// are supported, the other frames and the non-DHCP traffic are skipped.
+/// Simple packet blocks carry no timestamp, so they are read with `UNIX_EPOCH`.
+///
+/// A datagram on the DHCP ports which cannot be decoded yields an error
+/// and the iteration may be continued. A malformed capture yields an error
+/// and ends the iteration.
+pub struct CaptureReader<R> {
+    src: R,
+    format: Format,
+    is_little_endian: bool,
+    /// The pcapng interfaces of the current section.
+    interfaces: Vec<Interface>,
+    is_finished: bool,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads the capture header.
+    ///
+    /// # Errors
+    /// `io::Error` if the header cannot be read or the format is unknown.
+    pub fn new(mut src: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        src.read_exact(&mut magic)?;
+
+        let mut reader = CaptureReader {
+            src,
+            format: Format::Pcapng,
+            is_little_endian: false,
+            interfaces: Vec::new(),
+            is_finished: false,
+        };
+        // the section header block type is a palindrome
+        if io::Cursor::new(&magic).get_u32_be() == PCAPNG_BLOCK_SECTION_HEADER {
+            reader.read_section_header()?;
+            return Ok(reader);
+        }
+
+        let (is_little_endian, is_nanoseconds) = match (
+            io::Cursor::new(&magic).get_u32_be(),
+            io::Cursor::new(&magic).get_u32_le(),
+        ) {
+            (PCAP_MAGIC_MICROSECONDS, _) => (false, false),
+            (PCAP_MAGIC_NANOSECONDS, _) => (false, true),
+            (_, PCAP_MAGIC_MICROSECONDS) => (true, false),
+            (_, PCAP_MAGIC_NANOSECONDS) => (true, true),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Capture format is unknown",
+                ))
+            }
+        };
+        let mut header = [0u8; SIZE_PCAP_HEADER - 4];
+        reader.src.read_exact(&mut header)?;
+        reader.is_little_endian = is_little_endian;
+        reader.format = Format::Pcap {
+            // the upper bits may carry the FCS length
+            link_type: reader.u32_at(&header, 16) & 0xffff,
+            is_nanoseconds,
+        };
+        Ok(reader)
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.src
+    }
+
+    /// Reads the next frame or returns `None` at the end of the capture.
+    fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        match self.format {
+            Format::Pcap {
+                link_type,
+                is_nanoseconds,
+            } => self.next_record(link_type, is_nanoseconds),
+            Format::Pcapng => self.next_block(),
+        }
+    }
+
+    fn next_record(&mut self, link_type: u32, is_nanoseconds: bool) -> io::Result<Option<Frame>> {
+        let mut header = [0u8; SIZE_PCAP_RECORD_HEADER];
+        if !read_or_eof(&mut self.src, &mut header)? {
+            return Ok(None);
+        }
+        let seconds = self.u32_at(&header, 0);
+        let fraction = self.u32_at(&header, 4);
+        let captured = self.u32_at(&header, 8) as usize;
+        if captured > SIZE_RECORD_MAX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Record length is invalid",
+            ));
+        }
+        let mut frame = vec![0u8; captured];
+        self.src.read_exact(&mut frame)?;
+
+        let nanoseconds = if is_nanoseconds {
+            fraction
+        } else {
+            fraction.saturating_mul(1_000)
+        };
+        let timestamp = UNIX_EPOCH + Duration::new(u64::from(seconds), nanoseconds);
+        Ok(Some((timestamp, link_type, frame)))
+    }
+
+    fn next_block(&mut self) -> io::Result<Option<Frame>> {
+        loop {
+            let mut block_type = [0u8; 4];
+            if !read_or_eof(&mut self.src, &mut block_type)? {
+                return Ok(None);
+            }
+            let block_type = self.u32_at(&block_type, 0);
+            if block_type == PCAPNG_BLOCK_SECTION_HEADER {
+                self.read_section_header()?;
+                continue;
+            }
+
+            let mut length = [0u8; 4];
+            self.src.read_exact(&mut length)?;
+            let length = self.u32_at(&length, 0) as usize;
+            check_block_length(length, SIZE_PCAPNG_BLOCK_OVERHEAD)?;
+            let mut block = vec![0u8; length - 8];
+            self.src.read_exact(&mut block)?;
+            let body = &block[..length - SIZE_PCAPNG_BLOCK_OVERHEAD];
+
+            match block_type {
+                PCAPNG_BLOCK_INTERFACE_DESCRIPTION => {
+                    let interface = self.interface(body)?;
+                    self.interfaces.push(interface);
+                }
+                PCAPNG_BLOCK_ENHANCED_PACKET => return self.enhanced_packet(body).map(Some),
+                PCAPNG_BLOCK_SIMPLE_PACKET => return self.simple_packet(body).map(Some),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads the rest of a section header block after its type.
+    ///
+    /// Sets the byte order and forgets the interfaces of the previous section.
+    fn read_section_header(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 8];
+        self.src.read_exact(&mut header)?;
+        self.is_little_endian =
+            if io::Cursor::new(&header[4..]).get_u32_le() == PCAPNG_BYTE_ORDER_MAGIC {
+                true
+            } else if io::Cursor::new(&header[4..]).get_u32_be() == PCAPNG_BYTE_ORDER_MAGIC {
+                false
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Byte order magic is invalid",
+                ));
+            };
+        let length = self.u32_at(&header, 0) as usize;
+        check_block_length(length, SIZE_PCAPNG_SECTION_HEADER)?;
+        let mut rest = vec![0u8; length - SIZE_PCAPNG_BLOCK_OVERHEAD];
+        self.src.read_exact(&mut rest)?;
+        self.interfaces.clear();
+        Ok(())
+    }
+
+    fn interface(&self, body: &[u8]) -> io::Result<Interface> {
+        if body.len() < 8 {
+            return Err(invalid_block());
+        }
+        let mut interface = Interface {
+            link_type: u32::from(self.u16_at(body, 0)),
+            snaplen: self.u32_at(body, 4),
+            units_per_second: UNITS_PER_SECOND_DEFAULT,
+        };
+
+        let mut offset = 8;
+        while offset + 4 <= body.len() {
+            let code = self.u16_at(body, offset);
+            let len = self.u16_at(body, offset + 2) as usize;
+            offset += 4;
+            if code == PCAPNG_OPTION_END || offset + len > body.len() {
+                break;
+            }
+            if code == PCAPNG_OPTION_IF_TSRESOL && len == 1 {
+                let resolution = body[offset];
+                let exponent = u32::from(resolution & 0x7f);
+                interface.units_per_second = match resolution & 0x80 {
+                    0 if exponent <= 19 => 10u64.pow(exponent),
+                    0x80 if exponent <= 63 => 1u64 << exponent,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Timestamp resolution is unsupported",
+                        ))
+                    }
+                };
+            }
+            // the values are padded to 32 bits
+            offset += (len + 3) & !3;
+        }
+        Ok(interface)
+    }
+
+    fn enhanced_packet(&self, body: &[u8]) -> io::Result<Frame> {
+        if body.len() < 20 {
+            return Err(invalid_block());
+        }
+        let interface = self.interface_by_id(self.u32_at(body, 0) as usize)?;
+        let ticks = u64::from(self.u32_at(body, 4)) << 32 | u64::from(self.u32_at(body, 8));
+        let captured = self.u32_at(body, 12) as usize;
+        if 20 + captured > body.len() {
+            return Err(invalid_block());
+        }
+
+        let units = interface.units_per_second;
+        let nanoseconds =
+            u128::from(ticks % units) * u128::from(NANOSECONDS_PER_SECOND) / u128::from(units);
+        let timestamp = UNIX_EPOCH
+            .checked_add(Duration::new(ticks / units, nanoseconds as u32))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Packet timestamp is out of range",
+                )
+            })?;
+        Ok((
+            timestamp,
+            interface.link_type,
+            body[20..20 + captured].to_vec(),
+        ))
+    }
+
+    fn simple_packet(&self, body: &[u8]) -> io::Result<Frame> {
+        if body.len() < 4 {
+            return Err(invalid_block());
+        }
+        let interface = self.interface_by_id(0)?;
+        let mut captured = self.u32_at(body, 0) as usize;
+        if interface.snaplen != 0 {
+            captured = captured.min(interface.snaplen as usize);
+        }
+        captured = captured.min(body.len() - 4);
+        Ok((
+            UNIX_EPOCH,
+            interface.link_type,
+            body[4..4 + captured].to_vec(),
+        ))
+    }
+
+    fn interface_by_id(&self, id: usize) -> io::Result<Interface> {
+        self.interfaces.get(id).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Packet refers to an unknown interface",
+            )
+        })
+    }
+
+    fn u16_at(&self, src: &[u8], offset: usize) -> u16 {
+        let mut cursor = io::Cursor::new(&src[offset..]);
+        if self.is_little_endian {
+            cursor.get_u16_le()
+        } else {
+            cursor.get_u16_be()
+        }
+    }
+
+    fn u32_at(&self, src: &[u8], offset: usize) -> u32 {
+        let mut cursor = io::Cursor::new(&src[offset..]);
+        if self.is_little_endian {
+            cursor.get_u32_le()
+        } else {
+            cursor.get_u32_be()
+        }
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CaptureItem>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.is_finished {
+            let (timestamp, link_type, frame) = match self.next_frame() {
+                Ok(Some(frame)) => frame,
+                Ok(None) => break,
+                Err(error) => {
+                    self.is_finished = true;
+                    return Some(Err(error));
+                }
+            };
+            let (source, destination, payload) = match headers::strip(link_type, &frame) {
+                Some(datagram) => datagram,
+                None => continue,
+            };
+            if !is_dhcp_port(source.port()) && !is_dhcp_port(destination.port()) {
+                continue;
+            }
+            return Some(
+                Message::from_bytes(payload)
+                    .map(|message| (timestamp, source, destination, message))
+                    .map_err(io::Error::from),
+            );
+        }
+        self.is_finished = true;
+        None
+    }
+}
+
+fn is_dhcp_port(port: u16) -> bool {
+    port == DHCP_PORT_SERVER || port == DHCP_PORT_CLIENT
+}
+
+fn check_block_length(length: usize, minimal: usize) -> io::Result<()> {
+    if length < minimal || !length.is_multiple_of(4) || length > SIZE_RECORD_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Block length is invalid",
+        ));
+    }
+    Ok(())
+}
+
+fn invalid_block() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Block is too small")
+}
+
+/// Fills the buffer or returns `false` if the source is exhausted before the first byte.
+///
+/// # Errors
+/// `io::Error` if the source is exhausted in the middle of the buffer.
+fn read_or_eof<R: Read>(src: &mut R, buffer: &mut [u8]) -> io::Result<bool> {
+    let mut amount = 0;
+    while amount < buffer.len() {
+        match src.read(&mut buffer[amount..]) {
+            Ok(0) if amount == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => amount += read,
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use bytes::BufMut;
+
+    use dhcp_protocol::{
+        HardwareAddress, HardwareType, MessageType, OperationCode, OptionTag, OptionValue, Options,
+    };
+
+    use headers::LINKTYPE_ETHERNET;
+    use writer::CaptureWriter;
+
+    fn message(message_type: MessageType) -> Message {
+        let mut options = Options::default();
+        options
+            .set(
+                OptionTag::DhcpMessageType,
+                OptionValue::MessageType(message_type),
+            )
+            .unwrap();
+        Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: true,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options,
+        }
+    }
+
+    fn client() -> SocketAddrV4 {
+        SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), DHCP_PORT_CLIENT)
+    }
+
+    fn server() -> SocketAddrV4 {
+        SocketAddrV4::new(Ipv4Addr::new(255, 255, 255, 255), DHCP_PORT_SERVER)
+    }
+
+    /// Appends a big-endian pcapng block padding the body to 32 bits.
+    fn block(dst: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+        let padding = (4 - body.len() % 4) % 4;
+        let length = (SIZE_PCAPNG_BLOCK_OVERHEAD + body.len() + padding) as u32;
+        dst.put_u32_be(block_type);
+        dst.put_u32_be(length);
+        dst.put_slice(body);
+        dst.put_slice(&vec![0u8; padding]);
+        dst.put_u32_be(length);
+    }
+
+    /// Appends a section header and an Ethernet interface with the timestamp resolution.
+    fn section(dst: &mut Vec<u8>, resolution: u8) {
+        let mut section = Vec::new();
+        section.put_u32_be(PCAPNG_BYTE_ORDER_MAGIC);
+        section.put_u16_be(1);
+        section.put_u16_be(0);
+        section.put_u64_be(u64::MAX);
+        block(dst, PCAPNG_BLOCK_SECTION_HEADER, &section);
+        let mut interface = Vec::new();
+        interface.put_u16_be(LINKTYPE_ETHERNET as u16);
+        interface.put_u16_be(0);
+        interface.put_u32_be(0);
+        interface.put_u16_be(PCAPNG_OPTION_IF_TSRESOL);
+        interface.put_u16_be(1);
+        interface.put_slice(&[resolution, 0, 0, 0]);
+        interface.put_u16_be(PCAPNG_OPTION_END);
+        interface.put_u16_be(0);
+        block(dst, PCAPNG_BLOCK_INTERFACE_DESCRIPTION, &interface);
+    }
+
+    fn enhanced_packet(dst: &mut Vec<u8>, ticks: u64, frame: &[u8]) {
+        let mut body = Vec::new();
+        body.put_u32_be(0);
+        body.put_u32_be((ticks >> 32) as u32);
+        body.put_u32_be(ticks as u32);
+        body.put_u32_be(frame.len() as u32);
+        body.put_u32_be(frame.len() as u32);
+        body.put_slice(frame);
+        block(dst, PCAPNG_BLOCK_ENHANCED_PACKET, &body);
+    }
+
+    #[test]
+    fn pcap_round_trip() {
+        let timestamp = UNIX_EPOCH + Duration::new(1_500_000_000, 123_456_000);
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        writer
+            .write(
+                timestamp,
+                client(),
+                server(),
+                &message(MessageType::DhcpDiscover),
+            )
+            .unwrap();
+        writer
+            .write(
+                timestamp,
+                client(),
+                server(),
+                &message(MessageType::DhcpRequest),
+            )
+            .unwrap();
+        let capture = writer.into_inner();
+
+        let items = CaptureReader::new(capture.as_slice())
+            .unwrap()
+            .collect::<io::Result<Vec<CaptureItem>>>()
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        let (read_timestamp, source, destination, ref message) = items[1];
+        assert_eq!(read_timestamp, timestamp);
+        assert_eq!(source, client());
+        assert_eq!(destination, server());
+        assert_eq!(message.transaction_id, 0xdead_beef);
+        assert_eq!(
+            message.options.dhcp_message_type,
+            Some(MessageType::DhcpRequest)
+        );
+    }
+
+    #[test]
+    fn pcapng_vlan_frames_are_stripped() {
+        let payload = {
+            let mut buffer = vec![0u8; 1024];
+            let amount = message(MessageType::DhcpDiscover)
+                .to_bytes(&mut buffer, None)
+                .unwrap();
+            buffer.truncate(amount);
+            buffer
+        };
+        let frame = headers::synthesize(
+            client(),
+            server(),
+            &message(MessageType::DhcpDiscover),
+            &payload,
+        )
+        .unwrap();
+        // an 802.1ad tag followed by an 802.1Q one
+        let mut tagged = frame[..12].to_vec();
+        tagged.extend_from_slice(&[0x88, 0xa8, 0x00, 0x0a, 0x81, 0x00, 0x00, 0x64]);
+        tagged.extend_from_slice(&frame[12..]);
+        // the same datagram to the DNS port
+        let mut dns = frame.clone();
+        dns[36..38].copy_from_slice(&[0x00, 0x35]);
+        dns[34..36].copy_from_slice(&[0x00, 0x35]);
+
+        let mut capture = Vec::new();
+        section(&mut capture, 9);
+        enhanced_packet(&mut capture, 1_500_000_000_000_000_001, &dns);
+        enhanced_packet(&mut capture, 1_500_000_000_000_000_002, &tagged);
+
+        let items = CaptureReader::new(capture.as_slice())
+            .unwrap()
+            .collect::<io::Result<Vec<CaptureItem>>>()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        let (timestamp, source, destination, ref message) = items[0];
+        assert_eq!(timestamp, UNIX_EPOCH + Duration::new(1_500_000_000, 2));
+        assert_eq!(source, client());
+        assert_eq!(destination, server());
+        assert_eq!(
+            message.options.dhcp_message_type,
+            Some(MessageType::DhcpDiscover)
+        );
+    }
+
+    #[test]
+    fn pcapng_timestamp_overflow_is_rejected() {
+        // one-second resolution makes the largest timestamp exceed the system time range
+        let mut capture = Vec::new();
+        section(&mut capture, 0);
+        enhanced_packet(&mut capture, u64::MAX, &[]);
+        assert_eq!(capture.len(), 92);
+
+        let error = match CaptureReader::new(capture.as_slice()).unwrap().next() {
+            Some(Err(error)) => error,
+            _ => panic!("The packet has been read"),
+        };
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
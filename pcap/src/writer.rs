@@ -0,0 +1,94 @@
+//! The capture file writer module.
+
+use std::{
+    io::{self, Write},
+    net::SocketAddrV4,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::BufMut;
+
+use dhcp_protocol::{Message, SIZE_HEADER_IP, SIZE_HEADER_UDP};
+
+use constants::*;
+use headers::{self, LINKTYPE_ETHERNET};
+
+/// The largest message fitting an IPv4 packet.
+const SIZE_BUFFER: usize = u16::MAX as usize - SIZE_HEADER_IP - SIZE_HEADER_UDP;
+
+/// Writes DHCP messages as a classic little-endian pcap capture.
+///
+/// The timestamps have the microsecond resolution and the frames get
+/// synthesized Ethernet, IPv4 and UDP headers with valid checksums.
+pub struct CaptureWriter<W: Write> {
+    dst: W,
+    /// Stores the serialized message.
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    /// Writes the capture header.
+    ///
+    /// # Errors
+    /// `io::Error` if the header cannot be written.
+    pub fn new(mut dst: W) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(SIZE_PCAP_HEADER);
+        header.put_u32_le(PCAP_MAGIC_MICROSECONDS);
+        header.put_u16_le(PCAP_VERSION_MAJOR);
+        header.put_u16_le(PCAP_VERSION_MINOR);
+        header.put_u32_le(0); // the time zone offset
+        header.put_u32_le(0); // the timestamp accuracy
+        header.put_u32_le(PCAP_SNAPLEN);
+        header.put_u32_le(LINKTYPE_ETHERNET);
+        dst.write_all(&header)?;
+
+        Ok(CaptureWriter {
+            dst,
+            buffer: vec![0u8; SIZE_BUFFER],
+        })
+    }
+
+    /// Writes the message sent from `source` to `destination` at `timestamp`.
+    ///
+    /// # Errors
+    /// `io::Error` if the message cannot be serialized or written
+    /// or the timestamp is earlier than `UNIX_EPOCH`.
+    pub fn write(
+        &mut self,
+        timestamp: SystemTime,
+        source: SocketAddrV4,
+        destination: SocketAddrV4,
+        message: &Message,
+    ) -> io::Result<()> {
+        let since_epoch = timestamp.duration_since(UNIX_EPOCH).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Timestamp is before the epoch")
+        })?;
+        if since_epoch.as_secs() > u64::from(u32::MAX) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Timestamp does not fit the capture format",
+            ));
+        }
+
+        let amount = message.to_bytes(&mut self.buffer, None)?;
+        let frame = headers::synthesize(source, destination, message, &self.buffer[..amount])?;
+
+        let mut header = Vec::with_capacity(SIZE_PCAP_RECORD_HEADER);
+        header.put_u32_le(since_epoch.as_secs() as u32);
+        header.put_u32_le(since_epoch.subsec_micros());
+        header.put_u32_le(frame.len() as u32);
+        header.put_u32_le(frame.len() as u32);
+        self.dst.write_all(&header)?;
+        self.dst.write_all(&frame)
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.dst.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.dst
+    }
+}
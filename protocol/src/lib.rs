@@ -4,15 +4,46 @@ extern crate bytes;
 extern crate eui48;
 #[macro_use]
 extern crate failure;
+extern crate md5;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 mod v4;
+pub mod v6;
 
 pub use self::v4::{
     constants::*,
-    options::{MessageType, OptionTag, Options, Overload},
+    options::{
+        Authentication, ClientArchitecture, ClientFqdn, CustomOption, DomainName,
+        MachineIdentifier, MessageType, NetworkInterfaceIdentifier, OptionDecoder, OptionRegistry,
+        OptionTag, OptionValue, Options, Overload, PxeBootItem, PxeBootMenuItem, PxeBootServer,
+        PxeMenuPrompt, PxeOptions, RelayAgentInformation, SipServers, UserClass, VendorClassData,
+        VendorDecoder, VendorInformation, VendorOptions, VendorRegistry, VendorSpecificData,
+        AUTHENTICATION_ALGORITHM_HMAC_MD5, AUTHENTICATION_PROTOCOL_DELAYED,
+        AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY, AUTHENTICATION_RDM_MONOTONIC,
+        CLIENT_FQDN_RCODE_SERVER, PXE_CLASS_ID_PREFIX, PXE_DISCOVERY_DIRECT_DOWNLOAD,
+        PXE_DISCOVERY_NO_BROADCAST, PXE_DISCOVERY_NO_MULTICAST, PXE_DISCOVERY_SERVER_LIST_ONLY,
+        RECONFIGURE_KEY_TYPE_HMAC_MD5, RECONFIGURE_KEY_TYPE_KEY, RELAY_AGENT_FLAG_UNICAST,
+        SIZE_HMAC_MD5,
+    },
+    AuthenticationMode,
+    DecodeError,
+    Enforcement,
+    ExpectedLength,
+    HardwareAddress,
     HardwareType,
+    KeyStore,
     Message,
+    MessageRef,
     OperationCode,
+    OptionsIter,
+    Region,
+    ReplayDetection,
+    ValidationReport,
+    Violation,
 };
 
 pub const DHCP_PORT_SERVER: u16 = 67;
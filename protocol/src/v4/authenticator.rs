@@ -0,0 +1,494 @@
+//! DHCP message authentication module.
+
+use std::{cmp, collections::HashMap, hash::Hash, io, mem};
+
+use super::{
+    options::{hmac_md5, MessageType, OptionTag, SIZE_HMAC_MD5},
+    Message, MessageRef,
+};
+
+/// The `hops` field offset in bytes.
+const OFFSET_HOPS: usize = 3;
+/// The `giaddr` field offset in bytes.
+const OFFSET_GATEWAY_IP_ADDRESS: usize = 24;
+/// The `giaddr` field size in bytes.
+const SIZE_GATEWAY_IP_ADDRESS: usize = mem::size_of::<u32>();
+/// The tag octet and the length octet.
+const SIZE_OPTION_PREFIX: usize = 2;
+/// The protocol, algorithm and RDM octets and the replay detection field.
+const SIZE_AUTHENTICATION_PREFIX: usize = mem::size_of::<u8>() * 3 + mem::size_of::<u64>();
+
+/// The shared keys of the delayed authentication by their secret IDs.
+///
+/// [RFC 3118 §5](https://tools.ietf.org/html/rfc3118#section-5)
+#[derive(Debug, Clone, Default)]
+pub struct KeyStore {
+    keys: HashMap<u32, Vec<u8>>,
+}
+
+impl KeyStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        KeyStore::default()
+    }
+
+    /// Adds the key of the secret ID replacing the previous one.
+    pub fn insert(&mut self, secret_id: u32, key: Vec<u8>) -> &mut Self {
+        self.keys.insert(secret_id, key);
+        self
+    }
+
+    /// Returns the key of the secret ID.
+    pub fn get(&self, secret_id: u32) -> Option<&[u8]> {
+        self.keys.get(&secret_id).map(|key| key.as_slice())
+    }
+}
+
+/// The last replay detection values received from the peers.
+///
+/// A peer may be identified by anything like its secret ID or client identifier.
+///
+/// [RFC 3118 §3](https://tools.ietf.org/html/rfc3118#section-3)
+#[derive(Debug, Clone)]
+pub struct ReplayDetection<K: Hash + Eq> {
+    values: HashMap<K, u64>,
+}
+
+impl<K: Hash + Eq> Default for ReplayDetection<K> {
+    fn default() -> Self {
+        ReplayDetection {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> ReplayDetection<K> {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        ReplayDetection::default()
+    }
+
+    /// Returns the last replay detection value received from the peer.
+    pub fn last(&self, peer: &K) -> Option<u64> {
+        self.values.get(peer).cloned()
+    }
+
+    /// Records the replay detection value of the message signed with the delayed authentication.
+    ///
+    /// Must be called after the message is verified, otherwise a forged value may block the peer.
+    pub fn record(&mut self, peer: K, message: &Message) {
+        let value = match message.options.authentication {
+            Some(ref authentication) if authentication.delayed_secret_id().is_some() => {
+                authentication.replay_detection
+            }
+            _ => return,
+        };
+        let last = self.values.entry(peer).or_insert(value);
+        *last = cmp::max(*last, value);
+    }
+}
+
+/// How a server or a client treats the delayed authentication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthenticationMode {
+    /// Messages are signed and the unauthenticated ones are discarded.
+    Require,
+    /// Messages are signed if the peer supports the authentication.
+    /// Unauthenticated messages are accepted, but the ones with invalid digests are discarded.
+    Prefer,
+    /// The authentication is neither requested nor checked.
+    Ignore,
+}
+
+impl Message {
+    /// Serializes the message and fills the digest of its authentication option
+    /// computed over the written packet with the key.
+    ///
+    /// The option must be set beforehand, e.g. by `Authentication::delayed_hmac_md5`
+    /// or `Authentication::reconfigure_hmac_md5`. The digest is computed over the final
+    /// packet, since the size limit may change the way the options are placed.
+    ///
+    /// [RFC 3118 §5](https://tools.ietf.org/html/rfc3118#section-5)
+    /// [RFC 6704 §3](https://tools.ietf.org/html/rfc6704#section-3)
+    ///
+    /// # Errors
+    /// `io::Error` if the buffer is too small or the message has no authentication digest.
+    pub fn to_bytes_signed(
+        &self,
+        dst: &mut [u8],
+        max_size: Option<u16>,
+        key: &[u8],
+    ) -> io::Result<usize> {
+        let amount = self.to_bytes(dst, max_size)?;
+        sign_packet(&mut dst[..amount], key)?;
+        Ok(amount)
+    }
+
+    /// Verifies the delayed authentication digest of the `packet` the message has been
+    /// decoded from with the key of the message secret ID.
+    ///
+    /// `last_replay_detection` is the last value received from the peer (see `ReplayDetection`).
+    ///
+    /// Returns `false` if the digest is absent or invalid, the key is unknown or
+    /// the replay detection value is not greater than the last one.
+    pub fn verify_delayed(
+        &self,
+        packet: &[u8],
+        keys: &KeyStore,
+        last_replay_detection: Option<u64>,
+    ) -> bool {
+        let (secret_id, replay_detection) = match self.options.authentication {
+            Some(ref authentication) => match authentication.delayed_secret_id() {
+                Some(secret_id) => (secret_id, authentication.replay_detection),
+                None => return false,
+            },
+            None => return false,
+        };
+        // the replay detection value is checked before the digest
+        if last_replay_detection.is_some_and(|last| replay_detection <= last) {
+            return false;
+        }
+        match keys.get(secret_id) {
+            Some(key) => verify_packet(packet, key),
+            None => false,
+        }
+    }
+
+    /// Checks if the message is acceptable in the authentication mode.
+    ///
+    /// A `DHCPDISCOVER` only requests the authentication, since the client does
+    /// not know which key to use yet, so such a request satisfies the `Require` mode.
+    ///
+    /// `packet` and `last_replay_detection` are passed to `verify_delayed`.
+    pub fn is_authentic(
+        &self,
+        packet: &[u8],
+        mode: AuthenticationMode,
+        keys: &KeyStore,
+        last_replay_detection: Option<u64>,
+    ) -> bool {
+        let is_required = match mode {
+            AuthenticationMode::Require => true,
+            AuthenticationMode::Prefer => false,
+            AuthenticationMode::Ignore => return true,
+        };
+        match self.options.authentication {
+            Some(ref authentication) if authentication.is_delayed_request() => {
+                match self.options.dhcp_message_type {
+                    Some(MessageType::DhcpDiscover) => true,
+                    _ => !is_required,
+                }
+            }
+            Some(ref authentication) if authentication.delayed_secret_id().is_some() => {
+                self.verify_delayed(packet, keys, last_replay_detection)
+            }
+            _ => !is_required,
+        }
+    }
+
+    /// Verifies the reconfigure key signature of the `packet` the `DHCPFORCERENEW` message
+    /// has been decoded from.
+    ///
+    /// Returns `false` if the signature is absent or invalid.
+    pub fn verify_reconfigure(&self, packet: &[u8], key: &[u8]) -> bool {
+        match self.options.authentication {
+            Some(ref authentication) if authentication.is_reconfigure_hmac_md5() => {
+                verify_packet(packet, key)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The parts of a packet which are excluded from the digest computation.
+struct Layout {
+    /// The packet offsets of the digest at the end of the authentication information.
+    ///
+    /// The authentication option may be split into several instances (RFC 3396),
+    /// so the digest octets are not necessarily contiguous.
+    digest: Vec<usize>,
+    /// The relay agent information instances as `(offset, size)` in descending offset order.
+    relay_agent_information: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    /// Returns `None` if the packet is malformed or has no authentication option with a digest.
+    fn new(packet: &[u8]) -> Option<Self> {
+        let message = MessageRef::new(packet).ok()?;
+        let mut information = Vec::new();
+        let mut relay_agent_information = Vec::new();
+        let mut options = message.options();
+        while let Some(option) = options.next() {
+            let (tag, value) = option.ok()?;
+            // the offset of the tag is followed by the length octet and the value
+            let offset = options.offset();
+            if tag == OptionTag::Authentication as u8 {
+                let start = offset + SIZE_OPTION_PREFIX;
+                information.extend(start..start + value.len());
+            } else if tag == OptionTag::RelayAgentInformation as u8 {
+                relay_agent_information.push((offset, SIZE_OPTION_PREFIX + value.len()));
+            }
+        }
+        if information.len() < SIZE_AUTHENTICATION_PREFIX + SIZE_HMAC_MD5 {
+            return None;
+        }
+        relay_agent_information.sort_by_key(|&(offset, _)| cmp::Reverse(offset));
+        Some(Layout {
+            digest: information.split_off(information.len() - SIZE_HMAC_MD5),
+            relay_agent_information,
+        })
+    }
+
+    /// Computes the HMAC-MD5 over the packet.
+    ///
+    /// RFC 3118 §2
+    /// Because a DHCP relay agent may alter the values of the 'giaddr' and
+    /// 'hops' fields in the DHCP message, the contents of those two fields
+    /// MUST be set to zero for the computation of any hash function over the
+    /// message header. Additionally, a relay agent may append the DHCP relay
+    /// agent information option 82 [8] as the last option in a message to
+    /// servers. If a server finds option 82 included in a received message,
+    /// the server MUST compute any hash function as if the option were NOT
+    /// included in the message without changing the order of options.
+    /// Whenever the server sends back option 82 to a relay agent, the server
+    /// MUST not include the option in the computation of any hash function
+    /// over the message.
+    ///
+    /// RFC 3118 §5
+    /// The entire DHCP message (setting the MAC field of the authentication
+    /// option to 0), including the DHCP message header and the options field,
+    /// is used as input to the HMAC-MD5 computation function.
+    fn hmac_md5(&self, packet: &[u8], key: &[u8]) -> [u8; SIZE_HMAC_MD5] {
+        let mut data = packet.to_vec();
+        data[OFFSET_HOPS] = 0;
+        let gateway_ip_address =
+            OFFSET_GATEWAY_IP_ADDRESS..OFFSET_GATEWAY_IP_ADDRESS + SIZE_GATEWAY_IP_ADDRESS;
+        for byte in data[gateway_ip_address].iter_mut() {
+            *byte = 0;
+        }
+        for position in self.digest.iter() {
+            data[*position] = 0;
+        }
+        for (offset, size) in self.relay_agent_information.iter() {
+            data.drain(*offset..*offset + *size);
+        }
+        hmac_md5(key, &data)
+    }
+}
+
+/// Computes the authentication digest of the serialized message.
+///
+/// # Errors
+/// `io::Error` if the packet has no authentication option with a digest.
+fn packet_digest(packet: &[u8], key: &[u8]) -> io::Result<[u8; SIZE_HMAC_MD5]> {
+    match Layout::new(packet) {
+        Some(layout) => Ok(layout.hmac_md5(packet, key)),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Authentication information is too short",
+        )),
+    }
+}
+
+/// Fills the authentication digest of the serialized message.
+///
+/// # Errors
+/// `io::Error` if the packet has no authentication option with a digest.
+fn sign_packet(packet: &mut [u8], key: &[u8]) -> io::Result<()> {
+    let digest = packet_digest(packet, key)?;
+    // the layout is valid, since the digest has been computed
+    let layout = Layout::new(packet).unwrap();
+    for (position, byte) in layout.digest.iter().zip(digest.iter()) {
+        packet[*position] = *byte;
+    }
+    Ok(())
+}
+
+/// Compares the authentication digest of the received packet with the computed one.
+///
+/// Returns `false` if the packet has no authentication option with a digest.
+fn verify_packet(packet: &[u8], key: &[u8]) -> bool {
+    let layout = match Layout::new(packet) {
+        Some(layout) => layout,
+        None => return false,
+    };
+    let received: Vec<u8> = layout
+        .digest
+        .iter()
+        .map(|position| packet[*position])
+        .collect();
+    is_digest_equal(&layout.hmac_md5(packet, key), &received)
+}
+
+/// Compares the digests in constant time, so the length of the matching prefix is not leaked.
+fn is_digest_equal(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b.iter())
+            .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    use super::super::{
+        constants::*,
+        options::{Authentication, Options},
+        HardwareAddress, HardwareType, OperationCode,
+    };
+
+    fn message(message_type: MessageType) -> Message {
+        let options = Options {
+            dhcp_message_type: Some(message_type),
+            ..Options::default()
+        };
+        Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: false,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options,
+        }
+    }
+
+    /// Serializes the message without signing it.
+    fn unsigned(message: &Message) -> Vec<u8> {
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        buffer.truncate(amount);
+        buffer
+    }
+
+    /// Serializes the message signed with the key.
+    fn signed(message: &Message, key: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes_signed(&mut buffer, None, key).unwrap();
+        buffer.truncate(amount);
+        buffer
+    }
+
+    #[test]
+    fn forcerenew_is_signed_with_reconfigure_key() {
+        let key = [0x42; SIZE_HMAC_MD5];
+        let mut message = message(MessageType::DhcpForceRenew);
+        message.operation_code = OperationCode::BootReply;
+        message.options.dhcp_server_id = Some(Ipv4Addr::new(192, 168, 0, 2));
+        assert!(message.validate().is_err());
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        assert!(message.to_bytes_signed(&mut buffer, None, &key).is_err());
+        message.options.authentication = Some(Authentication::reconfigure_hmac_md5(1));
+        assert!(message.validate().is_ok());
+
+        let mut packet = signed(&message, &key);
+        // the relay agents may change `hops` and `giaddr`
+        packet[3] = 1;
+        packet[24..28].copy_from_slice(&[10, 0, 0, 1]);
+        assert!(message.verify_reconfigure(&packet, &key));
+        assert!(!message.verify_reconfigure(&packet, &[0x24; SIZE_HMAC_MD5]));
+
+        packet[4] ^= 0x01;
+        assert!(!message.verify_reconfigure(&packet, &key));
+    }
+
+    #[test]
+    fn digest_is_computed_over_the_sent_packet() {
+        let mut keys = KeyStore::new();
+        keys.insert(1, b"shared secret".to_vec());
+        let servers: Vec<Ipv4Addr> = (0..100).map(|i| Ipv4Addr::new(10, 0, 2, i)).collect();
+        let mut message = message(MessageType::DhcpRequest);
+        message.options.domain_name_servers = Some(servers);
+        message.options.authentication = Some(Authentication::delayed_hmac_md5(2, 1));
+
+        // the size limit makes the options overload the `file` and `sname` fields
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL * 2];
+        let amount = message
+            .to_bytes_signed(
+                &mut buffer,
+                Some(SIZE_MESSAGE_MINIMAL as u16),
+                b"shared secret",
+            )
+            .unwrap();
+        let packet = &buffer[..amount];
+        let decoded = Message::from_bytes(packet).unwrap();
+        assert!(decoded.options.overload.is_some());
+        assert!(decoded.verify_delayed(packet, &keys, None));
+
+        // a relay agent appends its information just before the `end` option
+        let mut relayed = buffer[..amount - 1].to_vec();
+        relayed.extend_from_slice(&[OptionTag::RelayAgentInformation as u8, 3, 1, 1, 0x2a]);
+        relayed.push(buffer[amount - 1]);
+        let decoded = Message::from_bytes(&relayed).unwrap();
+        assert!(decoded.options.relay_agent_information.is_some());
+        assert!(decoded.verify_delayed(&relayed, &keys, None));
+    }
+
+    #[test]
+    fn delayed_authentication_modes() {
+        let mut keys = KeyStore::new();
+        keys.insert(1, b"shared secret".to_vec());
+
+        let mut discover = message(MessageType::DhcpDiscover);
+        let packet = unsigned(&discover);
+        assert!(!discover.is_authentic(&packet, AuthenticationMode::Require, &keys, None));
+        assert!(discover.is_authentic(&packet, AuthenticationMode::Prefer, &keys, None));
+        discover.options.authentication = Some(Authentication::delayed_request(1));
+        let packet = unsigned(&discover);
+        assert!(discover.is_authentic(&packet, AuthenticationMode::Require, &keys, None));
+
+        let mut request = message(MessageType::DhcpRequest);
+        request.options.address_request = Some(Ipv4Addr::new(192, 168, 0, 100));
+        request.options.authentication = Some(Authentication::delayed_hmac_md5(2, 1));
+        assert!(request.validate().is_ok());
+        let mut packet = signed(&request, b"shared secret");
+        assert!(request.is_authentic(&packet, AuthenticationMode::Require, &keys, None));
+
+        // `hops` is excluded from the digest, but `secs` is not
+        packet[3] = 1;
+        assert!(request.verify_delayed(&packet, &keys, None));
+        packet[9] = 1;
+        assert!(!request.is_authentic(&packet, AuthenticationMode::Prefer, &keys, None));
+        assert!(request.is_authentic(&packet, AuthenticationMode::Ignore, &keys, None));
+
+        request.options.authentication = Some(Authentication::delayed_hmac_md5(3, 2));
+        let packet = signed(&request, b"shared secret");
+        assert!(!request.verify_delayed(&packet, &keys, None));
+    }
+
+    #[test]
+    fn replayed_delayed_authentication_is_rejected() {
+        let mut keys = KeyStore::new();
+        keys.insert(1, b"shared secret".to_vec());
+        let mut replay_detection = ReplayDetection::new();
+
+        let mut request = message(MessageType::DhcpRequest);
+        request.options.authentication = Some(Authentication::delayed_hmac_md5(2, 1));
+        let packet = signed(&request, b"shared secret");
+        assert!(request.verify_delayed(&packet, &keys, replay_detection.last(&1)));
+        replay_detection.record(1, &request);
+        assert_eq!(replay_detection.last(&1), Some(2));
+
+        let last = replay_detection.last(&1);
+        assert!(!request.verify_delayed(&packet, &keys, last));
+        assert!(!request.is_authentic(&packet, AuthenticationMode::Prefer, &keys, last));
+        // the other peers are tracked separately
+        assert!(request.verify_delayed(&packet, &keys, replay_detection.last(&2)));
+
+        request.options.authentication = Some(Authentication::delayed_hmac_md5(3, 1));
+        let packet = signed(&request, b"shared secret");
+        assert!(request.verify_delayed(&packet, &keys, last));
+    }
+}
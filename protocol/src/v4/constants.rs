@@ -35,3 +35,6 @@ pub const SIZE_HEADER_UDP: usize = 8;
 
 /// The minimal message size the client MUST be able to accept.
 pub const SIZE_MESSAGE_MINIMAL: usize = 576;
+
+/// The minimal number of seconds the client stops DHCPv4 for (RFC 8925 `MIN_V6ONLY_WAIT`).
+pub const V6ONLY_WAIT_MINIMAL: u32 = 300;
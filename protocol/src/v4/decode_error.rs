@@ -0,0 +1,113 @@
+//! DHCP message decoding error module.
+
+use std::{fmt, io};
+
+/// The message area an option was found in.
+///
+/// [RFC 2131 §4.1](https://tools.ietf.org/html/rfc2131#section-4.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// The `options` field.
+    Main,
+    /// The `sname` field overloaded with options.
+    Sname,
+    /// The `file` field overloaded with options.
+    File,
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Region::Main => write!(f, "options"),
+            Region::Sname => write!(f, "sname"),
+            Region::File => write!(f, "file"),
+        }
+    }
+}
+
+/// The length an option value must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedLength {
+    Exactly(usize),
+    AtLeast(usize),
+    /// A non-zero multiple of the element size.
+    MultipleOf(usize),
+}
+
+impl fmt::Display for ExpectedLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpectedLength::Exactly(len) => write!(f, "exactly {}", len),
+            ExpectedLength::AtLeast(len) => write!(f, "at least {}", len),
+            ExpectedLength::MultipleOf(len) => write!(f, "a multiple of {}", len),
+        }
+    }
+}
+
+/// The error type returned by `Message::from_bytes` and `MessageRef`.
+///
+/// The offsets are counted from the start of the packet. An option split into
+/// several instances (RFC 3396) is reported at its first instance.
+#[derive(Fail, Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    #[fail(
+        display = "Packet is too small: {} bytes, at least {} expected",
+        actual, expected
+    )]
+    TooSmall { expected: usize, actual: usize },
+    #[fail(
+        display = "Hardware address length {} at offset {} is invalid",
+        actual, offset
+    )]
+    HardwareAddressLength { offset: usize, actual: usize },
+    #[fail(
+        display = "Magic cookie {:#010x} at offset {} is invalid",
+        actual, offset
+    )]
+    MagicCookie { offset: usize, actual: u32 },
+    /// The length octet and the value do not fit the area.
+    #[fail(
+        display = "Option {} at offset {} in {} is abrupted: {} bytes expected, {} left",
+        tag, offset, region, expected, actual
+    )]
+    OptionAbrupted {
+        region: Region,
+        offset: usize,
+        tag: u8,
+        expected: usize,
+        actual: usize,
+    },
+    #[fail(
+        display = "Option {} at offset {} in {} has length {}, {} expected",
+        tag, offset, region, actual, expected
+    )]
+    OptionLength {
+        region: Region,
+        offset: usize,
+        tag: u8,
+        expected: ExpectedLength,
+        actual: usize,
+    },
+    #[fail(
+        display = "Option {} at offset {} in {} is invalid: {}",
+        tag, offset, region, reason
+    )]
+    OptionValue {
+        region: Region,
+        offset: usize,
+        tag: u8,
+        reason: String,
+    },
+}
+
+impl From<DecodeError> for io::Error {
+    fn from(error: DecodeError) -> Self {
+        let kind = match error {
+            DecodeError::TooSmall { .. } | DecodeError::OptionAbrupted { .. } => {
+                io::ErrorKind::UnexpectedEof
+            }
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error.to_string())
+    }
+}
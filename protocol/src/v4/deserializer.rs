@@ -3,22 +3,24 @@
 use std::{io, mem, net::Ipv4Addr};
 
 use bytes::Buf;
-use eui48::{EUI48LEN, MacAddress};
 
 use super::{
-    constants::*,
-    options::{OptionTag::*, Options, Overload},
-    Message,
+    decode_error::{DecodeError, ExpectedLength, Region},
+    options::{
+        Authentication as AuthenticationStruct, ClientArchitecture,
+        ClientFqdn as ClientFqdnStruct, DomainName, MachineIdentifier,
+        NetworkInterfaceIdentifier, OptionRegistry, OptionTag::*, Options,
+        RelayAgentInformation as RelayAgentInformationStruct, SipServers as SipServersStruct,
+        UserClass as UserClassStruct, VendorClassData, VendorSpecificData,
+    },
+    HardwareAddress, Message, MessageRef,
 };
 
-/// Checks if there is enough space in buffer to get a value.
+/// Checks if there is enough space in the option value to get an element.
 macro_rules! check_remaining(
     ($cursor:expr, $length:expr) => (
         if $cursor.remaining() < $length {
-            return Err(
-                io::Error::new(io::ErrorKind::UnexpectedEof,
-                "Buffer is too small or packet has invalid length octets",
-            ));
+            return Err(ValueError::Invalid("Option value element is abrupted".to_owned()));
         }
     );
 );
@@ -27,12 +29,12 @@ macro_rules! check_remaining(
 macro_rules! check_length(
     ($len:expr) => (
         if $len == 0 {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Length octet is zero"));
+            return Err(ValueError::Length(ExpectedLength::AtLeast(1), $len));
         }
     );
     ($len:expr, $correct:expr) => (
         if $len != $correct {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Length octet is invalid"));
+            return Err(ValueError::Length(ExpectedLength::Exactly($correct), $len));
         }
     );
 );
@@ -41,621 +43,535 @@ macro_rules! check_length(
 macro_rules! check_divisibility(
     ($len:expr, $divider:expr) => (
         if $len % $divider != 0 {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Divisibility check failed"));
+            return Err(ValueError::Length(ExpectedLength::MultipleOf($divider), $len));
         }
     );
 );
 
-/// A range from the current cursor position to the specified distance.
-macro_rules! distance(
-    ($cursor:expr, $distance:expr) => (
-        ($cursor.position() as usize)..(($cursor.position() as usize) + $distance)
-    );
-);
+/// An option with its instances concatenated.
+struct RawOption {
+    tag: u8,
+    /// The region of the first instance.
+    region: Region,
+    /// The packet offset of the first instance.
+    offset: usize,
+    value: Vec<u8>,
+}
 
-impl Message {
-    /// DHCP message deserialization.
+/// The option value error which lacks the option context.
+enum ValueError {
+    Length(ExpectedLength, usize),
+    /// Reported by the option structure decoders.
+    Invalid(String),
+}
+
+impl ValueError {
+    fn into_decode_error(self, option: &RawOption) -> DecodeError {
+        match self {
+            ValueError::Length(expected, actual) => DecodeError::OptionLength {
+                region: option.region,
+                offset: option.offset,
+                tag: option.tag,
+                expected,
+                actual,
+            },
+            ValueError::Invalid(reason) => DecodeError::OptionValue {
+                region: option.region,
+                offset: option.offset,
+                tag: option.tag,
+                reason,
+            },
+        }
+    }
+}
+
+impl From<io::Error> for ValueError {
+    fn from(error: io::Error) -> Self {
+        ValueError::Invalid(error.to_string())
+    }
+}
+
+impl<'a> MessageRef<'a> {
+    /// Decodes the options and copies the fields into the owned message.
+    ///
+    /// # Errors
+    /// `DecodeError` on the first abrupted or invalid option.
+    pub fn to_owned(&self) -> Result<Message, DecodeError> {
+        self.decode(false, None).map(|(message, _)| message)
+    }
+
+    /// Like `to_owned`, but decodes the user-defined options registered in `registry`.
     ///
     /// # Errors
-    /// `io::Error` if the packet is abrupted, too small or contains invalid length octets.
-    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
-        let mut cursor = ::std::io::Cursor::new(src.as_ref());
-        check_remaining!(cursor, OFFSET_OPTIONS);
+    /// `DecodeError` on the first abrupted or invalid option.
+    pub fn to_owned_with(&self, registry: &OptionRegistry) -> Result<Message, DecodeError> {
+        self.decode(false, Some(registry)).map(|(message, _)| message)
+    }
+
+    /// Decodes the message skipping the abrupted and invalid options.
+    ///
+    /// Returns the message along with the errors of the skipped options,
+    /// which is useful for troubleshooting buggy clients and relay agents.
+    /// An abrupted option makes the rest of its area to be skipped.
+    pub fn to_owned_lenient(&self) -> (Message, Vec<DecodeError>) {
+        self.decode(true, None)
+            .expect("The lenient decoding does not fail after the header is checked")
+    }
 
+    /// Like `to_owned_lenient`, but decodes the user-defined options registered in `registry`.
+    pub fn to_owned_lenient_with(&self, registry: &OptionRegistry) -> (Message, Vec<DecodeError>) {
+        self.decode(true, Some(registry))
+            .expect("The lenient decoding does not fail after the header is checked")
+    }
+
+    fn decode(
+        &self,
+        is_lenient: bool,
+        registry: Option<&OptionRegistry>,
+    ) -> Result<(Message, Vec<DecodeError>), DecodeError> {
         let mut message = Message {
-            operation_code: cursor.get_u8().into(),
-            hardware_type: cursor.get_u8().into(),
-            hardware_address_length: cursor.get_u8(),
-            hardware_options: cursor.get_u8(),
-            transaction_id: cursor.get_u32_be(),
-            seconds: cursor.get_u16_be(),
-            // https://tools.ietf.org/html/rfc2131#section-2
-            // https://tools.ietf.org/html/rfc1700#page-3
-            // Leftmost bit (0 bit) is most significant
-            is_broadcast: cursor.get_u16_be() & 0x8000 != 0,
-            client_ip_address: Ipv4Addr::from(cursor.get_u32_be()),
-            your_ip_address: Ipv4Addr::from(cursor.get_u32_be()),
-            server_ip_address: Ipv4Addr::from(cursor.get_u32_be()),
-            gateway_ip_address: Ipv4Addr::from(cursor.get_u32_be()),
-            client_hardware_address: match MacAddress::from_bytes(&src[distance!(cursor, EUI48LEN)])
-            {
-                Ok(address) => {
-                    cursor.advance(SIZE_HARDWARE_ADDRESS);
-                    address
-                }
-                Err(_) => panic!("MacAddress::from_bytes must always succeed"),
-            },
-            server_name: {
-                let vec = Vec::from(&src[distance!(cursor, SIZE_SERVER_NAME)]);
-                cursor.advance(SIZE_SERVER_NAME);
-                vec
-            },
-            boot_filename: {
-                let vec = Vec::from(&src[distance!(cursor, SIZE_BOOT_FILENAME)]);
-                cursor.advance(SIZE_BOOT_FILENAME);
-                vec
-            },
+            operation_code: self.operation_code(),
+            hardware_type: self.hardware_type(),
+            hardware_options: self.hardware_options(),
+            transaction_id: self.transaction_id(),
+            seconds: self.seconds(),
+            is_broadcast: self.is_broadcast(),
+            client_ip_address: self.client_ip_address(),
+            your_ip_address: self.your_ip_address(),
+            server_ip_address: self.server_ip_address(),
+            gateway_ip_address: self.gateway_ip_address(),
+            // the length is checked by `MessageRef::new`
+            client_hardware_address: HardwareAddress::new(self.client_hardware_address())
+                .unwrap_or_default(),
+            server_name: self.server_name().to_vec(),
+            boot_filename: self.boot_filename().to_vec(),
             options: Options::default(),
         };
+        let mut errors = Vec::new();
 
-        if cursor.get_u32_be() != MAGIC_COOKIE {
-            return Err(io::Error::new(io::ErrorKind::InvalidData, "MAGIC_COOKIE"));
+        // the option instances are concatenated in the order of the areas as per RFC 3396
+        // and kept in the order of their first occurrence
+        let mut raw: Vec<RawOption> = Vec::new();
+        let mut iter = self.options();
+        while let Some(option) = iter.next() {
+            let (tag, value) = match option {
+                Ok(option) => option,
+                Err(error) => {
+                    if !is_lenient {
+                        return Err(error);
+                    }
+                    errors.push(error);
+                    continue;
+                }
+            };
+            if let Some(option) = raw.iter_mut().find(|option| option.tag == tag) {
+                option.value.extend_from_slice(value);
+                continue;
+            }
+            raw.push(RawOption {
+                tag,
+                region: iter.region(),
+                offset: iter.offset(),
+                value: value.to_vec(),
+            });
         }
 
-        Self::append_options(&mut cursor, &mut message.options)?;
-        match message.options.overload {
-            Some(Overload::File) => {
-                let mut cursor =
-                    ::std::io::Cursor::new(&src[OFFSET_BOOT_FILENAME..OFFSET_MAGIC_COOKIE]);
-                Self::append_options(&mut cursor, &mut message.options)?;
-            }
-            Some(Overload::Sname) => {
-                let mut cursor =
-                    ::std::io::Cursor::new(&src[OFFSET_SERVER_NAME..OFFSET_BOOT_FILENAME]);
-                Self::append_options(&mut cursor, &mut message.options)?;
-            }
-            Some(Overload::Both) => {
-                let mut cursor =
-                    ::std::io::Cursor::new(&src[OFFSET_BOOT_FILENAME..OFFSET_MAGIC_COOKIE]);
-                Self::append_options(&mut cursor, &mut message.options)?;
-                let mut cursor =
-                    ::std::io::Cursor::new(&src[OFFSET_SERVER_NAME..OFFSET_BOOT_FILENAME]);
-                Self::append_options(&mut cursor, &mut message.options)?;
-            }
-            _ => {}
+        for option in raw.iter() {
+            let result =
+                Message::append_option(option.tag, &option.value, registry, &mut message.options);
+            match result {
+                Ok(()) => message.options.order.push(option.tag),
+                Err(error) => {
+                    let error = error.into_decode_error(option);
+                    if !is_lenient {
+                        return Err(error);
+                    }
+                    errors.push(error);
+                }
+            }
         }
+        Ok((message, errors))
+    }
+}
 
-        Ok(message)
+impl Message {
+    /// DHCP message deserialization.
+    ///
+    /// Decodes the borrowed `MessageRef` view into the owned message.
+    ///
+    /// # Errors
+    /// `DecodeError` if the packet is abrupted, too small or contains invalid options.
+    pub fn from_bytes(src: &[u8]) -> Result<Self, DecodeError> {
+        MessageRef::new(src)?.to_owned()
     }
 
-    fn append_options(mut cursor: &mut io::Cursor<&[u8]>, options: &mut Options) -> io::Result<()> {
-        while cursor.remaining() > 0 {
-            check_remaining!(cursor, mem::size_of::<u8>());
-            let tag = cursor.get_u8();
-            match tag.into() {
-                // unsplittable options
-                TimeOffset => options.time_offset = Some(Self::get_opt_u32(&mut cursor)?),
-                SubnetMask => options.subnet_mask = Some(Self::get_opt_ipv4(&mut cursor)?),
-                BootFileSize => options.boot_file_size = Some(Self::get_opt_u16(&mut cursor)?),
-                SwapServer => options.swap_server = Some(Self::get_opt_ipv4(&mut cursor)?),
-                ForwardOnOff => options.forward_on_off = Some(Self::get_opt_u8(&mut cursor)?),
-                NonLocalSourceRouteOnOff => {
-                    options.non_local_source_route_on_off = Some(Self::get_opt_u8(&mut cursor)?)
-                }
-                MaxDatagramReassemblySize => {
-                    options.max_datagram_reassembly_size = Some(Self::get_opt_u16(&mut cursor)?)
-                }
-                DefaultIpTtl => options.default_ip_ttl = Some(Self::get_opt_u8(&mut cursor)?),
-                MtuTimeout => options.mtu_timeout = Some(Self::get_opt_u32(&mut cursor)?),
-                MtuInterface => options.mtu_interface = Some(Self::get_opt_u16(&mut cursor)?),
-                MtuSubnet => options.mtu_subnet = Some(Self::get_opt_u8(&mut cursor)?),
-                BroadcastAddress => {
-                    options.broadcast_address = Some(Self::get_opt_ipv4(&mut cursor)?)
-                }
-                MaskRecovery => options.mask_recovery = Some(Self::get_opt_u8(&mut cursor)?),
-                MaskSupplier => options.mask_supplier = Some(Self::get_opt_u8(&mut cursor)?),
-                PerformRouterDiscovery => {
-                    options.perform_router_discovery = Some(Self::get_opt_u8(&mut cursor)?)
-                }
-                RouterSolicitationAddress => {
-                    options.router_solicitation_address = Some(Self::get_opt_ipv4(&mut cursor)?)
-                }
-                TrailerEncapsulation => {
-                    options.trailer_encapsulation = Some(Self::get_opt_u8(&mut cursor)?)
-                }
-                ArpTimeout => options.arp_timeout = Some(Self::get_opt_u32(&mut cursor)?),
-                EthernetEncapsulation => {
-                    options.ethernet_encapsulation = Some(Self::get_opt_u8(&mut cursor)?)
-                }
-                DefaultTcpTtl => options.default_tcp_ttl = Some(Self::get_opt_u8(&mut cursor)?),
-                KeepaliveTime => options.keepalive_time = Some(Self::get_opt_u32(&mut cursor)?),
-                KeepaliveData => options.keepalive_data = Some(Self::get_opt_u8(&mut cursor)?),
-                NetbiosNodeType => options.netbios_node_type = Some(Self::get_opt_u8(&mut cursor)?),
-                AddressRequest => options.address_request = Some(Self::get_opt_ipv4(&mut cursor)?),
-                AddressTime => options.address_time = Some(Self::get_opt_u32(&mut cursor)?),
-                Overload => options.overload = Some(Self::get_opt_u8(&mut cursor)?.into()),
-                DhcpMessageType => {
-                    options.dhcp_message_type = Some(Self::get_opt_u8(&mut cursor)?.into())
-                }
-                DhcpServerId => options.dhcp_server_id = Some(Self::get_opt_ipv4(&mut cursor)?),
-                DhcpMaxMessageSize => {
-                    options.dhcp_max_message_size = Some(Self::get_opt_u16(&mut cursor)?)
-                }
-                RenewalTime => options.renewal_time = Some(Self::get_opt_u32(&mut cursor)?),
-                RebindingTime => options.rebinding_time = Some(Self::get_opt_u32(&mut cursor)?),
+    /// DHCP message deserialization with the user-defined options.
+    ///
+    /// The options registered in `registry` are decoded into `Options::custom`.
+    ///
+    /// # Errors
+    /// `DecodeError` if the packet is abrupted, too small or contains invalid options.
+    pub fn from_bytes_with(src: &[u8], registry: &OptionRegistry) -> Result<Self, DecodeError> {
+        MessageRef::new(src)?.to_owned_with(registry)
+    }
 
-                // splittable options
-                Routers => {
-                    options.routers =
-                        Some(Self::get_opt_vec_ipv4(&mut cursor, &mut options.routers)?)
-                }
-                TimeServers => {
-                    options.time_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.time_servers,
-                    )?)
-                }
-                NameServers => {
-                    options.name_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.name_servers,
-                    )?)
-                }
-                DomainNameServers => {
-                    options.domain_name_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.domain_name_servers,
-                    )?)
-                }
-                LogServers => {
-                    options.log_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.log_servers,
-                    )?)
-                }
-                QuotesServers => {
-                    options.quotes_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.quotes_servers,
-                    )?)
-                }
-                LprServers => {
-                    options.lpr_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.lpr_servers,
-                    )?)
-                }
-                ImpressServers => {
-                    options.impress_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.impress_servers,
-                    )?)
-                }
-                RlpServers => {
-                    options.rlp_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.rlp_servers,
-                    )?)
-                }
-                Hostname => {
-                    options.hostname =
-                        Some(Self::get_opt_string(&mut cursor, &mut options.hostname)?)
-                }
-                MeritDumpFile => {
-                    options.merit_dump_file = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.merit_dump_file,
-                    )?)
-                }
-                DomainName => {
-                    options.domain_name =
-                        Some(Self::get_opt_string(&mut cursor, &mut options.domain_name)?)
-                }
-                RootPath => {
-                    options.root_path =
-                        Some(Self::get_opt_string(&mut cursor, &mut options.root_path)?)
-                }
-                ExtensionsPath => {
-                    options.extensions_path = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.extensions_path,
-                    )?)
-                }
-                PolicyFilters => {
-                    options.policy_filters = Some(Self::get_opt_vec_ipv4_pairs(
-                        &mut cursor,
-                        &mut options.policy_filters,
-                    )?)
-                }
-                MtuPlateau => {
-                    options.mtu_plateau = Some(Self::get_opt_vec_u16(
-                        &mut cursor,
-                        &mut options.mtu_plateau,
-                    )?)
-                }
-                StaticRoutes => {
-                    options.static_routes = Some(Self::get_opt_vec_ipv4_pairs(
-                        &mut cursor,
-                        &mut options.static_routes,
-                    )?)
-                }
-                NisDomain => {
-                    options.nis_domain =
-                        Some(Self::get_opt_string(&mut cursor, &mut options.nis_domain)?)
-                }
-                NisServers => {
-                    options.nis_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.nis_servers,
-                    )?)
-                }
-                NtpServers => {
-                    options.ntp_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.ntp_servers,
-                    )?)
-                }
-                VendorSpecific => {
-                    options.vendor_specific = Some(Self::get_opt_vec(
-                        &mut cursor,
-                        &mut options.vendor_specific,
-                    )?)
-                }
-                NetbiosNameServers => {
-                    options.netbios_name_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.netbios_name_servers,
-                    )?)
-                }
-                NetbiosDistributionServers => {
-                    options.netbios_distribution_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.netbios_distribution_servers,
-                    )?)
-                }
-                NetbiosScope => {
-                    options.netbios_scope = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.netbios_scope,
-                    )?)
-                }
-                XWindowFontServers => {
-                    options.x_window_font_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.x_window_font_servers,
-                    )?)
-                }
-                XWindowManagerServers => {
-                    options.x_window_manager_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.x_window_manager_servers,
-                    )?)
-                }
-                ParameterList => {
-                    options.parameter_list =
-                        Some(Self::get_opt_vec(&mut cursor, &mut options.parameter_list)?)
-                }
-                DhcpMessage => {
-                    options.dhcp_message = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.dhcp_message,
-                    )?)
-                }
-                ClassId => {
-                    options.class_id = Some(Self::get_opt_vec(&mut cursor, &mut options.class_id)?)
-                }
-                ClientId => {
-                    options.client_id =
-                        Some(Self::get_opt_vec(&mut cursor, &mut options.client_id)?)
-                }
-                NetwareIpDomain => {
-                    options.netware_ip_domain = Some(Self::get_opt_vec(
-                        &mut cursor,
-                        &mut options.netware_ip_domain,
-                    )?)
-                }
-                NetwareIpOption => {
-                    options.netware_ip_option = Some(Self::get_opt_vec(
-                        &mut cursor,
-                        &mut options.netware_ip_option,
-                    )?)
-                }
-                NisDomainName => {
-                    options.nis_v3_domain_name = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.nis_v3_domain_name,
-                    )?)
-                }
-                NisServerAddress => {
-                    options.nis_v3_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.nis_v3_servers,
-                    )?)
-                }
-                ServerName => {
-                    options.server_name =
-                        Some(Self::get_opt_string(&mut cursor, &mut options.server_name)?)
-                }
-                BootfileName => {
-                    options.bootfile_name = Some(Self::get_opt_string(
-                        &mut cursor,
-                        &mut options.bootfile_name,
-                    )?)
-                }
-                HomeAgentAddresses => {
-                    options.home_agent_addresses = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.home_agent_addresses,
-                    )?)
-                }
-                SmtpServers => {
-                    options.smtp_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.smtp_servers,
-                    )?)
-                }
-                Pop3Servers => {
-                    options.pop3_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.pop3_servers,
-                    )?)
-                }
-                NntpServers => {
-                    options.nntp_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.nntp_servers,
-                    )?)
-                }
-                WwwServers => {
-                    options.www_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.www_servers,
-                    )?)
-                }
-                FingerServers => {
-                    options.finger_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.finger_servers,
-                    )?)
-                }
-                IrcServers => {
-                    options.irc_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.irc_servers,
-                    )?)
-                }
-                StreetTalkServers => {
-                    options.street_talk_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.street_talk_servers,
-                    )?)
-                }
-                StdaServers => {
-                    options.stda_servers = Some(Self::get_opt_vec_ipv4(
-                        &mut cursor,
-                        &mut options.stda_servers,
-                    )?)
-                }
-                ClasslessStaticRoutes => {
-                    options.classless_static_routes = Some(Self::get_opt_classless_static_routes(
-                        &mut cursor,
-                        &mut options.classless_static_routes,
-                    )?)
-                }
+    /// DHCP message deserialization skipping the invalid options.
+    ///
+    /// See `MessageRef::to_owned_lenient`.
+    ///
+    /// # Errors
+    /// `DecodeError` if the fixed header or the magic cookie is invalid.
+    pub fn from_bytes_lenient(src: &[u8]) -> Result<(Self, Vec<DecodeError>), DecodeError> {
+        Ok(MessageRef::new(src)?.to_owned_lenient())
+    }
+
+    fn append_option(
+        tag: u8,
+        value: &[u8],
+        registry: Option<&OptionRegistry>,
+        options: &mut Options,
+    ) -> Result<(), ValueError> {
+        match tag.into() {
+            // unsplittable options
+            TimeOffset => options.time_offset = Some(Self::get_opt_u32(value)?),
+            SubnetMask => options.subnet_mask = Some(Self::get_opt_ipv4(value)?),
+            BootFileSize => options.boot_file_size = Some(Self::get_opt_u16(value)?),
+            SwapServer => options.swap_server = Some(Self::get_opt_ipv4(value)?),
+            ForwardOnOff => options.forward_on_off = Some(Self::get_opt_u8(value)?),
+            NonLocalSourceRouteOnOff => {
+                options.non_local_source_route_on_off = Some(Self::get_opt_u8(value)?)
+            }
+            MaxDatagramReassemblySize => {
+                options.max_datagram_reassembly_size = Some(Self::get_opt_u16(value)?)
+            }
+            DefaultIpTtl => options.default_ip_ttl = Some(Self::get_opt_u8(value)?),
+            MtuTimeout => options.mtu_timeout = Some(Self::get_opt_u32(value)?),
+            MtuInterface => options.mtu_interface = Some(Self::get_opt_u16(value)?),
+            MtuSubnet => options.mtu_subnet = Some(Self::get_opt_u8(value)?),
+            BroadcastAddress => options.broadcast_address = Some(Self::get_opt_ipv4(value)?),
+            MaskRecovery => options.mask_recovery = Some(Self::get_opt_u8(value)?),
+            MaskSupplier => options.mask_supplier = Some(Self::get_opt_u8(value)?),
+            PerformRouterDiscovery => {
+                options.perform_router_discovery = Some(Self::get_opt_u8(value)?)
+            }
+            RouterSolicitationAddress => {
+                options.router_solicitation_address = Some(Self::get_opt_ipv4(value)?)
+            }
+            TrailerEncapsulation => {
+                options.trailer_encapsulation = Some(Self::get_opt_u8(value)?)
+            }
+            ArpTimeout => options.arp_timeout = Some(Self::get_opt_u32(value)?),
+            EthernetEncapsulation => {
+                options.ethernet_encapsulation = Some(Self::get_opt_u8(value)?)
+            }
+            DefaultTcpTtl => options.default_tcp_ttl = Some(Self::get_opt_u8(value)?),
+            KeepaliveTime => options.keepalive_time = Some(Self::get_opt_u32(value)?),
+            KeepaliveData => options.keepalive_data = Some(Self::get_opt_u8(value)?),
+            NetbiosNodeType => options.netbios_node_type = Some(Self::get_opt_u8(value)?),
+            AddressRequest => options.address_request = Some(Self::get_opt_ipv4(value)?),
+            AddressTime => options.address_time = Some(Self::get_opt_u32(value)?),
+            Overload => options.overload = Some(Self::get_opt_u8(value)?.into()),
+            DhcpMessageType => {
+                options.dhcp_message_type = Some(Self::get_opt_u8(value)?.into())
+            }
+            DhcpServerId => options.dhcp_server_id = Some(Self::get_opt_ipv4(value)?),
+            DhcpMaxMessageSize => {
+                options.dhcp_max_message_size = Some(Self::get_opt_u16(value)?)
+            }
+            RenewalTime => options.renewal_time = Some(Self::get_opt_u32(value)?),
+            RebindingTime => options.rebinding_time = Some(Self::get_opt_u32(value)?),
 
-                End => break,
-                Pad => continue,
-                Unknown => Self::skip(&mut cursor)?,
+            // splittable options
+            Routers => options.routers = Some(Self::get_opt_vec_ipv4(value)?),
+            TimeServers => options.time_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            NameServers => options.name_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            DomainNameServers => {
+                options.domain_name_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            LogServers => options.log_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            QuotesServers => options.quotes_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            LprServers => options.lpr_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            ImpressServers => options.impress_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            RlpServers => options.rlp_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            Hostname => options.hostname = Some(Self::get_opt_string(value)?),
+            MeritDumpFile => options.merit_dump_file = Some(Self::get_opt_string(value)?),
+            DomainName => options.domain_name = Some(Self::get_opt_string(value)?),
+            RootPath => options.root_path = Some(Self::get_opt_string(value)?),
+            ExtensionsPath => options.extensions_path = Some(Self::get_opt_string(value)?),
+            PolicyFilters => {
+                options.policy_filters = Some(Self::get_opt_vec_ipv4_pairs(value)?)
+            }
+            MtuPlateau => options.mtu_plateau = Some(Self::get_opt_vec_u16(value)?),
+            StaticRoutes => options.static_routes = Some(Self::get_opt_vec_ipv4_pairs(value)?),
+            NisDomain => options.nis_domain = Some(Self::get_opt_string(value)?),
+            NisServers => options.nis_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            NtpServers => options.ntp_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            VendorSpecific => options.vendor_specific = Some(Self::get_opt_vec(value)?),
+            NetbiosNameServers => {
+                options.netbios_name_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            NetbiosDistributionServers => {
+                options.netbios_distribution_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            NetbiosScope => options.netbios_scope = Some(Self::get_opt_string(value)?),
+            XWindowFontServers => {
+                options.x_window_font_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            XWindowManagerServers => {
+                options.x_window_manager_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            ParameterList => options.parameter_list = Some(Self::get_opt_vec(value)?),
+            DhcpMessage => options.dhcp_message = Some(Self::get_opt_string(value)?),
+            ClassId => options.class_id = Some(Self::get_opt_vec(value)?),
+            ClientId => options.client_id = Some(Self::get_opt_vec(value)?),
+            NetwareIpDomain => options.netware_ip_domain = Some(Self::get_opt_vec(value)?),
+            NetwareIpOption => options.netware_ip_option = Some(Self::get_opt_vec(value)?),
+            NisDomainName => options.nis_v3_domain_name = Some(Self::get_opt_string(value)?),
+            NisServerAddress => options.nis_v3_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            ServerName => options.server_name = Some(Self::get_opt_string(value)?),
+            BootfileName => options.bootfile_name = Some(Self::get_opt_string(value)?),
+            HomeAgentAddresses => {
+                options.home_agent_addresses = Some(Self::get_opt_vec_ipv4(value)?)
             }
+            SmtpServers => options.smtp_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            Pop3Servers => options.pop3_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            NntpServers => options.nntp_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            WwwServers => options.www_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            FingerServers => options.finger_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            IrcServers => options.irc_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            StreetTalkServers => {
+                options.street_talk_servers = Some(Self::get_opt_vec_ipv4(value)?)
+            }
+            StdaServers => options.stda_servers = Some(Self::get_opt_vec_ipv4(value)?),
+            UserClass => options.user_class = Some(UserClassStruct::from_bytes(value)?),
+            RapidCommit => {
+                check_length!(value.len(), 0);
+                options.rapid_commit = Some(());
+            }
+            ClientFqdn => options.client_fqdn = Some(ClientFqdnStruct::from_bytes(value)?),
+            RelayAgentInformation => {
+                options.relay_agent_information =
+                    Some(RelayAgentInformationStruct::from_bytes(value)?)
+            }
+            Authentication => {
+                options.authentication = Some(AuthenticationStruct::from_bytes(value)?)
+            }
+            ClientLastTransactionTime => {
+                options.client_last_transaction_time = Some(Self::get_opt_u32(value)?)
+            }
+            AssociatedIp => options.associated_ip = Some(Self::get_opt_vec_ipv4(value)?),
+            ClientSystemArchitecture => {
+                options.client_system_architecture = Some(
+                    Self::get_opt_vec_u16(value)?
+                        .into_iter()
+                        .map(ClientArchitecture::from)
+                        .collect(),
+                )
+            }
+            ClientNetworkInterfaceIdentifier => {
+                options.client_network_interface_identifier =
+                    Some(NetworkInterfaceIdentifier::from_bytes(value)?)
+            }
+            ClientMachineIdentifier => {
+                options.client_machine_identifier = Some(MachineIdentifier::from_bytes(value)?)
+            }
+            Ipv6OnlyPreferred => options.ipv6_only_preferred = Some(Self::get_opt_u32(value)?),
+            CaptivePortal => options.captive_portal = Some(Self::get_opt_string(value)?),
+            SubnetSelection => options.subnet_selection = Some(Self::get_opt_ipv4(value)?),
+            DomainSearch => {
+                options.domain_search = Some(DomainName::from_compressed_list(value)?)
+            }
+            SipServers => options.sip_servers = Some(SipServersStruct::from_bytes(value)?),
+            ClasslessStaticRoutes => {
+                options.classless_static_routes =
+                    Some(Self::get_opt_classless_static_routes(value)?)
+            }
+            ForcerenewNonceCapable => {
+                options.forcerenew_nonce_capable = Some(Self::get_opt_vec(value)?)
+            }
+            ViVendorClass => {
+                options.vi_vendor_class = Some(VendorClassData::from_bytes(value)?)
+            }
+            ViVendorSpecific => {
+                options.vi_vendor_specific = Some(VendorSpecificData::from_bytes(value)?)
+            }
+
+            End | Pad => {}
+            Unknown => match registry.and_then(|registry| registry.decoder(tag)) {
+                Some(decoder) => options.custom.push((tag, decoder(value)?)),
+                None => options.unknown.push((tag, value.to_vec())),
+            },
         }
         Ok(())
     }
 
-    /// Cannot be splitted so reassembling not required.
-    fn get_opt_u8(cursor: &mut io::Cursor<&[u8]>) -> io::Result<u8> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len, mem::size_of::<u8>());
-        check_remaining!(cursor, len);
-        let value = cursor.get_u8();
-        Ok(value)
+    fn get_opt_u8(value: &[u8]) -> Result<u8, ValueError> {
+        check_length!(value.len(), mem::size_of::<u8>());
+        Ok(value[0])
     }
 
-    /// Cannot be splitted so reassembling not required.
-    fn get_opt_u16(cursor: &mut io::Cursor<&[u8]>) -> io::Result<u16> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len, mem::size_of::<u16>());
-        check_remaining!(cursor, len);
-        let value = cursor.get_u16_be();
-        Ok(value)
+    fn get_opt_u16(value: &[u8]) -> Result<u16, ValueError> {
+        check_length!(value.len(), mem::size_of::<u16>());
+        Ok(io::Cursor::new(value).get_u16_be())
     }
 
-    /// Cannot be splitted so reassembling not required.
-    fn get_opt_u32(cursor: &mut io::Cursor<&[u8]>) -> io::Result<u32> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len, mem::size_of::<u32>());
-        check_remaining!(cursor, len);
-        let value = cursor.get_u32_be();
-        Ok(value)
+    fn get_opt_u32(value: &[u8]) -> Result<u32, ValueError> {
+        check_length!(value.len(), mem::size_of::<u32>());
+        Ok(io::Cursor::new(value).get_u32_be())
     }
 
-    /// Cannot be splitted so reassembling not required.
-    fn get_opt_ipv4(cursor: &mut io::Cursor<&[u8]>) -> io::Result<Ipv4Addr> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len, mem::size_of::<u32>());
-        check_remaining!(cursor, len);
-        let value = cursor.get_u32_be();
-        Ok(Ipv4Addr::from(value))
+    fn get_opt_ipv4(value: &[u8]) -> Result<Ipv4Addr, ValueError> {
+        check_length!(value.len(), mem::size_of::<u32>());
+        Ok(Ipv4Addr::from(io::Cursor::new(value).get_u32_be()))
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
-    fn get_opt_string(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<String>,
-    ) -> io::Result<String> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
-        check_remaining!(cursor, len);
-        let value = String::from_utf8_lossy(&cursor.bytes()[..len]).to_string();
-        cursor.advance(len);
-        if let Some(ref mut data) = option {
-            Ok(data.to_owned() + value.as_ref())
-        } else {
-            Ok(value)
-        }
+    fn get_opt_string(value: &[u8]) -> Result<String, ValueError> {
+        check_length!(value.len());
+        Ok(String::from_utf8_lossy(value).to_string())
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
-    fn get_opt_vec(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<Vec<u8>>,
-    ) -> io::Result<Vec<u8>> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
-        check_remaining!(cursor, len);
-        let mut value: Vec<u8> = cursor.bytes()[..len].to_vec();
-        cursor.advance(len);
-        if let Some(ref mut data) = option {
-            data.append(value.as_mut());
-            Ok(data.to_owned())
-        } else {
-            Ok(value)
-        }
+    fn get_opt_vec(value: &[u8]) -> Result<Vec<u8>, ValueError> {
+        check_length!(value.len());
+        Ok(value.to_vec())
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
-    fn get_opt_vec_u16(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<Vec<u16>>,
-    ) -> io::Result<Vec<u16>> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
+    fn get_opt_vec_u16(value: &[u8]) -> Result<Vec<u16>, ValueError> {
+        check_length!(value.len());
         let element_size = mem::size_of::<u16>();
-        check_divisibility!(len, element_size);
-        check_remaining!(cursor, len);
-        let amount = len / element_size;
-        let mut value = Vec::with_capacity(amount);
-        for _ in 0..amount {
-            check_remaining!(cursor, element_size);
-            value.push(cursor.get_u16_be());
-        }
-        if let Some(ref mut data) = option {
-            data.append(value.as_mut());
-            Ok(data.to_owned())
-        } else {
-            Ok(value)
+        check_divisibility!(value.len(), element_size);
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / element_size);
+        while cursor.remaining() > 0 {
+            result.push(cursor.get_u16_be());
         }
+        Ok(result)
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
-    fn get_opt_vec_ipv4(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<Vec<Ipv4Addr>>,
-    ) -> io::Result<Vec<Ipv4Addr>> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
+    fn get_opt_vec_ipv4(value: &[u8]) -> Result<Vec<Ipv4Addr>, ValueError> {
+        check_length!(value.len());
         let element_size = mem::size_of::<u32>();
-        check_divisibility!(len, element_size);
-        check_remaining!(cursor, len);
-        let amount = len / element_size;
-        let mut value = Vec::with_capacity(amount);
-        for _ in 0..amount {
-            check_remaining!(cursor, element_size);
-            value.push(Ipv4Addr::from(cursor.get_u32_be()))
-        }
-        if let Some(ref mut data) = option {
-            data.append(value.as_mut());
-            Ok(data.to_owned())
-        } else {
-            Ok(value)
+        check_divisibility!(value.len(), element_size);
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / element_size);
+        while cursor.remaining() > 0 {
+            result.push(Ipv4Addr::from(cursor.get_u32_be()));
         }
+        Ok(result)
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
-    fn get_opt_vec_ipv4_pairs(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<Vec<(Ipv4Addr, Ipv4Addr)>>,
-    ) -> io::Result<Vec<(Ipv4Addr, Ipv4Addr)>> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
+    fn get_opt_vec_ipv4_pairs(value: &[u8]) -> Result<Vec<(Ipv4Addr, Ipv4Addr)>, ValueError> {
+        check_length!(value.len());
         let element_size = mem::size_of::<u32>() * 2;
-        check_divisibility!(len, element_size);
-        check_remaining!(cursor, len);
-        let amount = len / element_size;
-        let mut value = Vec::with_capacity(amount);
-        for _ in 0..amount {
-            check_remaining!(cursor, element_size);
-            value.push((
+        check_divisibility!(value.len(), element_size);
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / element_size);
+        while cursor.remaining() > 0 {
+            result.push((
                 Ipv4Addr::from(cursor.get_u32_be()),
                 Ipv4Addr::from(cursor.get_u32_be()),
-            ))
-        }
-        if let Some(ref mut data) = option {
-            data.append(value.as_mut());
-            Ok(data.to_owned())
-        } else {
-            Ok(value)
+            ));
         }
+        Ok(result)
     }
 
-    /// Can be splitted so values are appended if an option already contains some data.
     /// The encoding algorithm explained at [RFC 3442](https://tools.ietf.org/html/rfc3442).
     fn get_opt_classless_static_routes(
-        cursor: &mut io::Cursor<&[u8]>,
-        option: &mut Option<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>>,
-    ) -> io::Result<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>> {
+        value: &[u8],
+    ) -> Result<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>, ValueError> {
         const BITS_IN_BYTE: usize = 8;
         const IPV4_BYTESIZE: usize = mem::size_of::<u32>();
         const IPV4_BITSIZE: usize = IPV4_BYTESIZE * BITS_IN_BYTE;
         const MIN_ELEMENT_SIZE: usize = 1 + IPV4_BYTESIZE;
 
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let mut len = cursor.get_u8() as usize;
-        check_length!(len);
-        check_remaining!(cursor, len);
-        let mut value = Vec::with_capacity(len / MIN_ELEMENT_SIZE);
-        while len > 0 {
+        check_length!(value.len());
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / MIN_ELEMENT_SIZE);
+        while cursor.remaining() > 0 {
             let subnet_mask_len = cursor.get_u8() as usize;
+            if subnet_mask_len > IPV4_BITSIZE {
+                return Err(ValueError::Invalid("Subnet mask width is invalid".to_owned()));
+            }
             let subnet_mask_i =
                 (<u32>::max_value() as u64 + 1) - 2u64.pow((IPV4_BITSIZE - subnet_mask_len) as u32);
 
-            let mut subnet_number_len = 0;
             let mut subnet_number_a: [u8; IPV4_BYTESIZE] = [0u8; IPV4_BYTESIZE];
             for i in 0..4 {
                 if subnet_mask_len > i * BITS_IN_BYTE {
-                    subnet_number_len += 1;
+                    check_remaining!(cursor, mem::size_of::<u8>());
                     subnet_number_a[i] = cursor.get_u8();
                 }
             }
-            len -= MIN_ELEMENT_SIZE + subnet_number_len;
 
+            check_remaining!(cursor, IPV4_BYTESIZE);
             let subnet_number = Ipv4Addr::from(subnet_number_a);
             let subnet_mask = Ipv4Addr::from(subnet_mask_i as u32);
             let router = Ipv4Addr::from(cursor.get_u32_be());
-            value.push((subnet_number, subnet_mask, router));
-        }
-        if let Some(ref mut data) = option {
-            data.append(value.as_mut());
-            Ok(data.to_owned())
-        } else {
-            Ok(value)
+            result.push((subnet_number, subnet_mask, router));
         }
+        Ok(result)
     }
+}
 
-    fn skip(cursor: &mut io::Cursor<&[u8]>) -> io::Result<()> {
-        check_remaining!(cursor, mem::size_of::<u8>());
-        let len = cursor.get_u8() as usize;
-        check_length!(len);
-        check_remaining!(cursor, len);
-        cursor.advance(len);
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::constants::*;
+
+    #[test]
+    fn option_instances_are_concatenated() {
+        let mut buffer = vec![0u8; OFFSET_OPTIONS];
+        buffer[OFFSET_MAGIC_COOKIE..OFFSET_OPTIONS].copy_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        // the hostname is splitted over the main area, `file` and `sname` in that order
+        buffer.extend_from_slice(&[12, 3, b'f', b'o', b'o', 52, 1, 3, 255]);
+        buffer[OFFSET_BOOT_FILENAME..OFFSET_BOOT_FILENAME + 5]
+            .copy_from_slice(&[12, 3, b'b', b'a', b'r']);
+        buffer[OFFSET_SERVER_NAME..OFFSET_SERVER_NAME + 5]
+            .copy_from_slice(&[12, 3, b'b', b'a', b'z']);
+
+        let decoded = Message::from_bytes(&buffer).unwrap();
+        assert_eq!(decoded.options.hostname, Some("foobarbaz".to_owned()));
+    }
+
+    #[test]
+    fn decode_errors_carry_context() {
+        let mut buffer = vec![0u8; OFFSET_OPTIONS];
+        buffer[OFFSET_MAGIC_COOKIE..OFFSET_OPTIONS].copy_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        // the 3-byte subnet mask is followed by a valid hostname and the overload option
+        buffer.extend_from_slice(&[1, 3, 255, 255, 0, 12, 3, b'f', b'o', b'o', 52, 1, 1, 255]);
+        // the option in the `file` field is abrupted
+        buffer[OFFSET_BOOT_FILENAME..OFFSET_BOOT_FILENAME + 2].copy_from_slice(&[15, 200]);
+
+        assert_eq!(
+            Message::from_bytes(&buffer).err(),
+            Some(DecodeError::OptionAbrupted {
+                region: Region::File,
+                offset: OFFSET_BOOT_FILENAME,
+                tag: 15,
+                expected: 201,
+                actual: SIZE_BOOT_FILENAME - 1,
+            })
+        );
+
+        let (decoded, errors) = Message::from_bytes_lenient(&buffer).unwrap();
+        assert_eq!(decoded.options.hostname, Some("foo".to_owned()));
+        assert!(decoded.options.subnet_mask.is_none());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[1],
+            DecodeError::OptionLength {
+                region: Region::Main,
+                offset: OFFSET_OPTIONS,
+                tag: SubnetMask as u8,
+                expected: ExpectedLength::Exactly(4),
+                actual: 3,
+            }
+        );
+
+        buffer[OFFSET_MAGIC_COOKIE] = 0;
+        match Message::from_bytes_lenient(&buffer) {
+            Err(DecodeError::MagicCookie { offset, .. }) => assert_eq!(offset, OFFSET_MAGIC_COOKIE),
+            _ => panic!("The magic cookie is not checked"),
+        }
     }
 }
@@ -0,0 +1,151 @@
+//! DHCP message hardware address module.
+
+use std::{fmt, io, str::FromStr};
+
+use eui48::{MacAddress, EUI48LEN};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::constants::SIZE_HARDWARE_ADDRESS;
+
+/// DHCP client hardware address.
+///
+/// Holds the first `hlen` bytes of the 16-byte `chaddr` field,
+/// so its length is the hardware address length of the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct HardwareAddress {
+    bytes: [u8; SIZE_HARDWARE_ADDRESS],
+    len: u8,
+}
+
+impl HardwareAddress {
+    /// Creates an address from the bytes.
+    ///
+    /// # Errors
+    /// `io::Error` if the address does not fit the `chaddr` field.
+    pub fn new(src: &[u8]) -> io::Result<Self> {
+        if src.len() > SIZE_HARDWARE_ADDRESS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Hardware address is too long",
+            ));
+        }
+        let mut bytes = [0u8; SIZE_HARDWARE_ADDRESS];
+        bytes[..src.len()].copy_from_slice(src);
+        Ok(HardwareAddress {
+            bytes,
+            len: src.len() as u8,
+        })
+    }
+
+    /// Creates a zero-length address (e.g. of an InfiniBand client).
+    pub fn empty() -> Self {
+        HardwareAddress::default()
+    }
+
+    /// The address bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// The address length which is sent in the `hlen` field.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Checks if the address is zero-length.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Converts the address to MAC-48 if it is 6 bytes long.
+    pub fn to_mac_address(&self) -> Option<MacAddress> {
+        if self.len() != EUI48LEN {
+            return None;
+        }
+        MacAddress::from_bytes(self.as_bytes()).ok()
+    }
+}
+
+impl From<MacAddress> for HardwareAddress {
+    fn from(address: MacAddress) -> Self {
+        let mut bytes = [0u8; SIZE_HARDWARE_ADDRESS];
+        bytes[..EUI48LEN].copy_from_slice(address.as_bytes());
+        HardwareAddress {
+            bytes,
+            len: EUI48LEN as u8,
+        }
+    }
+}
+
+impl fmt::Display for HardwareAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "EMPTY");
+        }
+        let octets: Vec<String> = self
+            .as_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        write!(f, "{}", octets.join(":"))
+    }
+}
+
+impl FromStr for HardwareAddress {
+    type Err = io::Error;
+
+    /// Parses the colon-separated hex octets. An empty string is an empty address.
+    fn from_str(s: &str) -> io::Result<Self> {
+        if s.is_empty() {
+            return Ok(HardwareAddress::empty());
+        }
+        let mut bytes = Vec::with_capacity(SIZE_HARDWARE_ADDRESS);
+        for octet in s.split(':') {
+            let byte = u8::from_str_radix(octet, 16).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Hardware address is invalid")
+            })?;
+            bytes.push(byte);
+        }
+        HardwareAddress::new(&bytes)
+    }
+}
+
+/// Serialized as a string like `00:0c:29:13:0e:37`, which is empty for an empty address.
+#[cfg(feature = "serde")]
+impl Serialize for HardwareAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_empty() {
+            serializer.serialize_str("")
+        } else {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for HardwareAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_uses_the_colon_notation() {
+        let address = HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]).unwrap();
+        let json = ::serde_json::to_string(&address).unwrap();
+        assert_eq!(json, r#""00:0c:29:13:0e:37""#);
+        let decoded: HardwareAddress = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, address);
+
+        assert_eq!(
+            ::serde_json::to_string(&HardwareAddress::empty()).unwrap(),
+            r#""""#
+        );
+    }
+}
@@ -4,11 +4,70 @@ use std::fmt;
 
 /// DHCP hardware type.
 ///
-/// Only MAC-48 is implemented.
-#[derive(Clone, Copy)]
+/// [RFC 1700](https://tools.ietf.org/html/rfc1700#page-163)
+/// [IANA ARP Hardware Types](https://www.iana.org/assignments/arp-parameters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HardwareType {
-    Undefined = 0,
+    Undefined,
     Ethernet,
+    ExperimentalEthernet,
+    AmateurRadioAx25,
+    ProteonProNetTokenRing,
+    Chaos,
+    Ieee802,
+    Arcnet,
+    Hyperchannel,
+    Lanstar,
+    AutonetShortAddress,
+    LocalTalk,
+    LocalNet,
+    UltraLink,
+    Smds,
+    FrameRelay,
+    AtmJxb2,
+    Hdlc,
+    FibreChannel,
+    AtmRfc2225,
+    SerialLine,
+    Atm,
+    MilStd188220,
+    Metricom,
+    Ieee1394,
+    Mapos,
+    Twinaxial,
+    Eui64,
+    Hiparp,
+    Iso7816,
+    ArpSec,
+    IpsecTunnel,
+    InfiniBand,
+    Tia102Cai,
+    Wiegand,
+    PureIp,
+    HwExp1,
+    Hfi,
+    UnifiedBus,
+
+    /// A type which is not known to this implementation.
+    Other(u8),
+}
+
+impl HardwareType {
+    /// The `hlen` value mandated for the hardware type, if any.
+    ///
+    /// [RFC 2855 §2](https://tools.ietf.org/html/rfc2855#section-2)
+    /// [RFC 4390 §2.1](https://tools.ietf.org/html/rfc4390#section-2.1)
+    pub fn address_length(self) -> Option<usize> {
+        use self::HardwareType::*;
+        match self {
+            Ethernet | Ieee802 => Some(6),
+            Ieee1394 | Eui64 => Some(8),
+            // the 20-byte InfiniBand address does not fit the `chaddr` field
+            InfiniBand => Some(0),
+            _ => None,
+        }
+    }
 }
 
 impl From<u8> for HardwareType {
@@ -16,8 +75,95 @@ impl From<u8> for HardwareType {
         use self::HardwareType::*;
         match value {
             1 => Ethernet,
+            2 => ExperimentalEthernet,
+            3 => AmateurRadioAx25,
+            4 => ProteonProNetTokenRing,
+            5 => Chaos,
+            6 => Ieee802,
+            7 => Arcnet,
+            8 => Hyperchannel,
+            9 => Lanstar,
+            10 => AutonetShortAddress,
+            11 => LocalTalk,
+            12 => LocalNet,
+            13 => UltraLink,
+            14 => Smds,
+            15 => FrameRelay,
+            16 => AtmJxb2,
+            17 => Hdlc,
+            18 => FibreChannel,
+            19 => AtmRfc2225,
+            20 => SerialLine,
+            21 => Atm,
+            22 => MilStd188220,
+            23 => Metricom,
+            24 => Ieee1394,
+            25 => Mapos,
+            26 => Twinaxial,
+            27 => Eui64,
+            28 => Hiparp,
+            29 => Iso7816,
+            30 => ArpSec,
+            31 => IpsecTunnel,
+            32 => InfiniBand,
+            33 => Tia102Cai,
+            34 => Wiegand,
+            35 => PureIp,
+            36 => HwExp1,
+            37 => Hfi,
+            38 => UnifiedBus,
+
+            0 => Undefined,
+            _ => Other(value),
+        }
+    }
+}
+
+impl From<HardwareType> for u8 {
+    fn from(value: HardwareType) -> Self {
+        use self::HardwareType::*;
+        match value {
+            Undefined => 0,
+            Ethernet => 1,
+            ExperimentalEthernet => 2,
+            AmateurRadioAx25 => 3,
+            ProteonProNetTokenRing => 4,
+            Chaos => 5,
+            Ieee802 => 6,
+            Arcnet => 7,
+            Hyperchannel => 8,
+            Lanstar => 9,
+            AutonetShortAddress => 10,
+            LocalTalk => 11,
+            LocalNet => 12,
+            UltraLink => 13,
+            Smds => 14,
+            FrameRelay => 15,
+            AtmJxb2 => 16,
+            Hdlc => 17,
+            FibreChannel => 18,
+            AtmRfc2225 => 19,
+            SerialLine => 20,
+            Atm => 21,
+            MilStd188220 => 22,
+            Metricom => 23,
+            Ieee1394 => 24,
+            Mapos => 25,
+            Twinaxial => 26,
+            Eui64 => 27,
+            Hiparp => 28,
+            Iso7816 => 29,
+            ArpSec => 30,
+            IpsecTunnel => 31,
+            InfiniBand => 32,
+            Tia102Cai => 33,
+            Wiegand => 34,
+            PureIp => 35,
+            HwExp1 => 36,
+            Hfi => 37,
+            UnifiedBus => 38,
 
-            _ => Undefined,
+            Other(value) => value,
         }
     }
 }
@@ -27,8 +173,46 @@ impl fmt::Display for HardwareType {
         use self::HardwareType::*;
         match self {
             Ethernet => write!(f, "Ethernet"),
+            ExperimentalEthernet => write!(f, "Experimental Ethernet"),
+            AmateurRadioAx25 => write!(f, "Amateur Radio AX.25"),
+            ProteonProNetTokenRing => write!(f, "Proteon ProNET Token Ring"),
+            Chaos => write!(f, "Chaos"),
+            Ieee802 => write!(f, "IEEE 802"),
+            Arcnet => write!(f, "ARCNET"),
+            Hyperchannel => write!(f, "Hyperchannel"),
+            Lanstar => write!(f, "Lanstar"),
+            AutonetShortAddress => write!(f, "Autonet Short Address"),
+            LocalTalk => write!(f, "LocalTalk"),
+            LocalNet => write!(f, "LocalNet"),
+            UltraLink => write!(f, "Ultra link"),
+            Smds => write!(f, "SMDS"),
+            FrameRelay => write!(f, "Frame Relay"),
+            AtmJxb2 => write!(f, "ATM (JXB2)"),
+            Hdlc => write!(f, "HDLC"),
+            FibreChannel => write!(f, "Fibre Channel"),
+            AtmRfc2225 => write!(f, "ATM (RFC 2225)"),
+            SerialLine => write!(f, "Serial Line"),
+            Atm => write!(f, "ATM"),
+            MilStd188220 => write!(f, "MIL-STD-188-220"),
+            Metricom => write!(f, "Metricom"),
+            Ieee1394 => write!(f, "IEEE 1394.1995"),
+            Mapos => write!(f, "MAPOS"),
+            Twinaxial => write!(f, "Twinaxial"),
+            Eui64 => write!(f, "EUI-64"),
+            Hiparp => write!(f, "HIPARP"),
+            Iso7816 => write!(f, "IP and ARP over ISO 7816-3"),
+            ArpSec => write!(f, "ARPSec"),
+            IpsecTunnel => write!(f, "IPsec tunnel"),
+            InfiniBand => write!(f, "InfiniBand"),
+            Tia102Cai => write!(f, "TIA-102 Project 25 CAI"),
+            Wiegand => write!(f, "Wiegand Interface"),
+            PureIp => write!(f, "Pure IP"),
+            HwExp1 => write!(f, "HW_EXP1"),
+            Hfi => write!(f, "HFI"),
+            UnifiedBus => write!(f, "Unified Bus"),
 
             Undefined => write!(f, "UNDEFINED"),
+            Other(value) => write!(f, "hardware type {}", value),
         }
     }
 }
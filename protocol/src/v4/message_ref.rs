@@ -0,0 +1,410 @@
+//! DHCP message borrowed view module.
+
+use std::{borrow::Cow, io, mem, net::Ipv4Addr};
+
+use bytes::Buf;
+
+use super::{
+    constants::*,
+    decode_error::{DecodeError, Region},
+    options::{MessageType, OptionTag, Overload, UserClass},
+    HardwareType, OperationCode,
+};
+
+/// The `hlen` field offset in bytes.
+const OFFSET_HARDWARE_ADDRESS_LENGTH: usize = 2;
+/// The `chaddr` field offset in bytes.
+const OFFSET_HARDWARE_ADDRESS: usize = OFFSET_SERVER_NAME - SIZE_HARDWARE_ADDRESS;
+
+/// A DHCP message borrowed from the packet buffer.
+///
+/// Only the fixed header and the magic cookie are checked on creation.
+/// The options are decoded lazily by `options` as borrowed `(tag, value)` slices,
+/// so a packet can be inspected without any allocations.
+#[derive(Debug, Clone, Copy)]
+pub struct MessageRef<'a> {
+    src: &'a [u8],
+    overload: Overload,
+}
+
+/// Iterates over the option instances of a `MessageRef`.
+///
+/// The areas are visited in the order of RFC 3396: the `options` field,
+/// then the `file` and `sname` fields if they are overloaded.
+/// If an option is abrupted, yields an error and skips the rest of its area.
+#[derive(Debug, Clone)]
+pub struct OptionsIter<'a> {
+    /// The areas with their offsets in the packet.
+    areas: [(Region, usize, &'a [u8]); 3],
+    area: usize,
+    position: usize,
+    /// The region and the packet offset of the last option.
+    last: (Region, usize),
+}
+
+impl<'a> MessageRef<'a> {
+    /// Checks the fixed header and the magic cookie and creates the view.
+    ///
+    /// # Errors
+    /// `DecodeError` if the packet is too small, the hardware address length is invalid
+    /// or the magic cookie is wrong.
+    pub fn new(src: &'a [u8]) -> Result<Self, DecodeError> {
+        if src.len() < OFFSET_OPTIONS {
+            return Err(DecodeError::TooSmall {
+                expected: OFFSET_OPTIONS,
+                actual: src.len(),
+            });
+        }
+        let hardware_address_length = src[OFFSET_HARDWARE_ADDRESS_LENGTH] as usize;
+        if hardware_address_length > SIZE_HARDWARE_ADDRESS {
+            return Err(DecodeError::HardwareAddressLength {
+                offset: OFFSET_HARDWARE_ADDRESS_LENGTH,
+                actual: hardware_address_length,
+            });
+        }
+        let magic_cookie = io::Cursor::new(&src[OFFSET_MAGIC_COOKIE..]).get_u32_be();
+        if magic_cookie != MAGIC_COOKIE {
+            return Err(DecodeError::MagicCookie {
+                offset: OFFSET_MAGIC_COOKIE,
+                actual: magic_cookie,
+            });
+        }
+
+        let mut message = MessageRef {
+            src,
+            overload: Overload::Undefined,
+        };
+        // the overload option may only occur in the `options` field
+        for option in message.options() {
+            match option {
+                Ok((tag, value)) if tag == OptionTag::Overload as u8 && value.len() == 1 => {
+                    message.overload = Overload::from(value[0]);
+                    break;
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        Ok(message)
+    }
+
+    /// The packet the view is borrowed from.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.src
+    }
+
+    pub fn operation_code(&self) -> OperationCode {
+        OperationCode::from(self.src[0])
+    }
+
+    pub fn hardware_type(&self) -> HardwareType {
+        HardwareType::from(self.src[1])
+    }
+
+    pub fn hardware_options(&self) -> u8 {
+        self.src[3]
+    }
+
+    pub fn transaction_id(&self) -> u32 {
+        self.u32_at(4)
+    }
+
+    pub fn seconds(&self) -> u16 {
+        io::Cursor::new(&self.src[8..]).get_u16_be()
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.src[10] & 0x80 != 0
+    }
+
+    pub fn client_ip_address(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.u32_at(12))
+    }
+
+    pub fn your_ip_address(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.u32_at(16))
+    }
+
+    pub fn server_ip_address(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.u32_at(20))
+    }
+
+    pub fn gateway_ip_address(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.u32_at(24))
+    }
+
+    /// The first `hlen` bytes of the `chaddr` field.
+    pub fn client_hardware_address(&self) -> &'a [u8] {
+        let len = self.src[OFFSET_HARDWARE_ADDRESS_LENGTH] as usize;
+        &self.src[OFFSET_HARDWARE_ADDRESS..OFFSET_HARDWARE_ADDRESS + len]
+    }
+
+    /// The whole `sname` field, which may contain overloaded options.
+    pub fn server_name(&self) -> &'a [u8] {
+        &self.src[OFFSET_SERVER_NAME..OFFSET_BOOT_FILENAME]
+    }
+
+    /// The whole `file` field, which may contain overloaded options.
+    pub fn boot_filename(&self) -> &'a [u8] {
+        &self.src[OFFSET_BOOT_FILENAME..OFFSET_MAGIC_COOKIE]
+    }
+
+    /// The areas overloaded with options.
+    pub fn overload(&self) -> Overload {
+        self.overload
+    }
+
+    /// Iterates over the option instances without the `Pad` and `End` ones.
+    pub fn options(&self) -> OptionsIter<'a> {
+        let empty: &'a [u8] = &[];
+        let (file, sname) = match self.overload {
+            Overload::File => (self.boot_filename(), empty),
+            Overload::Sname => (empty, self.server_name()),
+            Overload::Both => (self.boot_filename(), self.server_name()),
+            Overload::Undefined => (empty, empty),
+        };
+        OptionsIter {
+            areas: [
+                (Region::Main, OFFSET_OPTIONS, &self.src[OFFSET_OPTIONS..]),
+                (Region::File, OFFSET_BOOT_FILENAME, file),
+                (Region::Sname, OFFSET_SERVER_NAME, sname),
+            ],
+            area: 0,
+            position: 0,
+            last: (Region::Main, OFFSET_OPTIONS),
+        }
+    }
+
+    /// Returns the option value, concatenating the instances as per RFC 3396.
+    ///
+    /// The value is only copied if the option is split into several instances.
+    /// Returns `None` if the option is absent or an option before it is abrupted.
+    pub fn option(&self, tag: OptionTag) -> Option<Cow<'a, [u8]>> {
+        let mut result: Option<Cow<'a, [u8]>> = None;
+        for option in self.options() {
+            let (t, value) = match option {
+                Ok(option) => option,
+                Err(_) => break,
+            };
+            if t != tag as u8 {
+                continue;
+            }
+            result = Some(match result {
+                None => Cow::Borrowed(value),
+                Some(previous) => {
+                    let mut data = previous.into_owned();
+                    data.extend_from_slice(value);
+                    Cow::Owned(data)
+                }
+            });
+        }
+        result
+    }
+
+    /// Returns `None` if the option is absent or its length is invalid.
+    pub fn dhcp_message_type(&self) -> Option<MessageType> {
+        self.option_u8(OptionTag::DhcpMessageType)
+            .map(MessageType::from)
+    }
+
+    /// Returns `None` if the option is absent or its length is invalid.
+    pub fn dhcp_server_id(&self) -> Option<Ipv4Addr> {
+        self.option_ipv4(OptionTag::DhcpServerId)
+    }
+
+    /// Returns `None` if the option is absent or its length is invalid.
+    pub fn address_request(&self) -> Option<Ipv4Addr> {
+        self.option_ipv4(OptionTag::AddressRequest)
+    }
+
+    /// Returns `None` if the option is absent or its length is invalid.
+    pub fn address_time(&self) -> Option<u32> {
+        self.option_u32(OptionTag::AddressTime)
+    }
+
+    /// Returns `None` if the option is absent or its length is invalid.
+    pub fn dhcp_max_message_size(&self) -> Option<u16> {
+        match self.option(OptionTag::DhcpMaxMessageSize) {
+            Some(ref value) if value.len() == mem::size_of::<u16>() => {
+                Some(io::Cursor::new(value).get_u16_be())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn client_id(&self) -> Option<Cow<'a, [u8]>> {
+        self.option(OptionTag::ClientId)
+    }
+
+    pub fn hostname(&self) -> Option<Cow<'a, [u8]>> {
+        self.option(OptionTag::Hostname)
+    }
+
+    pub fn parameter_list(&self) -> Option<Cow<'a, [u8]>> {
+        self.option(OptionTag::ParameterList)
+    }
+
+    /// Returns `None` if the option is absent or empty.
+    pub fn user_class(&self) -> Option<UserClass> {
+        self.option(OptionTag::UserClass)
+            .and_then(|value| UserClass::from_bytes(&value).ok())
+    }
+
+    /// The raw relay agent information (use `RelayAgentInformation::from_bytes` to decode it).
+    pub fn relay_agent_information(&self) -> Option<Cow<'a, [u8]>> {
+        self.option(OptionTag::RelayAgentInformation)
+    }
+
+    fn option_u8(&self, tag: OptionTag) -> Option<u8> {
+        match self.option(tag) {
+            Some(ref value) if value.len() == mem::size_of::<u8>() => Some(value[0]),
+            _ => None,
+        }
+    }
+
+    fn option_u32(&self, tag: OptionTag) -> Option<u32> {
+        match self.option(tag) {
+            Some(ref value) if value.len() == mem::size_of::<u32>() => {
+                Some(io::Cursor::new(value).get_u32_be())
+            }
+            _ => None,
+        }
+    }
+
+    fn option_ipv4(&self, tag: OptionTag) -> Option<Ipv4Addr> {
+        self.option_u32(tag).map(Ipv4Addr::from)
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        io::Cursor::new(&self.src[offset..]).get_u32_be()
+    }
+}
+
+impl<'a> OptionsIter<'a> {
+    /// The region of the option returned last.
+    pub fn region(&self) -> Region {
+        self.last.0
+    }
+
+    /// The packet offset of the option returned last.
+    pub fn offset(&self) -> usize {
+        self.last.1
+    }
+}
+
+impl<'a> Iterator for OptionsIter<'a> {
+    type Item = Result<(u8, &'a [u8]), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.area < self.areas.len() {
+            let (region, base, src) = self.areas[self.area];
+            if self.position >= src.len() {
+                self.area += 1;
+                self.position = 0;
+                continue;
+            }
+
+            let tag = src[self.position];
+            if tag == OptionTag::End as u8 {
+                self.position = src.len();
+                continue;
+            }
+            self.last = (region, base + self.position);
+            self.position += 1;
+            if tag == OptionTag::Pad as u8 {
+                continue;
+            }
+
+            let left = src.len() - self.position;
+            let expected = if left == 0 {
+                1
+            } else {
+                1 + src[self.position] as usize
+            };
+            if expected > left {
+                self.position = src.len();
+                return Some(Err(DecodeError::OptionAbrupted {
+                    region,
+                    offset: self.last.1,
+                    tag,
+                    expected,
+                    actual: left,
+                }));
+            }
+            let len = src[self.position] as usize;
+            let value = &src[self.position + 1..self.position + 1 + len];
+            self.position += 1 + len;
+            return Some(Ok((tag, value)));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::{options::Options, HardwareAddress, Message};
+
+    #[test]
+    fn message_ref_borrows_options() {
+        let options = Options {
+            dhcp_message_type: Some(MessageType::DhcpRequest),
+            address_request: Some(Ipv4Addr::new(192, 168, 0, 10)),
+            client_id: Some(vec![1, 0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]),
+            user_class: Some(UserClass::new(vec![b"iPXE".to_vec()])),
+            ..Options::default()
+        };
+        let message = Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: false,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options,
+        };
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+
+        let view = MessageRef::new(&buffer[..amount]).unwrap();
+        assert_eq!(view.transaction_id(), 0xdead_beef);
+        assert_eq!(view.hardware_type(), HardwareType::Ethernet);
+        assert_eq!(
+            view.client_hardware_address(),
+            &[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]
+        );
+        assert_eq!(view.dhcp_message_type(), Some(MessageType::DhcpRequest));
+        assert_eq!(view.address_request(), Some(Ipv4Addr::new(192, 168, 0, 10)));
+        assert_eq!(view.dhcp_server_id(), None);
+        assert_eq!(view.user_class(), message.options.user_class);
+        match view.client_id() {
+            Some(Cow::Borrowed(value)) => assert_eq!(value[0], 1),
+            _ => panic!("The client identifier is not borrowed"),
+        }
+        let tags: Vec<u8> = view.options().map(|option| option.unwrap().0).collect();
+        assert!(tags.contains(&(OptionTag::ClientId as u8)));
+
+        let owned = view.to_owned().unwrap();
+        assert_eq!(
+            owned.options.address_request,
+            message.options.address_request
+        );
+        assert_eq!(owned.options.client_id, message.options.client_id);
+
+        // the abrupted option is reported lazily
+        buffer[amount - 1] = OptionTag::Hostname as u8;
+        let view = MessageRef::new(&buffer[..amount]).unwrap();
+        assert!(view.options().any(|option| option.is_err()));
+        assert!(view.to_owned().is_err());
+        assert!(MessageRef::new(&buffer[..OFFSET_OPTIONS - 1]).is_err());
+    }
+}
@@ -3,7 +3,8 @@
 use std::fmt;
 
 /// DHCP opcode.
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OperationCode {
     Undefined = 0,
     BootRequest,
@@ -0,0 +1,269 @@
+//! DHCP authentication module.
+
+use std::{
+    fmt, io, mem,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::{Buf, BufMut};
+use md5;
+
+/// The delayed authentication protocol (RFC 3118 §5).
+pub const AUTHENTICATION_PROTOCOL_DELAYED: u8 = 1;
+/// The reconfigure key authentication protocol (RFC 6704 §3).
+pub const AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY: u8 = 3;
+/// The HMAC-MD5 algorithm used by both delayed and reconfigure key authentication.
+pub const AUTHENTICATION_ALGORITHM_HMAC_MD5: u8 = 1;
+/// The monotonically increasing counter replay detection method.
+pub const AUTHENTICATION_RDM_MONOTONIC: u8 = 0;
+
+/// The reconfigure key authentication information carries the key.
+pub const RECONFIGURE_KEY_TYPE_KEY: u8 = 1;
+/// The reconfigure key authentication information carries the HMAC-MD5 digest.
+pub const RECONFIGURE_KEY_TYPE_HMAC_MD5: u8 = 2;
+
+/// The HMAC-MD5 digest and reconfigure key size.
+pub const SIZE_HMAC_MD5: usize = 16;
+/// The delayed authentication secret ID and HMAC-MD5 digest.
+const SIZE_DELAYED_INFORMATION: usize = mem::size_of::<u32>() + SIZE_HMAC_MD5;
+/// The protocol, algorithm and RDM octets and the replay detection field.
+const SIZE_PREFIX: usize = mem::size_of::<u8>() * 3 + mem::size_of::<u64>();
+/// The MD5 block size used to pad HMAC keys.
+const SIZE_MD5_BLOCK: usize = 64;
+
+/// DHCP authentication option.
+///
+/// [RFC 3118 §2](https://tools.ietf.org/html/rfc3118#section-2)
+/// [RFC 6704 §3](https://tools.ietf.org/html/rfc6704#section-3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Authentication {
+    pub protocol: u8,
+    pub algorithm: u8,
+    /// The replay detection method.
+    pub rdm: u8,
+    pub replay_detection: u64,
+    /// The protocol specific authentication information.
+    pub information: Vec<u8>,
+}
+
+impl Authentication {
+    /// The monotonically increasing replay detection value (the current time in nanoseconds).
+    pub fn monotonic_counter() -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.as_secs() * 1_000_000_000 + u64::from(now.subsec_nanos())
+    }
+
+    /// Creates the option requesting the delayed authentication in a `DHCPDISCOVER`.
+    pub fn delayed_request(replay_detection: u64) -> Self {
+        Self::delayed(replay_detection, Vec::new())
+    }
+
+    /// Creates the option to be signed with the key of the secret ID
+    /// in the delayed authentication.
+    ///
+    /// The digest is zeroed until the message is signed.
+    pub fn delayed_hmac_md5(replay_detection: u64, secret_id: u32) -> Self {
+        let mut information = Vec::with_capacity(SIZE_DELAYED_INFORMATION);
+        information.put_u32_be(secret_id);
+        information.put(&[0u8; SIZE_HMAC_MD5][..]);
+        Self::delayed(replay_detection, information)
+    }
+
+    /// Checks if the option only requests the delayed authentication.
+    pub fn is_delayed_request(&self) -> bool {
+        self.is_delayed() && self.information.is_empty()
+    }
+
+    /// Returns the secret ID if the option carries a delayed authentication digest.
+    pub fn delayed_secret_id(&self) -> Option<u32> {
+        if !self.is_delayed() || self.information.len() != SIZE_DELAYED_INFORMATION {
+            return None;
+        }
+        Some(io::Cursor::new(&self.information).get_u32_be())
+    }
+
+    /// Creates the option delivering the reconfigure key to a client in a `DHCPACK`.
+    pub fn reconfigure_key(replay_detection: u64, key: &[u8]) -> Self {
+        let mut information = Vec::with_capacity(mem::size_of::<u8>() + key.len());
+        information.push(RECONFIGURE_KEY_TYPE_KEY);
+        information.extend_from_slice(key);
+        Self::reconfigure(replay_detection, information)
+    }
+
+    /// Creates the option to be signed with the reconfigure key in a `DHCPFORCERENEW`.
+    ///
+    /// The digest is zeroed until the message is signed.
+    pub fn reconfigure_hmac_md5(replay_detection: u64) -> Self {
+        let mut information = vec![0u8; mem::size_of::<u8>() + SIZE_HMAC_MD5];
+        information[0] = RECONFIGURE_KEY_TYPE_HMAC_MD5;
+        Self::reconfigure(replay_detection, information)
+    }
+
+    /// Returns the key if the option delivers a reconfigure key.
+    pub fn reconfigure_key_value(&self) -> Option<&[u8]> {
+        if !self.is_reconfigure() {
+            return None;
+        }
+        match self.information.split_first() {
+            Some((&RECONFIGURE_KEY_TYPE_KEY, key)) if key.len() == SIZE_HMAC_MD5 => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Checks if the option carries a reconfigure key HMAC-MD5 digest.
+    pub fn is_reconfigure_hmac_md5(&self) -> bool {
+        self.is_reconfigure()
+            && self.information.len() == mem::size_of::<u8>() + SIZE_HMAC_MD5
+            && self.information[0] == RECONFIGURE_KEY_TYPE_HMAC_MD5
+    }
+
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is abrupted.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() < SIZE_PREFIX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Authentication is abrupted",
+            ));
+        }
+        let mut cursor = io::Cursor::new(src);
+        Ok(Authentication {
+            protocol: cursor.get_u8(),
+            algorithm: cursor.get_u8(),
+            rdm: cursor.get_u8(),
+            replay_detection: cursor.get_u64_be(),
+            information: cursor.bytes().to_vec(),
+        })
+    }
+
+    /// Encodes the option value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(SIZE_PREFIX + self.information.len());
+        dst.put_u8(self.protocol);
+        dst.put_u8(self.algorithm);
+        dst.put_u8(self.rdm);
+        dst.put_u64_be(self.replay_detection);
+        dst.put(&self.information);
+        dst
+    }
+
+    fn delayed(replay_detection: u64, information: Vec<u8>) -> Self {
+        Authentication {
+            protocol: AUTHENTICATION_PROTOCOL_DELAYED,
+            algorithm: AUTHENTICATION_ALGORITHM_HMAC_MD5,
+            rdm: AUTHENTICATION_RDM_MONOTONIC,
+            replay_detection,
+            information,
+        }
+    }
+
+    fn is_delayed(&self) -> bool {
+        self.protocol == AUTHENTICATION_PROTOCOL_DELAYED
+            && self.algorithm == AUTHENTICATION_ALGORITHM_HMAC_MD5
+            && self.rdm == AUTHENTICATION_RDM_MONOTONIC
+    }
+
+    fn reconfigure(replay_detection: u64, information: Vec<u8>) -> Self {
+        Authentication {
+            protocol: AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY,
+            algorithm: AUTHENTICATION_ALGORITHM_HMAC_MD5,
+            rdm: AUTHENTICATION_RDM_MONOTONIC,
+            replay_detection,
+            information,
+        }
+    }
+
+    fn is_reconfigure(&self) -> bool {
+        self.protocol == AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY
+            && self.algorithm == AUTHENTICATION_ALGORITHM_HMAC_MD5
+            && self.rdm == AUTHENTICATION_RDM_MONOTONIC
+    }
+}
+
+impl fmt::Display for Authentication {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "protocol {}, algorithm {}, RDM {}, replay detection {}",
+            self.protocol, self.algorithm, self.rdm, self.replay_detection,
+        )
+    }
+}
+
+/// Computes the keyed hash (RFC 2104).
+pub fn hmac_md5(key: &[u8], data: &[u8]) -> [u8; SIZE_HMAC_MD5] {
+    let mut block = [0u8; SIZE_MD5_BLOCK];
+    if key.len() > SIZE_MD5_BLOCK {
+        block[..SIZE_HMAC_MD5].copy_from_slice(&md5::compute(key).0);
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = md5::Context::new();
+    inner.consume(block.iter().map(|byte| byte ^ 0x36).collect::<Vec<u8>>());
+    inner.consume(data);
+    let mut outer = md5::Context::new();
+    outer.consume(block.iter().map(|byte| byte ^ 0x5c).collect::<Vec<u8>>());
+    outer.consume(inner.compute().0);
+    outer.compute().0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_md5_test_vectors() {
+        // the examples from RFC 2104 Appendix
+        assert_eq!(
+            hmac_md5(&[0x0b; 16], b"Hi There"),
+            [
+                0x92, 0x94, 0x72, 0x7a, 0x36, 0x38, 0xbb, 0x1c, 0x13, 0xf4, 0x8e, 0xf8, 0x15, 0x8b,
+                0xfc, 0x9d,
+            ]
+        );
+        assert_eq!(
+            hmac_md5(b"Jefe", b"what do ya want for nothing?"),
+            [
+                0x75, 0x0c, 0x78, 0x3e, 0x6a, 0xb0, 0xb5, 0x03, 0xea, 0xa8, 0x6e, 0x31, 0x0a, 0x5d,
+                0xb7, 0x38,
+            ]
+        );
+    }
+
+    #[test]
+    fn reconfigure_key_round_trip() {
+        let key = [0x5a; SIZE_HMAC_MD5];
+        let option = Authentication::reconfigure_key(42, &key);
+        let bytes = option.to_bytes();
+        assert_eq!(&bytes[..12], &[3, 1, 0, 0, 0, 0, 0, 0, 0, 0, 42, 1]);
+
+        let decoded = Authentication::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.reconfigure_key_value(), Some(&key[..]));
+        assert!(!decoded.is_reconfigure_hmac_md5());
+        assert!(Authentication::from_bytes(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn delayed_round_trip() {
+        let request = Authentication::from_bytes(&Authentication::delayed_request(7).to_bytes());
+        let request = request.unwrap();
+        assert!(request.is_delayed_request());
+        assert_eq!(request.delayed_secret_id(), None);
+
+        let option = Authentication::delayed_hmac_md5(8, 0x0102_0304);
+        let bytes = option.to_bytes();
+        assert_eq!(bytes.len(), SIZE_PREFIX + SIZE_DELAYED_INFORMATION);
+        assert_eq!(&bytes[11..15], &[1, 2, 3, 4]);
+
+        let decoded = Authentication::from_bytes(&bytes).unwrap();
+        assert!(!decoded.is_delayed_request());
+        assert_eq!(decoded.delayed_secret_id(), Some(0x0102_0304));
+        assert_eq!(decoded.reconfigure_key_value(), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! DHCP client system architecture module.
+
+use std::fmt;
+
+/// DHCP client system architecture type.
+///
+/// [RFC 4578 §2.1](https://tools.ietf.org/html/rfc4578#section-2.1)
+/// [IANA Processor Architecture Types](https://www.iana.org/assignments/dhcpv6-parameters)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ClientArchitecture {
+    X86Bios,
+    NecPc98,
+    EfiItanium,
+    DecAlpha,
+    ArcX86,
+    IntelLeanClient,
+    EfiIa32,
+    EfiX64,
+    EfiXscale,
+    EfiBc,
+    EfiArm32,
+    EfiArm64,
+    PowerPcOpenFirmware,
+    PowerPcEpapr,
+    PowerOpalV3,
+    EfiX86Http,
+    EfiX64Http,
+    EfiBcHttp,
+    EfiArm32Http,
+    EfiArm64Http,
+    X86BiosHttp,
+    UbootArm32,
+    UbootArm64,
+    UbootArm32Http,
+    UbootArm64Http,
+    EfiRiscV32,
+    EfiRiscV32Http,
+    EfiRiscV64,
+    EfiRiscV64Http,
+    EfiRiscV128,
+    EfiRiscV128Http,
+    S390Basic,
+    S390Extended,
+    EfiMips32,
+    EfiMips64,
+    EfiSunway32,
+    EfiSunway64,
+    EfiLoongArch32,
+    EfiLoongArch32Http,
+    EfiLoongArch64,
+    EfiLoongArch64Http,
+    ArmRpiBoot,
+
+    /// A type which is not known to this implementation.
+    Other(u16),
+}
+
+impl ClientArchitecture {
+    /// Checks if the client downloads the boot file via HTTP instead of TFTP.
+    pub fn is_http(self) -> bool {
+        use self::ClientArchitecture::*;
+        matches!(
+            self,
+            EfiX86Http
+                | EfiX64Http
+                | EfiBcHttp
+                | EfiArm32Http
+                | EfiArm64Http
+                | X86BiosHttp
+                | UbootArm32Http
+                | UbootArm64Http
+                | EfiRiscV32Http
+                | EfiRiscV64Http
+                | EfiRiscV128Http
+                | EfiLoongArch32Http
+                | EfiLoongArch64Http
+        )
+    }
+}
+
+impl From<u16> for ClientArchitecture {
+    fn from(value: u16) -> Self {
+        use self::ClientArchitecture::*;
+        match value {
+            0 => X86Bios,
+            1 => NecPc98,
+            2 => EfiItanium,
+            3 => DecAlpha,
+            4 => ArcX86,
+            5 => IntelLeanClient,
+            6 => EfiIa32,
+            7 => EfiX64,
+            8 => EfiXscale,
+            9 => EfiBc,
+            10 => EfiArm32,
+            11 => EfiArm64,
+            12 => PowerPcOpenFirmware,
+            13 => PowerPcEpapr,
+            14 => PowerOpalV3,
+            15 => EfiX86Http,
+            16 => EfiX64Http,
+            17 => EfiBcHttp,
+            18 => EfiArm32Http,
+            19 => EfiArm64Http,
+            20 => X86BiosHttp,
+            21 => UbootArm32,
+            22 => UbootArm64,
+            23 => UbootArm32Http,
+            24 => UbootArm64Http,
+            25 => EfiRiscV32,
+            26 => EfiRiscV32Http,
+            27 => EfiRiscV64,
+            28 => EfiRiscV64Http,
+            29 => EfiRiscV128,
+            30 => EfiRiscV128Http,
+            31 => S390Basic,
+            32 => S390Extended,
+            33 => EfiMips32,
+            34 => EfiMips64,
+            35 => EfiSunway32,
+            36 => EfiSunway64,
+            37 => EfiLoongArch32,
+            38 => EfiLoongArch32Http,
+            39 => EfiLoongArch64,
+            40 => EfiLoongArch64Http,
+            41 => ArmRpiBoot,
+
+            _ => Other(value),
+        }
+    }
+}
+
+impl From<ClientArchitecture> for u16 {
+    fn from(value: ClientArchitecture) -> Self {
+        use self::ClientArchitecture::*;
+        match value {
+            X86Bios => 0,
+            NecPc98 => 1,
+            EfiItanium => 2,
+            DecAlpha => 3,
+            ArcX86 => 4,
+            IntelLeanClient => 5,
+            EfiIa32 => 6,
+            EfiX64 => 7,
+            EfiXscale => 8,
+            EfiBc => 9,
+            EfiArm32 => 10,
+            EfiArm64 => 11,
+            PowerPcOpenFirmware => 12,
+            PowerPcEpapr => 13,
+            PowerOpalV3 => 14,
+            EfiX86Http => 15,
+            EfiX64Http => 16,
+            EfiBcHttp => 17,
+            EfiArm32Http => 18,
+            EfiArm64Http => 19,
+            X86BiosHttp => 20,
+            UbootArm32 => 21,
+            UbootArm64 => 22,
+            UbootArm32Http => 23,
+            UbootArm64Http => 24,
+            EfiRiscV32 => 25,
+            EfiRiscV32Http => 26,
+            EfiRiscV64 => 27,
+            EfiRiscV64Http => 28,
+            EfiRiscV128 => 29,
+            EfiRiscV128Http => 30,
+            S390Basic => 31,
+            S390Extended => 32,
+            EfiMips32 => 33,
+            EfiMips64 => 34,
+            EfiSunway32 => 35,
+            EfiSunway64 => 36,
+            EfiLoongArch32 => 37,
+            EfiLoongArch32Http => 38,
+            EfiLoongArch64 => 39,
+            EfiLoongArch64Http => 40,
+            ArmRpiBoot => 41,
+
+            Other(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for ClientArchitecture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ClientArchitecture::*;
+        match self {
+            X86Bios => write!(f, "x86 BIOS"),
+            NecPc98 => write!(f, "NEC/PC98"),
+            EfiItanium => write!(f, "Itanium"),
+            DecAlpha => write!(f, "DEC Alpha"),
+            ArcX86 => write!(f, "Arc x86"),
+            IntelLeanClient => write!(f, "Intel Lean Client"),
+            EfiIa32 => write!(f, "x86 UEFI"),
+            EfiX64 => write!(f, "x64 UEFI"),
+            EfiXscale => write!(f, "EFI Xscale"),
+            EfiBc => write!(f, "EBC"),
+            EfiArm32 => write!(f, "ARM 32-bit UEFI"),
+            EfiArm64 => write!(f, "ARM 64-bit UEFI"),
+            PowerPcOpenFirmware => write!(f, "PowerPC Open Firmware"),
+            PowerPcEpapr => write!(f, "PowerPC ePAPR"),
+            PowerOpalV3 => write!(f, "POWER OPAL v3"),
+            EfiX86Http => write!(f, "x86 UEFI HTTP"),
+            EfiX64Http => write!(f, "x64 UEFI HTTP"),
+            EfiBcHttp => write!(f, "EBC from HTTP"),
+            EfiArm32Http => write!(f, "ARM 32-bit UEFI HTTP"),
+            EfiArm64Http => write!(f, "ARM 64-bit UEFI HTTP"),
+            X86BiosHttp => write!(f, "x86 BIOS HTTP"),
+            UbootArm32 => write!(f, "ARM 32-bit uboot"),
+            UbootArm64 => write!(f, "ARM 64-bit uboot"),
+            UbootArm32Http => write!(f, "ARM 32-bit uboot HTTP"),
+            UbootArm64Http => write!(f, "ARM 64-bit uboot HTTP"),
+            EfiRiscV32 => write!(f, "RISC-V 32-bit UEFI"),
+            EfiRiscV32Http => write!(f, "RISC-V 32-bit UEFI HTTP"),
+            EfiRiscV64 => write!(f, "RISC-V 64-bit UEFI"),
+            EfiRiscV64Http => write!(f, "RISC-V 64-bit UEFI HTTP"),
+            EfiRiscV128 => write!(f, "RISC-V 128-bit UEFI"),
+            EfiRiscV128Http => write!(f, "RISC-V 128-bit UEFI HTTP"),
+            S390Basic => write!(f, "s390 Basic"),
+            S390Extended => write!(f, "s390 Extended"),
+            EfiMips32 => write!(f, "MIPS 32-bit UEFI"),
+            EfiMips64 => write!(f, "MIPS 64-bit UEFI"),
+            EfiSunway32 => write!(f, "Sunway 32-bit UEFI"),
+            EfiSunway64 => write!(f, "Sunway 64-bit UEFI"),
+            EfiLoongArch32 => write!(f, "LoongArch 32-bit UEFI"),
+            EfiLoongArch32Http => write!(f, "LoongArch 32-bit UEFI HTTP"),
+            EfiLoongArch64 => write!(f, "LoongArch 64-bit UEFI"),
+            EfiLoongArch64Http => write!(f, "LoongArch 64-bit UEFI HTTP"),
+            ArmRpiBoot => write!(f, "arm rpiboot"),
+
+            Other(value) => write!(f, "architecture {}", value),
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! DHCP client FQDN option module.
+
+use std::{fmt, io};
+
+use super::DomainName;
+
+/// The `S` flag bit.
+const FLAG_S: u8 = 0b0000_0001;
+/// The `O` flag bit.
+const FLAG_O: u8 = 0b0000_0010;
+/// The `E` flag bit.
+const FLAG_E: u8 = 0b0000_0100;
+/// The `N` flag bit.
+const FLAG_N: u8 = 0b0000_1000;
+
+/// The flags and the deprecated RCODE octets.
+const SIZE_PREFIX: usize = 3;
+
+/// The RCODE value servers SHOULD send (RFC 4702 §2.2).
+pub const CLIENT_FQDN_RCODE_SERVER: u8 = 255;
+
+/// DHCP client FQDN option.
+///
+/// [RFC 4702](https://tools.ietf.org/html/rfc4702)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClientFqdn {
+    /// The `S` flag. The server should perform the A RR update.
+    pub server_update: bool,
+    /// The `O` flag. The server has overridden the client `S` flag preference.
+    pub server_override: bool,
+    /// The `E` flag. The domain name is in the canonical wire format, otherwise in ASCII.
+    pub canonical_encoding: bool,
+    /// The `N` flag. The server should not perform any DNS updates.
+    pub no_update: bool,
+    /// Deprecated, must be 0 in client messages.
+    pub rcode1: u8,
+    /// Deprecated, must be 0 in client messages.
+    pub rcode2: u8,
+    /// Empty if the client wants the server to provide the name.
+    pub domain_name: Option<DomainName>,
+}
+
+impl ClientFqdn {
+    /// Creates a client message option value with the canonical encoding.
+    pub fn new(domain_name: Option<DomainName>, server_update: bool, no_update: bool) -> Self {
+        ClientFqdn {
+            server_update: server_update && !no_update,
+            server_override: false,
+            canonical_encoding: true,
+            no_update,
+            rcode1: 0,
+            rcode2: 0,
+            domain_name,
+        }
+    }
+
+    /// Creates a server response to the client option.
+    ///
+    /// `server_update` is `true` if the server performs the A RR update itself.
+    /// The domain name and the encoding are echoed as they were received.
+    ///
+    /// RFC 4702 §2.1
+    /// The "O" bit indicates whether the server has overridden the client's
+    /// preference for the "S" bit. A client MUST set this bit to 0. A server
+    /// MUST set this bit to 1 if the "S" bit in its reply to the client does
+    /// not match the "S" bit received from the client.
+    pub fn reply(&self, server_update: bool) -> Self {
+        ClientFqdn {
+            server_update,
+            server_override: server_update != self.server_update,
+            canonical_encoding: self.canonical_encoding,
+            no_update: self.no_update && !server_update,
+            rcode1: CLIENT_FQDN_RCODE_SERVER,
+            rcode2: CLIENT_FQDN_RCODE_SERVER,
+            domain_name: self.domain_name.to_owned(),
+        }
+    }
+
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is too short or the domain name is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() < SIZE_PREFIX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Client FQDN is too short",
+            ));
+        }
+        let flags = src[0];
+        let canonical_encoding = flags & FLAG_E != 0;
+        let name = &src[SIZE_PREFIX..];
+        let domain_name = if name.is_empty() || name == [0] {
+            None
+        } else if canonical_encoding {
+            Some(DomainName::from_wire(name)?)
+        } else {
+            Some(DomainName::from_ascii(name)?)
+        };
+
+        Ok(ClientFqdn {
+            server_update: flags & FLAG_S != 0,
+            server_override: flags & FLAG_O != 0,
+            canonical_encoding,
+            no_update: flags & FLAG_N != 0,
+            rcode1: src[1],
+            rcode2: src[2],
+            domain_name,
+        })
+    }
+
+    /// Encodes the option value using the encoding specified by the `E` flag.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.server_update {
+            flags |= FLAG_S;
+        }
+        if self.server_override {
+            flags |= FLAG_O;
+        }
+        if self.canonical_encoding {
+            flags |= FLAG_E;
+        }
+        if self.no_update {
+            flags |= FLAG_N;
+        }
+
+        let mut dst = vec![flags, self.rcode1, self.rcode2];
+        if let Some(ref domain_name) = self.domain_name {
+            if self.canonical_encoding {
+                dst.extend_from_slice(&domain_name.to_wire());
+            } else {
+                dst.extend_from_slice(&domain_name.to_ascii());
+            }
+        }
+        dst
+    }
+}
+
+impl fmt::Display for ClientFqdn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{} {}/{} ",
+            if self.no_update { "N" } else { "-" },
+            if self.canonical_encoding { "E" } else { "-" },
+            if self.server_override { "O" } else { "-" },
+            if self.server_update { "S" } else { "-" },
+            self.rcode1,
+            self.rcode2,
+        )?;
+        match self.domain_name {
+            Some(ref domain_name) => write!(f, "{}", domain_name),
+            None => write!(f, "<empty>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_encoding() {
+        let src = [0b0000_0101, 0, 0, 4, b'h', b'o', b's', b't', 3, b'c', b'o', b'm', 0];
+        let client_fqdn = ClientFqdn::from_bytes(&src).unwrap();
+        assert!(client_fqdn.server_update);
+        assert!(client_fqdn.canonical_encoding);
+        let domain_name = client_fqdn.domain_name.as_ref().unwrap();
+        assert!(domain_name.is_fully_qualified());
+        assert_eq!(domain_name.to_string(), "host.com.");
+        assert_eq!(client_fqdn.to_bytes(), src.to_vec());
+    }
+
+    #[test]
+    fn ascii_encoding() {
+        let client_fqdn = ClientFqdn::from_bytes(b"\x00\x00\x00Host").unwrap();
+        assert!(!client_fqdn.canonical_encoding);
+        let domain_name = client_fqdn.domain_name.as_ref().unwrap();
+        assert!(!domain_name.is_fully_qualified());
+        assert_eq!(domain_name.labels(), &["host".to_owned()]);
+        assert_eq!(client_fqdn.to_bytes(), b"\x00\x00\x00host".to_vec());
+    }
+
+    #[test]
+    fn malformed_name_is_rejected() {
+        // the odd characters are only flagged
+        let client_fqdn =
+            ClientFqdn::from_bytes(&[0b0000_0100, 0, 0, 3, b'a', b'$', b'b', 0]).unwrap();
+        assert!(!client_fqdn.domain_name.unwrap().is_ldh());
+        assert!(ClientFqdn::from_bytes(&[0b0000_0100, 0, 0, 5, b'a', 0]).is_err());
+        assert!(ClientFqdn::from_bytes(b"\x00\x00\x00a..b").is_err());
+    }
+
+    #[test]
+    fn reply_flags() {
+        let name = "host.example.com".parse().ok();
+
+        let reply = ClientFqdn::new(name.clone(), false, false).reply(true);
+        assert!(reply.server_update && reply.server_override && !reply.no_update);
+        assert_eq!(reply.rcode1, CLIENT_FQDN_RCODE_SERVER);
+
+        let reply = ClientFqdn::new(name.clone(), true, false).reply(true);
+        assert!(reply.server_update && !reply.server_override);
+
+        let reply = ClientFqdn::new(name.clone(), false, true).reply(false);
+        assert!(!reply.server_update && !reply.server_override && reply.no_update);
+
+        let reply = ClientFqdn::new(name, true, false).reply(false);
+        assert!(!reply.server_update && reply.server_override);
+    }
+}
@@ -0,0 +1,288 @@
+//! DHCP user-defined options module.
+
+use std::{any::Any, collections::HashMap, fmt, io};
+
+#[cfg(feature = "serde")]
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{OptionTag, Options};
+
+/// A typed value of an option unknown to this crate, like a site-specific one (224-254).
+///
+/// [RFC 2132 §2](https://tools.ietf.org/html/rfc2132#section-2)
+pub trait CustomOption: fmt::Debug + Send + Sync {
+    /// Encodes the value into the option value bytes.
+    ///
+    /// # Errors
+    /// `io::Error` if the value cannot be encoded.
+    fn to_bytes(&self) -> io::Result<Vec<u8>>;
+
+    /// Allows downcasting to the concrete option type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Clones the value into a new box.
+    fn box_clone(&self) -> Box<dyn CustomOption>;
+}
+
+impl Clone for Box<dyn CustomOption> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// The values are equal if they are encoded into the same bytes.
+impl PartialEq for Box<dyn CustomOption> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.to_bytes(), other.to_bytes()) {
+            (Ok(ref left), Ok(ref right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Box<dyn CustomOption> {}
+
+/// The value of a user-defined option restored from its bytes by the deserializer,
+/// which has no decoders to make a typed value.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone)]
+struct RawOption(Vec<u8>);
+
+#[cfg(feature = "serde")]
+impl CustomOption for RawOption {
+    fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        Ok(self.0.to_owned())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn box_clone(&self) -> Box<dyn CustomOption> {
+        Box::new(self.clone())
+    }
+}
+
+/// Serializes `Options::custom` as `(code, bytes)` pairs encoded by `CustomOption::to_bytes`.
+///
+/// The values are deserialized as raw bytes, since the decoders are not available.
+#[cfg(feature = "serde")]
+pub(super) mod as_bytes {
+    use super::*;
+
+    /// The `Options::custom` values by their codes.
+    type CustomOptions = Vec<(u8, Box<dyn CustomOption>)>;
+
+    pub fn serialize<S: Serializer>(
+        custom: &[(u8, Box<dyn CustomOption>)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut pairs = Vec::with_capacity(custom.len());
+        for (code, value) in custom.iter() {
+            pairs.push((*code, value.to_bytes().map_err(ser::Error::custom)?));
+        }
+        pairs.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<CustomOptions, D::Error> {
+        let pairs: Vec<(u8, Vec<u8>)> = Vec::deserialize(deserializer)?;
+        Ok(pairs
+            .into_iter()
+            .map(|(code, value)| (code, Box::new(RawOption(value)) as Box<dyn CustomOption>))
+            .collect())
+    }
+}
+
+/// Decodes the option value into a typed one.
+pub type OptionDecoder = fn(&[u8]) -> io::Result<Box<dyn CustomOption>>;
+
+/// Maps the option codes unknown to this crate to their decoders.
+///
+/// The options with the registered codes are decoded into `Options::custom`
+/// by `Message::from_bytes_with` and the other unknown ones are kept raw.
+/// The built-in options are always decoded by this crate.
+#[derive(Clone, Default)]
+pub struct OptionRegistry {
+    decoders: HashMap<u8, OptionDecoder>,
+}
+
+impl OptionRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        OptionRegistry::default()
+    }
+
+    /// Registers the decoder for the option code replacing the previous one.
+    pub fn register(&mut self, code: u8, decoder: OptionDecoder) -> &mut Self {
+        self.decoders.insert(code, decoder);
+        self
+    }
+
+    /// Returns the decoder registered for the option code.
+    pub fn decoder(&self, code: u8) -> Option<OptionDecoder> {
+        self.decoders.get(&code).cloned()
+    }
+}
+
+impl Options {
+    /// Returns the user-defined option value.
+    pub fn custom(&self, code: u8) -> Option<&dyn CustomOption> {
+        self.custom
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Sets the user-defined option value replacing the previous one,
+    /// including the raw value of the option kept in `unknown`.
+    ///
+    /// # Errors
+    /// `io::Error` if the code belongs to a built-in option.
+    pub fn set_custom(&mut self, code: u8, value: Box<dyn CustomOption>) -> io::Result<()> {
+        match OptionTag::from(code) {
+            OptionTag::Unknown => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Option code belongs to a built-in option",
+                ))
+            }
+        }
+        match self.custom.iter_mut().find(|(c, _)| *c == code) {
+            Some(option) => option.1 = value,
+            None => self.custom.push((code, value)),
+        }
+        self.unknown.retain(|(c, _)| *c != code);
+        if !self.order.contains(&code) {
+            self.order.push(code);
+        }
+        Ok(())
+    }
+
+    /// Removes the user-defined option returning its value.
+    pub fn remove_custom(&mut self, code: u8) -> Option<Box<dyn CustomOption>> {
+        let index = self.custom.iter().position(|(c, _)| *c == code)?;
+        self.order.retain(|c| *c != code);
+        Some(self.custom.remove(index).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use v4::{constants::*, Message};
+
+    #[derive(Debug, Clone)]
+    struct SiteOption(u16);
+
+    impl CustomOption for SiteOption {
+        fn to_bytes(&self) -> io::Result<Vec<u8>> {
+            Ok(vec![(self.0 >> 8) as u8, self.0 as u8])
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn box_clone(&self) -> Box<dyn CustomOption> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn decode_site_option(src: &[u8]) -> io::Result<Box<dyn CustomOption>> {
+        if src.len() != 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid site option",
+            ));
+        }
+        Ok(Box::new(SiteOption(
+            u16::from(src[0]) << 8 | u16::from(src[1]),
+        )))
+    }
+
+    /// Makes a packet with a zeroed header followed by the options.
+    fn packet(options: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0u8; OFFSET_OPTIONS];
+        packet[OFFSET_MAGIC_COOKIE..OFFSET_OPTIONS].copy_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        packet.extend_from_slice(options);
+        packet
+    }
+
+    #[test]
+    fn custom_options_round_trip() {
+        let mut message = Message::from_bytes(&packet(&[255])).unwrap();
+        message
+            .options
+            .set_custom(224, Box::new(SiteOption(0x1234)))
+            .unwrap();
+        assert!(message
+            .options
+            .set_custom(OptionTag::Hostname as u8, Box::new(SiteOption(0)))
+            .is_err());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+
+        let mut registry = OptionRegistry::new();
+        registry.register(224, decode_site_option);
+        let decoded = Message::from_bytes_with(&buffer[..amount], &registry).unwrap();
+        let value = decoded.options.custom(224).unwrap();
+        assert_eq!(
+            value.as_any().downcast_ref::<SiteOption>().unwrap().0,
+            0x1234
+        );
+        assert!(decoded.options.unknown.is_empty());
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert!(decoded.options.custom(224).is_none());
+        assert_eq!(decoded.options.unknown, vec![(224, vec![0x12, 0x34])]);
+    }
+
+    #[test]
+    fn custom_option_replaces_the_unknown_one() {
+        let mut message = Message::from_bytes(&packet(&[224, 1, 7, 255])).unwrap();
+        assert_eq!(message.options.unknown, vec![(224, vec![7])]);
+        message
+            .options
+            .set_custom(224, Box::new(SiteOption(9)))
+            .unwrap();
+        assert!(message.options.unknown.is_empty());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let options = &buffer[OFFSET_OPTIONS..amount];
+        assert_eq!(&options[..5], &[224, 2, 0, 9, 255]);
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.options.unknown, vec![(224, vec![0, 9])]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_keeps_the_encoded_values() {
+        // a DHCPREQUEST for 192.168.0.10
+        let mut message =
+            Message::from_bytes(&packet(&[53, 1, 3, 50, 4, 192, 168, 0, 10, 255])).unwrap();
+        message
+            .options
+            .set_custom(224, Box::new(SiteOption(0x1234)))
+            .unwrap();
+
+        let json = ::serde_json::to_string(&message).unwrap();
+        assert!(json.contains(r#""dhcp_message_type":"DhcpRequest""#));
+        assert!(json.contains(r#""address_request":"192.168.0.10""#));
+        assert!(json.contains(r#""custom":[[224,[18,52]]]"#));
+
+        let decoded: Message = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.options.custom, message.options.custom);
+        let mut expected = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let mut actual = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut expected, None).unwrap();
+        assert_eq!(decoded.to_bytes(&mut actual, None).unwrap(), amount);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,354 @@
+//! DHCP domain name module.
+
+use std::{fmt, io, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The maximal label length in bytes.
+const SIZE_LABEL_MAX: usize = 63;
+/// The maximal domain name length in the wire format including the length octets.
+const SIZE_NAME_MAX: usize = 255;
+/// The two highest bits of a compression pointer.
+const POINTER_MASK: u8 = 0b1100_0000;
+/// The maximal offset a compression pointer can refer to.
+const POINTER_OFFSET_MAX: usize = 0x3fff;
+
+/// A validated domain name.
+///
+/// A name is either fully qualified (terminated with the root label)
+/// or partial, which the DHCP server is expected to complete.
+///
+/// [RFC 1035](https://tools.ietf.org/html/rfc1035)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DomainName {
+    labels: Vec<String>,
+    is_fully_qualified: bool,
+}
+
+impl DomainName {
+    /// Validates the labels and creates a domain name.
+    ///
+    /// # Errors
+    /// `io::Error` if a label is empty, too long or contains invalid characters,
+    /// or if the whole name is too long.
+    pub fn new(labels: Vec<String>, is_fully_qualified: bool) -> io::Result<Self> {
+        for label in labels.iter() {
+            Self::check_label(label.as_bytes())?;
+        }
+        Self::decoded(labels, is_fully_qualified)
+    }
+
+    /// Checks if the labels consist of letters, digits, hyphens and underscores
+    /// and do not start or end with a hyphen, which is required by `new`.
+    ///
+    /// The decoded names are not checked, so an odd octet sent by a peer
+    /// does not make the whole message undecodable.
+    pub fn is_ldh(&self) -> bool {
+        self.labels
+            .iter()
+            .all(|label| Self::check_label(label.as_bytes()).is_ok())
+    }
+
+    /// Creates a decoded name checking only the label and name lengths.
+    fn decoded(labels: Vec<String>, is_fully_qualified: bool) -> io::Result<Self> {
+        let mut size = if is_fully_qualified { 1 } else { 0 };
+        for label in labels.iter() {
+            if label.is_empty() || label.len() > SIZE_LABEL_MAX {
+                return Err(Self::error("Label length is invalid"));
+            }
+            size += 1 + label.len();
+        }
+        if size > SIZE_NAME_MAX {
+            return Err(Self::error("Domain name is too long"));
+        }
+        Ok(DomainName {
+            labels: labels
+                .iter()
+                .map(|label| label.to_ascii_lowercase())
+                .collect(),
+            is_fully_qualified,
+        })
+    }
+
+    /// The name labels in lowercase without the root one.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Checks if the name is terminated with the root label.
+    pub fn is_fully_qualified(&self) -> bool {
+        self.is_fully_qualified
+    }
+
+    /// Decodes the deprecated ASCII encoding like `host.example.com.`.
+    ///
+    /// The name is fully qualified if it ends with a dot.
+    ///
+    /// # Errors
+    /// `io::Error` if a label is empty or too long, or if the whole name is too long.
+    pub fn from_ascii(src: &[u8]) -> io::Result<Self> {
+        // some clients terminate the name with zeros
+        let len = src.iter().rposition(|byte| *byte != 0).map_or(0, |i| i + 1);
+        let src = String::from_utf8_lossy(&src[..len]);
+        let src = src.as_ref();
+        let is_fully_qualified = src.ends_with('.');
+        let mut labels: Vec<String> = src.split('.').map(|label| label.to_owned()).collect();
+        if is_fully_qualified {
+            labels.pop();
+        }
+        if labels.len() == 1 && labels[0].is_empty() {
+            labels.clear();
+        }
+        Self::decoded(labels, is_fully_qualified)
+    }
+
+    /// Encodes the name in the deprecated ASCII encoding.
+    pub fn to_ascii(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+
+    /// Decodes the canonical uncompressed wire format.
+    ///
+    /// The name is partial if it is not terminated with the zero-length root label.
+    ///
+    /// # Errors
+    /// `io::Error` if the name is too long or contains data after the root label.
+    pub fn from_wire(src: &[u8]) -> io::Result<Self> {
+        let mut labels = Vec::new();
+        let mut is_fully_qualified = false;
+        let mut i = 0;
+        while i < src.len() {
+            let len = src[i] as usize;
+            i += 1;
+            if len == 0 {
+                if i != src.len() {
+                    return Err(Self::error("Data after the root label"));
+                }
+                is_fully_qualified = true;
+                break;
+            }
+            if len > SIZE_LABEL_MAX || i + len > src.len() {
+                return Err(Self::error("Label length is invalid"));
+            }
+            labels.push(String::from_utf8_lossy(&src[i..i + len]).to_string());
+            i += len;
+        }
+        Self::decoded(labels, is_fully_qualified)
+    }
+
+    /// Encodes the name in the canonical uncompressed wire format.
+    pub fn to_wire(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(SIZE_NAME_MAX);
+        for label in self.labels.iter() {
+            dst.push(label.len() as u8);
+            dst.extend_from_slice(label.as_bytes());
+        }
+        if self.is_fully_qualified {
+            dst.push(0);
+        }
+        dst
+    }
+
+    /// Encodes a list of names using the message compression (RFC 1035 §4.1.4).
+    ///
+    /// Every name is written fully qualified as RFC 3397 requires.
+    /// A name suffix which has been already written is replaced with a pointer to it.
+    pub fn to_compressed_list(names: &[DomainName]) -> Vec<u8> {
+        let mut dst = Vec::new();
+        let mut suffixes: Vec<(&[String], usize)> = Vec::new();
+        for name in names.iter() {
+            let mut pointer = None;
+            for i in 0..name.labels.len() {
+                let suffix = &name.labels[i..];
+                if let Some(&(_, offset)) = suffixes.iter().find(|(s, _)| *s == suffix) {
+                    pointer = Some(offset);
+                    break;
+                }
+                if dst.len() <= POINTER_OFFSET_MAX {
+                    suffixes.push((suffix, dst.len()));
+                }
+                dst.push(name.labels[i].len() as u8);
+                dst.extend_from_slice(name.labels[i].as_bytes());
+            }
+            match pointer {
+                Some(offset) => {
+                    dst.push(POINTER_MASK | (offset >> 8) as u8);
+                    dst.push(offset as u8);
+                }
+                None => dst.push(0),
+            }
+        }
+        dst
+    }
+
+    /// Decodes a list of compressed names (RFC 1035 §4.1.4).
+    ///
+    /// A pointer may only refer to the data preceding the labels it terminates,
+    /// so forward references and loops are rejected.
+    ///
+    /// # Errors
+    /// `io::Error` if a name is abrupted or invalid, or a pointer refers forward.
+    pub fn from_compressed_list(src: &[u8]) -> io::Result<Vec<DomainName>> {
+        let mut names = Vec::new();
+        let mut i = 0;
+        while i < src.len() {
+            let mut labels = Vec::new();
+            let mut position = i;
+            let mut start = i;
+            let mut next = None;
+            loop {
+                if position >= src.len() {
+                    return Err(Self::error("Domain name is abrupted"));
+                }
+                let len = src[position] as usize;
+                if src[position] & POINTER_MASK == POINTER_MASK {
+                    if position + 1 >= src.len() {
+                        return Err(Self::error("Compression pointer is abrupted"));
+                    }
+                    let offset = (len & !(POINTER_MASK as usize)) << 8 | src[position + 1] as usize;
+                    // the targets strictly decrease, which makes loops impossible
+                    if offset >= start {
+                        return Err(Self::error("Compression pointer refers forward"));
+                    }
+                    if next.is_none() {
+                        next = Some(position + 2);
+                    }
+                    position = offset;
+                    start = offset;
+                    continue;
+                }
+                if src[position] & POINTER_MASK != 0 {
+                    return Err(Self::error("Label type is invalid"));
+                }
+                position += 1;
+                if len == 0 {
+                    break;
+                }
+                if position + len > src.len() {
+                    return Err(Self::error("Domain name is abrupted"));
+                }
+                labels.push(String::from_utf8_lossy(&src[position..position + len]).to_string());
+                position += len;
+            }
+            names.push(Self::decoded(labels, true)?);
+            i = next.unwrap_or(position);
+        }
+        Ok(names)
+    }
+
+    /// Letters, digits, hyphens and underscores are allowed, but a label cannot
+    /// start or end with a hyphen.
+    fn check_label(label: &[u8]) -> io::Result<()> {
+        if label.is_empty() || label.len() > SIZE_LABEL_MAX {
+            return Err(Self::error("Label length is invalid"));
+        }
+        if label[0] == b'-' || label[label.len() - 1] == b'-' {
+            return Err(Self::error("Label starts or ends with a hyphen"));
+        }
+        if !label
+            .iter()
+            .all(|byte| byte.is_ascii_alphanumeric() || *byte == b'-' || *byte == b'_')
+        {
+            return Err(Self::error("Label contains invalid characters"));
+        }
+        Ok(())
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+impl FromStr for DomainName {
+    type Err = io::Error;
+
+    /// Unlike `from_ascii`, requires the labels to be valid as `new` does.
+    fn from_str(s: &str) -> io::Result<Self> {
+        let name = Self::from_ascii(s.as_bytes())?;
+        if !name.is_ldh() {
+            return Err(Self::error("Label contains invalid characters"));
+        }
+        Ok(name)
+    }
+}
+
+impl fmt::Display for DomainName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.labels.join("."))?;
+        if self.is_fully_qualified {
+            write!(f, ".")?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialized as a string like `host.example.com.`.
+#[cfg(feature = "serde")]
+impl Serialize for DomainName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for DomainName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_list_round_trip() {
+        let names: Vec<DomainName> = vec![
+            "eng.apple.com.".parse().unwrap(),
+            "marketing.apple.com.".parse().unwrap(),
+        ];
+        // the example from RFC 3397 §4
+        let expected = b"\x03eng\x05apple\x03com\x00\x09marketing\xc0\x04".to_vec();
+        assert_eq!(DomainName::to_compressed_list(&names), expected);
+        assert_eq!(DomainName::from_compressed_list(&expected).unwrap(), names);
+    }
+
+    #[test]
+    fn forward_pointer_is_rejected() {
+        assert!(DomainName::from_compressed_list(&[0xc0, 0x02, 0x01, b'a', 0x00]).is_err());
+        assert!(DomainName::from_compressed_list(&[0xc0, 0x00]).is_err());
+    }
+
+    #[test]
+    fn pointer_loop_is_rejected() {
+        assert!(DomainName::from_compressed_list(&[0x01, b'a', 0xc0, 0x00]).is_err());
+        assert!(
+            DomainName::from_compressed_list(&[0x01, b'a', 0x00, 0x01, b'b', 0xc0, 0x03]).is_err()
+        );
+    }
+
+    #[test]
+    fn odd_labels_are_decoded_but_not_built() {
+        let name = DomainName::from_wire(b"\x03a b\x07example\x00").unwrap();
+        assert_eq!(name.labels(), &["a b".to_owned(), "example".to_owned()]);
+        assert!(!name.is_ldh());
+        let names = DomainName::from_compressed_list(b"\x04-foo\x00").unwrap();
+        assert!(!names[0].is_ldh());
+
+        assert!(DomainName::new(vec!["a b".to_owned()], true).is_err());
+        assert!("a b.example.".parse::<DomainName>().is_err());
+        assert!("ab.example.".parse::<DomainName>().unwrap().is_ldh());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_uses_the_text_form() {
+        let names: Vec<DomainName> = vec!["example.com.".parse().unwrap()];
+        let json = ::serde_json::to_string(&names).unwrap();
+        assert_eq!(json, r#"["example.com."]"#);
+        let decoded: Vec<DomainName> = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, names);
+    }
+}
@@ -2,8 +2,13 @@
 
 use std::fmt;
 
-/// DHCP message type (RFC 2131 only).
-#[derive(Debug, Clone, Copy)]
+/// DHCP message type.
+///
+/// [RFC 2131](https://tools.ietf.org/html/rfc2131)
+/// [RFC 3203](https://tools.ietf.org/html/rfc3203)
+/// [RFC 4388](https://tools.ietf.org/html/rfc4388)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MessageType {
     Undefined = 0,
     DhcpDiscover,
@@ -14,6 +19,19 @@ pub enum MessageType {
     DhcpNak,
     DhcpRelease,
     DhcpInform,
+
+    /*
+    RFC 3203 (DHCP reconfigure extension)
+    */
+    DhcpForceRenew,
+
+    /*
+    RFC 4388 (Dynamic Host Configuration Protocol (DHCP) Leasequery)
+    */
+    DhcpLeaseQuery = 10,
+    DhcpLeaseUnassigned,
+    DhcpLeaseUnknown,
+    DhcpLeaseActive,
 }
 
 impl fmt::Display for MessageType {
@@ -28,6 +46,11 @@ impl fmt::Display for MessageType {
             DhcpNak => write!(f, "DHCPNAK"),
             DhcpRelease => write!(f, "DHCPRELEASE"),
             DhcpInform => write!(f, "DHCPINFORM"),
+            DhcpForceRenew => write!(f, "DHCPFORCERENEW"),
+            DhcpLeaseQuery => write!(f, "DHCPLEASEQUERY"),
+            DhcpLeaseUnassigned => write!(f, "DHCPLEASEUNASSIGNED"),
+            DhcpLeaseUnknown => write!(f, "DHCPLEASEUNKNOWN"),
+            DhcpLeaseActive => write!(f, "DHCPLEASEACTIVE"),
 
             Undefined => write!(f, "UNDEFINED"),
         }
@@ -46,6 +69,11 @@ impl From<u8> for MessageType {
             6 => DhcpNak,
             7 => DhcpRelease,
             8 => DhcpInform,
+            9 => DhcpForceRenew,
+            10 => DhcpLeaseQuery,
+            11 => DhcpLeaseUnassigned,
+            12 => DhcpLeaseUnknown,
+            13 => DhcpLeaseActive,
 
             _ => Undefined,
         }
@@ -1,10 +1,48 @@
 //! DHCP options module.
 
+mod authentication;
+mod client_architecture;
+mod client_fqdn;
+mod custom;
+mod domain_name;
 mod message_type;
 mod option_tag;
+mod option_value;
 mod overload;
+mod pxe;
+mod relay_agent_information;
+mod sip_servers;
+mod user_class;
+mod vendor_identifying;
+mod vendor_specific;
 
-pub use self::{message_type::MessageType, option_tag::OptionTag, overload::Overload};
+pub use self::{
+    authentication::{
+        hmac_md5, Authentication, AUTHENTICATION_ALGORITHM_HMAC_MD5,
+        AUTHENTICATION_PROTOCOL_DELAYED, AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY,
+        AUTHENTICATION_RDM_MONOTONIC, RECONFIGURE_KEY_TYPE_HMAC_MD5, RECONFIGURE_KEY_TYPE_KEY,
+        SIZE_HMAC_MD5,
+    },
+    client_architecture::ClientArchitecture,
+    client_fqdn::{ClientFqdn, CLIENT_FQDN_RCODE_SERVER},
+    custom::{CustomOption, OptionDecoder, OptionRegistry},
+    domain_name::DomainName,
+    message_type::MessageType,
+    option_tag::OptionTag,
+    option_value::OptionValue,
+    overload::Overload,
+    pxe::{
+        MachineIdentifier, NetworkInterfaceIdentifier, PxeBootItem, PxeBootMenuItem,
+        PxeBootServer, PxeMenuPrompt, PxeOptions, PXE_CLASS_ID_PREFIX,
+        PXE_DISCOVERY_DIRECT_DOWNLOAD, PXE_DISCOVERY_NO_BROADCAST, PXE_DISCOVERY_NO_MULTICAST,
+        PXE_DISCOVERY_SERVER_LIST_ONLY,
+    },
+    relay_agent_information::{RelayAgentInformation, RELAY_AGENT_FLAG_UNICAST},
+    sip_servers::SipServers,
+    user_class::UserClass,
+    vendor_identifying::{VendorClassData, VendorSpecificData},
+    vendor_specific::{VendorDecoder, VendorInformation, VendorOptions, VendorRegistry},
+};
 
 use std::net::Ipv4Addr;
 
@@ -13,8 +51,23 @@ use std::net::Ipv4Addr;
 /// Implemented completely with `Option` for better flexibility and polymorphism.
 ///
 /// [RFC 2132](https://tools.ietf.org/html/rfc2132)
+/// [RFC 3004](https://tools.ietf.org/html/rfc3004)
+/// [RFC 3011](https://tools.ietf.org/html/rfc3011)
+/// [RFC 3046](https://tools.ietf.org/html/rfc3046)
+/// [RFC 3118](https://tools.ietf.org/html/rfc3118)
+/// [RFC 4388](https://tools.ietf.org/html/rfc4388)
+/// [RFC 4578](https://tools.ietf.org/html/rfc4578)
+/// [RFC 4039](https://tools.ietf.org/html/rfc4039)
+/// [RFC 4702](https://tools.ietf.org/html/rfc4702)
+/// [RFC 3397](https://tools.ietf.org/html/rfc3397)
+/// [RFC 3361](https://tools.ietf.org/html/rfc3361)
 /// [RFC 3442](https://tools.ietf.org/html/rfc3442)
-#[derive(Default)]
+/// [RFC 3925](https://tools.ietf.org/html/rfc3925)
+/// [RFC 6704](https://tools.ietf.org/html/rfc6704)
+/// [RFC 8910](https://tools.ietf.org/html/rfc8910)
+/// [RFC 8925](https://tools.ietf.org/html/rfc8925)
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Options {
     /*
     RFC 2132
@@ -112,8 +165,100 @@ pub struct Options {
     pub street_talk_servers: Option<Vec<Ipv4Addr>>,
     pub stda_servers: Option<Vec<Ipv4Addr>>,
 
+    /*
+    RFC 3004 (The User Class Option for DHCP)
+    */
+    pub user_class: Option<UserClass>,
+
+    /*
+    RFC 4039 (Rapid Commit Option for the Dynamic Host Configuration Protocol version 4 (DHCPv4))
+    */
+    // The option has no value and is only checked for presence.
+    pub rapid_commit: Option<()>,
+
+    /*
+    RFC 4702 (The DHCP Client FQDN Option)
+    */
+    pub client_fqdn: Option<ClientFqdn>,
+
+    /*
+    RFC 3046 (DHCP Relay Agent Information Option)
+    */
+    pub relay_agent_information: Option<RelayAgentInformation>,
+
+    /*
+    RFC 3118 (Authentication for DHCP Messages)
+    */
+    pub authentication: Option<Authentication>,
+
+    /*
+    RFC 4388 (Dynamic Host Configuration Protocol (DHCP) Leasequery)
+    */
+    pub client_last_transaction_time: Option<u32>,
+    pub associated_ip: Option<Vec<Ipv4Addr>>,
+
+    /*
+    RFC 4578 (DHCP Options for the Intel Preboot eXecution Environment (PXE))
+    */
+    pub client_system_architecture: Option<Vec<ClientArchitecture>>,
+    pub client_network_interface_identifier: Option<NetworkInterfaceIdentifier>,
+    pub client_machine_identifier: Option<MachineIdentifier>,
+
+    /*
+    RFC 8925 (IPv6-Only Preferred Option for DHCPv4)
+    */
+    // The number of seconds the client should stop DHCPv4 for (V6ONLY_WAIT).
+    pub ipv6_only_preferred: Option<u32>,
+
+    /*
+    RFC 8910 (Captive-Portal Identification in DHCP and Router Advertisements (RAs))
+    */
+    // The URI of the captive portal API endpoint.
+    pub captive_portal: Option<String>,
+
+    /*
+    RFC 3011 (The IPv4 Subnet Selection Option for DHCP)
+    */
+    // The subnet the address must be allocated on instead of the one chosen by `giaddr`.
+    pub subnet_selection: Option<Ipv4Addr>,
+
+    /*
+    RFC 3397 (Dynamic Host Configuration Protocol (DHCP) Domain Search Option)
+    */
+    pub domain_search: Option<Vec<DomainName>>,
+
+    /*
+    RFC 3361 (DHCP-for-IPv4 Option for Session Initiation Protocol (SIP) Servers)
+    */
+    pub sip_servers: Option<SipServers>,
+
     /*
     RFC 3442 (The Classless Static Route Option)
     */
     pub classless_static_routes: Option<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>>,
+
+    /*
+    RFC 3925 (Vendor-Identifying Vendor Options for DHCPv4)
+    */
+    pub vi_vendor_class: Option<Vec<VendorClassData>>,
+    pub vi_vendor_specific: Option<Vec<VendorSpecificData>>,
+
+    /*
+    RFC 6704 (Forcerenew Nonce Authentication)
+    */
+    // The algorithms the client supports for the reconfigure key authentication.
+    pub forcerenew_nonce_capable: Option<Vec<u8>>,
+
+    /*
+    Unknown and unsupported options
+    */
+    // Kept as raw `(code, value)` pairs in the order of appearance and written back verbatim.
+    pub unknown: Vec<(u8, Vec<u8>)>,
+    // Decoded by the decoders of `OptionRegistry` in the order of appearance.
+    #[cfg_attr(feature = "serde", serde(with = "custom::as_bytes"))]
+    pub custom: Vec<(u8, Box<dyn CustomOption>)>,
+
+    /// The codes of the options in the order they were received or set.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) order: Vec<u8>,
 }
@@ -1,7 +1,8 @@
 //! DHCP option tags module.
 
 /// DHCP options codes.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OptionTag {
     Unknown = -1,
     Pad = 0,
@@ -102,11 +103,85 @@ pub enum OptionTag {
     StreetTalkServers,
     StdaServers,
 
+    /*
+    RFC 3004 (The User Class Option for DHCP)
+    */
+    UserClass,
+
+    /*
+    RFC 4039 (Rapid Commit Option for the Dynamic Host Configuration Protocol version 4 (DHCPv4))
+    */
+    RapidCommit = 80,
+
+    /*
+    RFC 4702 (The DHCP Client FQDN Option)
+    */
+    ClientFqdn,
+
+    /*
+    RFC 3046 (DHCP Relay Agent Information Option)
+    */
+    RelayAgentInformation,
+
+    /*
+    RFC 3118 (Authentication for DHCP Messages)
+    */
+    Authentication = 90,
+
+    /*
+    RFC 4388 (Dynamic Host Configuration Protocol (DHCP) Leasequery)
+    */
+    ClientLastTransactionTime = 91,
+    AssociatedIp,
+
+    /*
+    RFC 4578 (DHCP Options for the Intel Preboot eXecution Environment (PXE))
+    */
+    ClientSystemArchitecture = 93,
+    ClientNetworkInterfaceIdentifier,
+    ClientMachineIdentifier = 97,
+
+    /*
+    RFC 8925 (IPv6-Only Preferred Option for DHCPv4)
+    */
+    Ipv6OnlyPreferred = 108,
+
+    /*
+    RFC 8910 (Captive-Portal Identification in DHCP and Router Advertisements (RAs))
+    */
+    CaptivePortal = 114,
+
+    /*
+    RFC 3011 (The IPv4 Subnet Selection Option for DHCP)
+    */
+    SubnetSelection = 118,
+
+    /*
+    RFC 3397 (Dynamic Host Configuration Protocol (DHCP) Domain Search Option)
+    */
+    DomainSearch = 119,
+
+    /*
+    RFC 3361 (DHCP-for-IPv4 Option for Session Initiation Protocol (SIP) Servers)
+    */
+    SipServers,
+
     /*
     RFC 3442 (The Classless Static Route Option)
     */
     ClasslessStaticRoutes = 121,
 
+    /*
+    RFC 3925 (Vendor-Identifying Vendor Options for DHCPv4)
+    */
+    ViVendorClass = 124,
+    ViVendorSpecific,
+
+    /*
+    RFC 6704 (Forcerenew Nonce Authentication)
+    */
+    ForcerenewNonceCapable = 145,
+
     End = 255,
 }
 
@@ -191,8 +266,27 @@ impl From<u8> for OptionTag {
             74 => IrcServers,
             75 => StreetTalkServers,
             76 => StdaServers,
+            77 => UserClass,
+
+            80 => RapidCommit,
+            81 => ClientFqdn,
+            82 => RelayAgentInformation,
+            90 => Authentication,
+            91 => ClientLastTransactionTime,
+            92 => AssociatedIp,
+            93 => ClientSystemArchitecture,
+            94 => ClientNetworkInterfaceIdentifier,
+            97 => ClientMachineIdentifier,
+            108 => Ipv6OnlyPreferred,
+            114 => CaptivePortal,
+            118 => SubnetSelection,
+            119 => DomainSearch,
+            120 => SipServers,
 
             121 => ClasslessStaticRoutes,
+            124 => ViVendorClass,
+            125 => ViVendorSpecific,
+            145 => ForcerenewNonceCapable,
 
             255 => End,
             _ => Unknown,
@@ -0,0 +1,347 @@
+//! DHCP option values module.
+
+use std::{fmt, io, net::Ipv4Addr};
+
+use super::{
+    Authentication, ClientArchitecture, ClientFqdn, CustomOption, DomainName, MachineIdentifier,
+    MessageType, NetworkInterfaceIdentifier, OptionTag, Options, Overload, RelayAgentInformation,
+    SipServers, UserClass, VendorClassData, VendorSpecificData,
+};
+
+/// A DHCP option value of any type.
+///
+/// Used to access the `Options` fields by their tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    /// The option has no value and is only checked for presence.
+    Empty,
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    Ipv4(Ipv4Addr),
+    String(String),
+    Bytes(Vec<u8>),
+    U16List(Vec<u16>),
+    Ipv4List(Vec<Ipv4Addr>),
+    Ipv4Pairs(Vec<(Ipv4Addr, Ipv4Addr)>),
+    /// `(subnet number, subnet mask, router)` triplets.
+    ClasslessStaticRoutes(Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>),
+    Overload(Overload),
+    MessageType(MessageType),
+    ClientFqdn(ClientFqdn),
+    RelayAgentInformation(RelayAgentInformation),
+    Authentication(Authentication),
+    ClientArchitectures(Vec<ClientArchitecture>),
+    NetworkInterfaceIdentifier(NetworkInterfaceIdentifier),
+    MachineIdentifier(MachineIdentifier),
+    DomainNames(Vec<DomainName>),
+    SipServers(SipServers),
+    UserClass(UserClass),
+    VendorClass(Vec<VendorClassData>),
+    VendorSpecific(Vec<VendorSpecificData>),
+    /// A user-defined option, see `OptionRegistry`.
+    Custom(Box<dyn CustomOption>),
+}
+
+/// Prints the lists with `Display` joining the elements with commas.
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::OptionValue::*;
+        match self {
+            Empty => write!(f, "()"),
+            U8(value) => write!(f, "{:?}", value),
+            U16(value) => write!(f, "{:?}", value),
+            U32(value) => write!(f, "{:?}", value),
+            Ipv4(value) => write!(f, "{:?}", value),
+            String(value) => write!(f, "{:?}", value),
+            Bytes(value) => write!(f, "{:?}", value),
+            U16List(value) => write!(f, "{:?}", value),
+            Ipv4List(value) => write!(f, "{:?}", value),
+            Ipv4Pairs(value) => write!(f, "{:?}", value),
+            ClasslessStaticRoutes(value) => write!(f, "{:?}", value),
+            Overload(value) => write!(f, "{}", value),
+            MessageType(value) => write!(f, "{}", value),
+            ClientFqdn(value) => write!(f, "{}", value),
+            RelayAgentInformation(value) => write!(f, "{:?}", value),
+            Authentication(value) => write!(f, "{}", value),
+            ClientArchitectures(value) => write!(f, "{}", join(value)),
+            NetworkInterfaceIdentifier(value) => write!(f, "{}", value),
+            MachineIdentifier(value) => write!(f, "{}", value),
+            DomainNames(value) => write!(f, "{}", join(value)),
+            SipServers(value) => write!(f, "{}", value),
+            UserClass(value) => write!(f, "{}", value),
+            VendorClass(value) => write!(f, "{}", join(value)),
+            VendorSpecific(value) => write!(f, "{}", join(value)),
+            Custom(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+/// Generates the accessors mapping the option tags to the `Options` fields.
+macro_rules! tagged_options (
+    ($($tag:ident => $field:ident: $variant:ident,)*) => (
+        /// The tags of the options stored in the `Options` fields.
+        const TAGS: &[OptionTag] = &[$(OptionTag::$tag,)* OptionTag::RapidCommit];
+
+        impl OptionTag {
+            /// The name of the `Options` field the option is stored in.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(OptionTag::$tag => stringify!($field),)*
+                    OptionTag::RapidCommit => "rapid_commit",
+                    OptionTag::Pad => "pad",
+                    OptionTag::End => "end",
+                    OptionTag::Unknown => "unknown",
+                }
+            }
+        }
+
+        impl Options {
+            /// Returns a copy of the option value.
+            pub fn get(&self, tag: OptionTag) -> Option<OptionValue> {
+                match tag {
+                    $(OptionTag::$tag => self.$field.clone().map(OptionValue::$variant),)*
+                    OptionTag::RapidCommit => self.rapid_commit.map(|_| OptionValue::Empty),
+                    _ => None,
+                }
+            }
+
+            /// Checks if the option is present.
+            pub fn contains(&self, tag: OptionTag) -> bool {
+                match tag {
+                    $(OptionTag::$tag => self.$field.is_some(),)*
+                    OptionTag::RapidCommit => self.rapid_commit.is_some(),
+                    _ => false,
+                }
+            }
+
+            /// Sets the option value replacing the previous one.
+            ///
+            /// A new option is placed after the present ones in the iteration order.
+            ///
+            /// # Errors
+            /// `io::Error` if the value type does not match the tag.
+            pub fn set(&mut self, tag: OptionTag, value: OptionValue) -> io::Result<()> {
+                match (tag, value) {
+                    $((OptionTag::$tag, OptionValue::$variant(value)) => {
+                        self.$field = Some(value)
+                    })*
+                    (OptionTag::RapidCommit, OptionValue::Empty) => self.rapid_commit = Some(()),
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "Option value type does not match the tag",
+                        ))
+                    }
+                }
+                if !self.order.contains(&(tag as u8)) {
+                    self.order.push(tag as u8);
+                }
+                Ok(())
+            }
+
+            /// Removes the option returning its value.
+            pub fn remove(&mut self, tag: OptionTag) -> Option<OptionValue> {
+                let value = match tag {
+                    $(OptionTag::$tag => self.$field.take().map(OptionValue::$variant),)*
+                    OptionTag::RapidCommit => self.rapid_commit.take().map(|_| OptionValue::Empty),
+                    _ => None,
+                };
+                self.order.retain(|code| *code != tag as u8);
+                value
+            }
+        }
+    );
+);
+
+tagged_options! {
+    SubnetMask => subnet_mask: Ipv4,
+    TimeOffset => time_offset: U32,
+    Routers => routers: Ipv4List,
+    TimeServers => time_servers: Ipv4List,
+    NameServers => name_servers: Ipv4List,
+    DomainNameServers => domain_name_servers: Ipv4List,
+    LogServers => log_servers: Ipv4List,
+    QuotesServers => quotes_servers: Ipv4List,
+    LprServers => lpr_servers: Ipv4List,
+    ImpressServers => impress_servers: Ipv4List,
+    RlpServers => rlp_servers: Ipv4List,
+    Hostname => hostname: String,
+    BootFileSize => boot_file_size: U16,
+    MeritDumpFile => merit_dump_file: String,
+    DomainName => domain_name: String,
+    SwapServer => swap_server: Ipv4,
+    RootPath => root_path: String,
+    ExtensionsPath => extensions_path: String,
+    ForwardOnOff => forward_on_off: U8,
+    NonLocalSourceRouteOnOff => non_local_source_route_on_off: U8,
+    PolicyFilters => policy_filters: Ipv4Pairs,
+    MaxDatagramReassemblySize => max_datagram_reassembly_size: U16,
+    DefaultIpTtl => default_ip_ttl: U8,
+    MtuTimeout => mtu_timeout: U32,
+    MtuPlateau => mtu_plateau: U16List,
+    MtuInterface => mtu_interface: U16,
+    MtuSubnet => mtu_subnet: U8,
+    BroadcastAddress => broadcast_address: Ipv4,
+    MaskRecovery => mask_recovery: U8,
+    MaskSupplier => mask_supplier: U8,
+    PerformRouterDiscovery => perform_router_discovery: U8,
+    RouterSolicitationAddress => router_solicitation_address: Ipv4,
+    StaticRoutes => static_routes: Ipv4Pairs,
+    TrailerEncapsulation => trailer_encapsulation: U8,
+    ArpTimeout => arp_timeout: U32,
+    EthernetEncapsulation => ethernet_encapsulation: U8,
+    DefaultTcpTtl => default_tcp_ttl: U8,
+    KeepaliveTime => keepalive_time: U32,
+    KeepaliveData => keepalive_data: U8,
+    NisDomain => nis_domain: String,
+    NisServers => nis_servers: Ipv4List,
+    NtpServers => ntp_servers: Ipv4List,
+    VendorSpecific => vendor_specific: Bytes,
+    NetbiosNameServers => netbios_name_servers: Ipv4List,
+    NetbiosDistributionServers => netbios_distribution_servers: Ipv4List,
+    NetbiosNodeType => netbios_node_type: U8,
+    NetbiosScope => netbios_scope: String,
+    XWindowFontServers => x_window_font_servers: Ipv4List,
+    XWindowManagerServers => x_window_manager_servers: Ipv4List,
+    AddressRequest => address_request: Ipv4,
+    AddressTime => address_time: U32,
+    Overload => overload: Overload,
+    DhcpMessageType => dhcp_message_type: MessageType,
+    DhcpServerId => dhcp_server_id: Ipv4,
+    ParameterList => parameter_list: Bytes,
+    DhcpMessage => dhcp_message: String,
+    DhcpMaxMessageSize => dhcp_max_message_size: U16,
+    RenewalTime => renewal_time: U32,
+    RebindingTime => rebinding_time: U32,
+    ClassId => class_id: Bytes,
+    ClientId => client_id: Bytes,
+    NetwareIpDomain => netware_ip_domain: Bytes,
+    NetwareIpOption => netware_ip_option: Bytes,
+    NisDomainName => nis_v3_domain_name: String,
+    NisServerAddress => nis_v3_servers: Ipv4List,
+    ServerName => server_name: String,
+    BootfileName => bootfile_name: String,
+    HomeAgentAddresses => home_agent_addresses: Ipv4List,
+    SmtpServers => smtp_servers: Ipv4List,
+    Pop3Servers => pop3_servers: Ipv4List,
+    NntpServers => nntp_servers: Ipv4List,
+    WwwServers => www_servers: Ipv4List,
+    FingerServers => finger_servers: Ipv4List,
+    IrcServers => irc_servers: Ipv4List,
+    StreetTalkServers => street_talk_servers: Ipv4List,
+    StdaServers => stda_servers: Ipv4List,
+    UserClass => user_class: UserClass,
+    ClientFqdn => client_fqdn: ClientFqdn,
+    RelayAgentInformation => relay_agent_information: RelayAgentInformation,
+    Authentication => authentication: Authentication,
+    ClientLastTransactionTime => client_last_transaction_time: U32,
+    AssociatedIp => associated_ip: Ipv4List,
+    ClientSystemArchitecture => client_system_architecture: ClientArchitectures,
+    ClientNetworkInterfaceIdentifier => client_network_interface_identifier:
+        NetworkInterfaceIdentifier,
+    ClientMachineIdentifier => client_machine_identifier: MachineIdentifier,
+    Ipv6OnlyPreferred => ipv6_only_preferred: U32,
+    CaptivePortal => captive_portal: String,
+    SubnetSelection => subnet_selection: Ipv4,
+    DomainSearch => domain_search: DomainNames,
+    SipServers => sip_servers: SipServers,
+    ClasslessStaticRoutes => classless_static_routes: ClasslessStaticRoutes,
+    ViVendorClass => vi_vendor_class: VendorClass,
+    ViVendorSpecific => vi_vendor_specific: VendorSpecific,
+    ForcerenewNonceCapable => forcerenew_nonce_capable: Bytes,
+}
+
+impl Options {
+    /// Iterates over the present options as `(code, value)` pairs.
+    ///
+    /// The options are yielded in the order they were received or set,
+    /// the ones assigned directly to the fields are yielded afterwards by their codes.
+    /// The user-defined options are yielded as `OptionValue::Custom`
+    /// and the other unknown ones as `OptionValue::Bytes`.
+    pub fn iter(&self) -> ::std::vec::IntoIter<(u8, OptionValue)> {
+        let mut is_yielded = [false; 256];
+        let mut result = Vec::new();
+
+        let known = TAGS.iter().map(|tag| *tag as u8);
+        let unknown = self.unknown.iter().map(|(code, _)| *code);
+        let custom = self.custom.iter().map(|(code, _)| *code);
+        let codes = self.order.iter().cloned().chain(known).chain(unknown).chain(custom);
+        for code in codes {
+            if is_yielded[code as usize] {
+                continue;
+            }
+            let value = match OptionTag::from(code) {
+                OptionTag::Unknown => match self.custom(code) {
+                    Some(value) => Some(OptionValue::Custom(value.box_clone())),
+                    None => self
+                        .unknown
+                        .iter()
+                        .find(|(c, _)| *c == code)
+                        .map(|(_, value)| OptionValue::Bytes(value.to_owned())),
+                },
+                tag => self.get(tag),
+            };
+            if let Some(value) = value {
+                is_yielded[code as usize] = true;
+                result.push((code, value));
+            }
+        }
+        result.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use v4::{constants::*, Message};
+
+    #[test]
+    fn options_are_accessed_by_tags_in_wire_order() {
+        let mut buffer = vec![0u8; OFFSET_OPTIONS];
+        buffer[OFFSET_MAGIC_COOKIE..OFFSET_OPTIONS].copy_from_slice(&[0x63, 0x82, 0x53, 0x63]);
+        buffer.extend_from_slice(&[12, 3, b'f', b'o', b'o', 224, 1, 7, 53, 1, 1, 1, 4]);
+        buffer.extend_from_slice(&[255, 255, 255, 0, 255]);
+
+        let mut options = Message::from_bytes(&buffer).unwrap().options;
+        let codes: Vec<u8> = options.iter().map(|(code, _)| code).collect();
+        assert_eq!(codes, vec![12, 224, 53, 1]);
+        assert_eq!(
+            options.get(OptionTag::DhcpMessageType),
+            Some(OptionValue::MessageType(MessageType::DhcpDiscover))
+        );
+
+        options
+            .set(OptionTag::Hostname, OptionValue::String("bar".to_owned()))
+            .unwrap();
+        options
+            .set(OptionTag::RapidCommit, OptionValue::Empty)
+            .unwrap();
+        assert!(options.set(OptionTag::Routers, OptionValue::U8(1)).is_err());
+        assert_eq!(
+            options.remove(OptionTag::SubnetMask),
+            Some(OptionValue::Ipv4(Ipv4Addr::new(255, 255, 255, 0)))
+        );
+        assert!(!options.contains(OptionTag::SubnetMask));
+
+        let values: Vec<(u8, OptionValue)> = options.iter().collect();
+        assert_eq!(
+            values,
+            vec![
+                (12, OptionValue::String("bar".to_owned())),
+                (224, OptionValue::Bytes(vec![7])),
+                (53, OptionValue::MessageType(MessageType::DhcpDiscover)),
+                (80, OptionValue::Empty),
+            ]
+        );
+    }
+}
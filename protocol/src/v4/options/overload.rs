@@ -3,7 +3,8 @@
 use std::fmt;
 
 /// DHCP option overload values.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Overload {
     Undefined = 0,
     File,
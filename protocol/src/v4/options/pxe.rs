@@ -0,0 +1,425 @@
+//! DHCP network boot options module.
+
+use std::{any::Any, fmt, io, mem, net::Ipv4Addr};
+
+use bytes::{Buf, BufMut};
+
+use super::{VendorInformation, VendorOptions};
+
+/// The discovery control sub-option code.
+const CODE_DISCOVERY_CONTROL: u8 = 6;
+/// The boot servers sub-option code.
+const CODE_BOOT_SERVERS: u8 = 8;
+/// The boot menu sub-option code.
+const CODE_BOOT_MENU: u8 = 9;
+/// The menu prompt sub-option code.
+const CODE_MENU_PROMPT: u8 = 10;
+/// The boot item sub-option code.
+const CODE_BOOT_ITEM: u8 = 71;
+
+/// The UNDI network interface type.
+const NETWORK_INTERFACE_UNDI: u8 = 1;
+/// The GUID machine identifier type.
+const MACHINE_IDENTIFIER_GUID: u8 = 0;
+
+/// The GUID length in bytes.
+const SIZE_GUID: usize = 16;
+
+/// The vendor class identifier prefix of PXE clients and servers.
+pub const PXE_CLASS_ID_PREFIX: &[u8] = b"PXEClient";
+
+/// Disables the broadcast discovery.
+pub const PXE_DISCOVERY_NO_BROADCAST: u8 = 0b0000_0001;
+/// Disables the multicast discovery.
+pub const PXE_DISCOVERY_NO_MULTICAST: u8 = 0b0000_0010;
+/// Only the servers from the boot servers sub-option may be used.
+pub const PXE_DISCOVERY_SERVER_LIST_ONLY: u8 = 0b0000_0100;
+/// The boot file must be downloaded without the menu and discovery.
+pub const PXE_DISCOVERY_DIRECT_DOWNLOAD: u8 = 0b0000_1000;
+
+/// DHCP client network interface identifier option.
+///
+/// [RFC 4578 §2.2](https://tools.ietf.org/html/rfc4578#section-2.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NetworkInterfaceIdentifier {
+    /// Only the Universal Network Device Interface (1) is defined.
+    pub interface_type: u8,
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl NetworkInterfaceIdentifier {
+    /// Creates a UNDI identifier of the version.
+    pub fn undi(major: u8, minor: u8) -> Self {
+        NetworkInterfaceIdentifier {
+            interface_type: NETWORK_INTERFACE_UNDI,
+            major,
+            minor,
+        }
+    }
+
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the length is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() != mem::size_of::<u8>() * 3 {
+            return Err(error("Network interface identifier length is invalid"));
+        }
+        Ok(NetworkInterfaceIdentifier {
+            interface_type: src[0],
+            major: src[1],
+            minor: src[2],
+        })
+    }
+
+    /// Encodes the option value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![self.interface_type, self.major, self.minor]
+    }
+}
+
+impl fmt::Display for NetworkInterfaceIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.interface_type {
+            NETWORK_INTERFACE_UNDI => write!(f, "UNDI")?,
+            interface_type => write!(f, "type {}", interface_type)?,
+        }
+        write!(f, " {}.{}", self.major, self.minor)
+    }
+}
+
+/// DHCP client machine identifier option.
+///
+/// [RFC 4578 §2.3](https://tools.ietf.org/html/rfc4578#section-2.3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MachineIdentifier {
+    /// Only the GUID (0) is defined.
+    pub identifier_type: u8,
+    pub identifier: Vec<u8>,
+}
+
+impl MachineIdentifier {
+    /// Creates a GUID identifier.
+    pub fn guid(guid: [u8; SIZE_GUID]) -> Self {
+        MachineIdentifier {
+            identifier_type: MACHINE_IDENTIFIER_GUID,
+            identifier: guid.to_vec(),
+        }
+    }
+
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is empty or the GUID length is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.is_empty()
+            || (src[0] == MACHINE_IDENTIFIER_GUID && src.len() != mem::size_of::<u8>() + SIZE_GUID)
+        {
+            return Err(error("Machine identifier length is invalid"));
+        }
+        Ok(MachineIdentifier {
+            identifier_type: src[0],
+            identifier: src[1..].to_vec(),
+        })
+    }
+
+    /// Encodes the option value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(mem::size_of::<u8>() + self.identifier.len());
+        dst.put_u8(self.identifier_type);
+        dst.put(&self.identifier);
+        dst
+    }
+}
+
+impl fmt::Display for MachineIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let id = &self.identifier;
+        if self.identifier_type != MACHINE_IDENTIFIER_GUID || id.len() != SIZE_GUID {
+            return write!(f, "type {} {:?}", self.identifier_type, id);
+        }
+        for (i, byte) in id.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// A boot server type with its addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PxeBootServer {
+    pub boot_server_type: u16,
+    pub addresses: Vec<Ipv4Addr>,
+}
+
+/// A boot menu item pointing to a boot server type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PxeBootMenuItem {
+    pub boot_server_type: u16,
+    pub description: String,
+}
+
+/// The boot menu prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PxeMenuPrompt {
+    /// Seconds to wait for a key press, 255 means infinity, 0 skips the menu.
+    pub timeout: u8,
+    pub prompt: String,
+}
+
+/// The boot item the client has chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PxeBootItem {
+    pub boot_server_type: u16,
+    pub layer: u16,
+}
+
+/// PXE vendor-specific information sub-options.
+///
+/// Decoded from the vendor-specific information option sent to or by `PXEClient` vendor classes.
+///
+/// [PXE 2.1 §2.4](http://www.pix.net/software/pxeboot/archive/pxespec.pdf)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PxeOptions {
+    /// The `PXE_DISCOVERY_*` flags.
+    pub discovery_control: Option<u8>,
+    pub boot_servers: Option<Vec<PxeBootServer>>,
+    pub boot_menu: Option<Vec<PxeBootMenuItem>>,
+    pub menu_prompt: Option<PxeMenuPrompt>,
+    pub boot_item: Option<PxeBootItem>,
+    /// Unsupported sub-options as raw `(code, value)` pairs in the order of appearance.
+    pub unknown: Vec<(u8, Vec<u8>)>,
+}
+
+impl PxeOptions {
+    /// Decodes the typed value from the sub-options.
+    ///
+    /// # Errors
+    /// `io::Error` if a sub-option is abrupted or has an invalid length.
+    pub fn from_options(options: &VendorOptions) -> io::Result<Self> {
+        let mut result = PxeOptions::default();
+        for (code, value) in options.sub_options.iter() {
+            let value = value.as_slice();
+            match *code {
+                CODE_DISCOVERY_CONTROL => {
+                    if value.len() != mem::size_of::<u8>() {
+                        return Err(error("Discovery control length is invalid"));
+                    }
+                    result.discovery_control = Some(value[0]);
+                }
+                CODE_BOOT_SERVERS => result.boot_servers = Some(Self::get_boot_servers(value)?),
+                CODE_BOOT_MENU => result.boot_menu = Some(Self::get_boot_menu(value)?),
+                CODE_MENU_PROMPT => {
+                    if value.is_empty() {
+                        return Err(error("Menu prompt is abrupted"));
+                    }
+                    result.menu_prompt = Some(PxeMenuPrompt {
+                        timeout: value[0],
+                        prompt: String::from_utf8_lossy(&value[1..]).to_string(),
+                    });
+                }
+                CODE_BOOT_ITEM => {
+                    if value.len() != mem::size_of::<u16>() * 2 {
+                        return Err(error("Boot item length is invalid"));
+                    }
+                    let mut cursor = io::Cursor::new(value);
+                    result.boot_item = Some(PxeBootItem {
+                        boot_server_type: cursor.get_u16_be(),
+                        layer: cursor.get_u16_be(),
+                    });
+                }
+                code => result.unknown.push((code, value.to_vec())),
+            }
+        }
+        Ok(result)
+    }
+
+    /// The `VendorDecoder` to register for `PXE_CLASS_ID_PREFIX`.
+    pub fn decode(options: &VendorOptions) -> io::Result<Box<dyn VendorInformation>> {
+        Ok(Box::new(Self::from_options(options)?))
+    }
+
+    fn get_boot_servers(value: &[u8]) -> io::Result<Vec<PxeBootServer>> {
+        let mut result = Vec::new();
+        let mut cursor = io::Cursor::new(value);
+        while cursor.remaining() > 0 {
+            if cursor.remaining() < mem::size_of::<u16>() + mem::size_of::<u8>() {
+                return Err(error("Boot server is abrupted"));
+            }
+            let boot_server_type = cursor.get_u16_be();
+            let count = cursor.get_u8() as usize;
+            if cursor.remaining() < count * mem::size_of::<u32>() {
+                return Err(error("Boot server is abrupted"));
+            }
+            let addresses = (0..count)
+                .map(|_| Ipv4Addr::from(cursor.get_u32_be()))
+                .collect();
+            result.push(PxeBootServer {
+                boot_server_type,
+                addresses,
+            });
+        }
+        Ok(result)
+    }
+
+    fn get_boot_menu(value: &[u8]) -> io::Result<Vec<PxeBootMenuItem>> {
+        let mut result = Vec::new();
+        let mut cursor = io::Cursor::new(value);
+        while cursor.remaining() > 0 {
+            if cursor.remaining() < mem::size_of::<u16>() + mem::size_of::<u8>() {
+                return Err(error("Boot menu item is abrupted"));
+            }
+            let boot_server_type = cursor.get_u16_be();
+            let len = cursor.get_u8() as usize;
+            if cursor.remaining() < len {
+                return Err(error("Boot menu item is abrupted"));
+            }
+            let description = String::from_utf8_lossy(&cursor.bytes()[..len]).to_string();
+            cursor.advance(len);
+            result.push(PxeBootMenuItem {
+                boot_server_type,
+                description,
+            });
+        }
+        Ok(result)
+    }
+}
+
+impl VendorInformation for PxeOptions {
+    fn to_options(&self) -> io::Result<VendorOptions> {
+        let mut options = VendorOptions::new();
+        if let Some(value) = self.discovery_control {
+            options.set(CODE_DISCOVERY_CONTROL, vec![value]);
+        }
+        if let Some(ref value) = self.boot_servers {
+            let mut bytes = Vec::new();
+            for server in value.iter() {
+                if server.addresses.len() > u8::MAX as usize {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Too many boot server addresses",
+                    ));
+                }
+                bytes.put_u16_be(server.boot_server_type);
+                bytes.put_u8(server.addresses.len() as u8);
+                for address in server.addresses.iter() {
+                    bytes.put_u32_be(u32::from(*address));
+                }
+            }
+            options.set(CODE_BOOT_SERVERS, bytes);
+        }
+        if let Some(ref value) = self.boot_menu {
+            let mut bytes = Vec::new();
+            for item in value.iter() {
+                if item.description.len() > u8::MAX as usize {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Boot menu item description is too long",
+                    ));
+                }
+                bytes.put_u16_be(item.boot_server_type);
+                bytes.put_u8(item.description.len() as u8);
+                bytes.put(item.description.as_bytes());
+            }
+            options.set(CODE_BOOT_MENU, bytes);
+        }
+        if let Some(ref value) = self.menu_prompt {
+            let mut bytes = vec![value.timeout];
+            bytes.put(value.prompt.as_bytes());
+            options.set(CODE_MENU_PROMPT, bytes);
+        }
+        if let Some(ref value) = self.boot_item {
+            let mut bytes = Vec::with_capacity(mem::size_of::<u16>() * 2);
+            bytes.put_u16_be(value.boot_server_type);
+            bytes.put_u16_be(value.layer);
+            options.set(CODE_BOOT_ITEM, bytes);
+        }
+        for (code, value) in self.unknown.iter() {
+            options.set(*code, value.to_owned());
+        }
+        Ok(options)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn error(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::VendorRegistry;
+    use super::*;
+
+    #[test]
+    fn pxe_options_round_trip() {
+        let pxe = PxeOptions {
+            discovery_control: Some(PXE_DISCOVERY_NO_MULTICAST | PXE_DISCOVERY_DIRECT_DOWNLOAD),
+            boot_servers: Some(vec![PxeBootServer {
+                boot_server_type: 0x8000,
+                addresses: vec![Ipv4Addr::new(192, 168, 0, 2), Ipv4Addr::new(192, 168, 0, 3)],
+            }]),
+            boot_menu: Some(vec![
+                PxeBootMenuItem {
+                    boot_server_type: 0,
+                    description: "Local boot".to_owned(),
+                },
+                PxeBootMenuItem {
+                    boot_server_type: 0x8000,
+                    description: "Install".to_owned(),
+                },
+            ]),
+            menu_prompt: Some(PxeMenuPrompt {
+                timeout: 10,
+                prompt: "Press F8".to_owned(),
+            }),
+            boot_item: Some(PxeBootItem {
+                boot_server_type: 0x8000,
+                layer: 0,
+            }),
+            unknown: vec![(1, vec![224, 0, 1, 2])],
+        };
+
+        let bytes = pxe.to_options().unwrap().to_bytes().unwrap();
+        let mut registry = VendorRegistry::new();
+        registry.register(PXE_CLASS_ID_PREFIX, PxeOptions::decode);
+        let decoded = registry
+            .decode(b"PXEClient:Arch:00007:UNDI:003016", &bytes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(decoded.as_any().downcast_ref::<PxeOptions>(), Some(&pxe));
+    }
+
+    #[test]
+    fn invalid_sub_options_are_rejected() {
+        let options = VendorOptions {
+            sub_options: vec![(CODE_BOOT_SERVERS, vec![0, 1, 2, 192, 168])],
+        };
+        assert!(PxeOptions::from_options(&options).is_err());
+        let options = VendorOptions {
+            sub_options: vec![(CODE_BOOT_ITEM, vec![0, 1, 2])],
+        };
+        assert!(PxeOptions::from_options(&options).is_err());
+    }
+
+    #[test]
+    fn machine_identifier() {
+        let guid = [
+            0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66,
+            0x77, 0x88,
+        ];
+        let id = MachineIdentifier::guid(guid);
+        assert_eq!(id.to_string(), "12345678-9abc-def0-1122-334455667788");
+        assert_eq!(MachineIdentifier::from_bytes(&id.to_bytes()).unwrap(), id);
+        assert!(MachineIdentifier::from_bytes(&[0, 1, 2]).is_err());
+    }
+}
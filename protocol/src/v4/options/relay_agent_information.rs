@@ -0,0 +1,240 @@
+//! DHCP relay agent information option module.
+
+use std::{io, mem, net::Ipv4Addr};
+
+use bytes::{Buf, BufMut};
+
+/// The circuit ID sub-option code (RFC 3046 §3.1).
+const CODE_CIRCUIT_ID: u8 = 1;
+/// The remote ID sub-option code (RFC 3046 §3.2).
+const CODE_REMOTE_ID: u8 = 2;
+/// The link selection sub-option code (RFC 3527).
+const CODE_LINK_SELECTION: u8 = 5;
+/// The subscriber ID sub-option code (RFC 3993).
+const CODE_SUBSCRIBER_ID: u8 = 6;
+/// The RADIUS attributes sub-option code (RFC 4014).
+const CODE_RADIUS_ATTRIBUTES: u8 = 7;
+/// The relay agent flags sub-option code (RFC 5010).
+const CODE_RELAY_AGENT_FLAGS: u8 = 10;
+/// The server identifier override sub-option code (RFC 5107).
+const CODE_SERVER_IDENTIFIER_OVERRIDE: u8 = 11;
+
+/// The sub-option and RADIUS attribute length limit.
+const SIZE_SUB_OPTION_MAX: usize = 255;
+/// The RADIUS attribute type and length octets.
+const SIZE_RADIUS_ATTRIBUTE_PREFIX: usize = 2;
+
+/// The `unicast` flag of the relay agent flags sub-option (RFC 5010).
+pub const RELAY_AGENT_FLAG_UNICAST: u8 = 0b1000_0000;
+
+/// DHCP relay agent information option.
+///
+/// Inserted by relay agents and echoed back by servers without modification.
+///
+/// [RFC 3046](https://tools.ietf.org/html/rfc3046)
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RelayAgentInformation {
+    pub circuit_id: Option<Vec<u8>>,
+    pub remote_id: Option<Vec<u8>>,
+    pub link_selection: Option<Ipv4Addr>,
+    pub subscriber_id: Option<Vec<u8>>,
+    /// Raw `(type, value)` RADIUS attributes.
+    pub radius_attributes: Option<Vec<(u8, Vec<u8>)>>,
+    pub relay_agent_flags: Option<u8>,
+    pub server_identifier_override: Option<Ipv4Addr>,
+    /// Unknown sub-options as raw `(code, value)` pairs in the order of appearance.
+    pub unknown: Vec<(u8, Vec<u8>)>,
+
+    /// The option value as it was received, which is not compared by `PartialEq`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) received: Option<Vec<u8>>,
+}
+
+impl PartialEq for RelayAgentInformation {
+    fn eq(&self, other: &Self) -> bool {
+        self.circuit_id == other.circuit_id
+            && self.remote_id == other.remote_id
+            && self.link_selection == other.link_selection
+            && self.subscriber_id == other.subscriber_id
+            && self.radius_attributes == other.radius_attributes
+            && self.relay_agent_flags == other.relay_agent_flags
+            && self.server_identifier_override == other.server_identifier_override
+            && self.unknown == other.unknown
+    }
+}
+
+impl Eq for RelayAgentInformation {}
+
+impl RelayAgentInformation {
+    /// Decodes the sub-options from the concatenated option value.
+    ///
+    /// The value is kept to be written back verbatim by `to_bytes`.
+    ///
+    /// # Errors
+    /// `io::Error` if a sub-option is abrupted or has an invalid length.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        let mut result = RelayAgentInformation {
+            received: Some(src.to_vec()),
+            ..Self::default()
+        };
+        let mut cursor = io::Cursor::new(src);
+        while cursor.remaining() > 0 {
+            if cursor.remaining() < mem::size_of::<u8>() * 2 {
+                return Err(Self::error("Sub-option is abrupted"));
+            }
+            let code = cursor.get_u8();
+            let len = cursor.get_u8() as usize;
+            if cursor.remaining() < len {
+                return Err(Self::error("Sub-option is abrupted"));
+            }
+            let value = &cursor.bytes()[..len];
+
+            match code {
+                CODE_CIRCUIT_ID => result.circuit_id = Some(value.to_vec()),
+                CODE_REMOTE_ID => result.remote_id = Some(value.to_vec()),
+                CODE_LINK_SELECTION => result.link_selection = Some(Self::get_ipv4(value)?),
+                CODE_SUBSCRIBER_ID => result.subscriber_id = Some(value.to_vec()),
+                CODE_RADIUS_ATTRIBUTES => {
+                    result.radius_attributes = Some(Self::get_radius_attributes(value)?)
+                }
+                CODE_RELAY_AGENT_FLAGS => {
+                    if len != mem::size_of::<u8>() {
+                        return Err(Self::error("Relay agent flags length is invalid"));
+                    }
+                    result.relay_agent_flags = Some(value[0]);
+                }
+                CODE_SERVER_IDENTIFIER_OVERRIDE => {
+                    result.server_identifier_override = Some(Self::get_ipv4(value)?)
+                }
+                _ => result.unknown.push((code, value.to_vec())),
+            }
+            cursor.advance(len);
+        }
+        Ok(result)
+    }
+
+    /// Encodes the sub-options in the order of their codes followed by the unknown ones.
+    ///
+    /// The received value is written verbatim if the sub-options have not been changed
+    /// after decoding, so the sub-option order, duplicates and contents are echoed intact.
+    ///
+    /// # Errors
+    /// `io::Error` if a sub-option or a RADIUS attribute is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        if let Some(ref received) = self.received {
+            if Self::from_bytes(received).ok().as_ref() == Some(self) {
+                return Ok(received.to_owned());
+            }
+        }
+
+        let mut dst = Vec::new();
+        if let Some(ref value) = self.circuit_id {
+            Self::put(&mut dst, CODE_CIRCUIT_ID, value)?;
+        }
+        if let Some(ref value) = self.remote_id {
+            Self::put(&mut dst, CODE_REMOTE_ID, value)?;
+        }
+        if let Some(ref value) = self.link_selection {
+            Self::put(&mut dst, CODE_LINK_SELECTION, &value.octets())?;
+        }
+        if let Some(ref value) = self.subscriber_id {
+            Self::put(&mut dst, CODE_SUBSCRIBER_ID, value)?;
+        }
+        if let Some(ref value) = self.radius_attributes {
+            let mut attributes = Vec::new();
+            for (kind, value) in value.iter() {
+                if SIZE_RADIUS_ATTRIBUTE_PREFIX + value.len() > SIZE_SUB_OPTION_MAX {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "RADIUS attribute is too long",
+                    ));
+                }
+                attributes.put_u8(*kind);
+                attributes.put_u8((SIZE_RADIUS_ATTRIBUTE_PREFIX + value.len()) as u8);
+                attributes.put(value);
+            }
+            Self::put(&mut dst, CODE_RADIUS_ATTRIBUTES, &attributes)?;
+        }
+        if let Some(value) = self.relay_agent_flags {
+            Self::put(&mut dst, CODE_RELAY_AGENT_FLAGS, &[value])?;
+        }
+        if let Some(ref value) = self.server_identifier_override {
+            Self::put(&mut dst, CODE_SERVER_IDENTIFIER_OVERRIDE, &value.octets())?;
+        }
+        for (code, value) in self.unknown.iter() {
+            Self::put(&mut dst, *code, value)?;
+        }
+        Ok(dst)
+    }
+
+    /// Checks if the relay agent has received the client message via unicast.
+    pub fn is_unicast(&self) -> bool {
+        self.relay_agent_flags
+            .is_some_and(|flags| flags & RELAY_AGENT_FLAG_UNICAST != 0)
+    }
+
+    fn put(dst: &mut Vec<u8>, code: u8, value: &[u8]) -> io::Result<()> {
+        if value.len() > SIZE_SUB_OPTION_MAX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Sub-option is too long",
+            ));
+        }
+        dst.put_u8(code);
+        dst.put_u8(value.len() as u8);
+        dst.put(value);
+        Ok(())
+    }
+
+    fn get_ipv4(value: &[u8]) -> io::Result<Ipv4Addr> {
+        if value.len() != mem::size_of::<u32>() {
+            return Err(Self::error("Address sub-option length is invalid"));
+        }
+        Ok(Ipv4Addr::new(value[0], value[1], value[2], value[3]))
+    }
+
+    /// The attribute length octet includes the type and length octets (RFC 2865 §5).
+    fn get_radius_attributes(value: &[u8]) -> io::Result<Vec<(u8, Vec<u8>)>> {
+        let mut result = Vec::new();
+        let mut cursor = io::Cursor::new(value);
+        while cursor.remaining() > 0 {
+            if cursor.remaining() < SIZE_RADIUS_ATTRIBUTE_PREFIX {
+                return Err(Self::error("RADIUS attribute is abrupted"));
+            }
+            let kind = cursor.get_u8();
+            let len = cursor.get_u8() as usize;
+            if len < SIZE_RADIUS_ATTRIBUTE_PREFIX
+                || cursor.remaining() < len - SIZE_RADIUS_ATTRIBUTE_PREFIX
+            {
+                return Err(Self::error("RADIUS attribute length is invalid"));
+            }
+            let len = len - SIZE_RADIUS_ATTRIBUTE_PREFIX;
+            result.push((kind, cursor.bytes()[..len].to_vec()));
+            cursor.advance(len);
+        }
+        Ok(result)
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn received_value_is_echoed_verbatim() {
+        // the remote ID precedes the duplicated circuit ID and the subscriber ID is not UTF-8
+        let value = [2, 1, 0xaa, 1, 1, 0x01, 1, 1, 0x02, 6, 2, 0xff, 0xfe];
+        let mut information = RelayAgentInformation::from_bytes(&value).unwrap();
+        assert_eq!(information.subscriber_id, Some(vec![0xff, 0xfe]));
+        assert_eq!(information.to_bytes().unwrap(), value.to_vec());
+
+        // the changed sub-options are encoded again
+        information.relay_agent_flags = Some(0);
+        assert_ne!(information.to_bytes().unwrap(), value.to_vec());
+    }
+}
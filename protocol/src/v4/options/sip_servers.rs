@@ -0,0 +1,139 @@
+//! DHCP SIP servers option module.
+
+use std::{fmt, io, net::Ipv4Addr};
+
+use super::DomainName;
+
+/// The encoding octet of the domain names list.
+const ENCODING_DOMAIN_NAMES: u8 = 0;
+/// The encoding octet of the IPv4 addresses list.
+const ENCODING_ADDRESSES: u8 = 1;
+
+const SIZE_ADDRESS: usize = 4;
+
+/// DHCP SIP servers option.
+///
+/// The encodings are mutually exclusive, so a server sends either of them.
+///
+/// [RFC 3361](https://tools.ietf.org/html/rfc3361)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SipServers {
+    /// `enc` 0, the servers in the order of preference.
+    DomainNames(Vec<DomainName>),
+    /// `enc` 1, the servers in the order of preference.
+    Addresses(Vec<Ipv4Addr>),
+}
+
+impl SipServers {
+    /// Decodes the option value.
+    ///
+    /// The domain names may be compressed (RFC 1035 §4.1.4) with the pointers
+    /// relative to the first octet following the `enc` octet.
+    ///
+    /// # Errors
+    /// `io::Error` if the encoding is unknown, the list is empty or malformed.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() < 2 {
+            return Err(Self::error("SIP servers list is empty"));
+        }
+        let list = &src[1..];
+        match src[0] {
+            ENCODING_DOMAIN_NAMES => Ok(SipServers::DomainNames(
+                DomainName::from_compressed_list(list)?,
+            )),
+            ENCODING_ADDRESSES => {
+                if !list.len().is_multiple_of(SIZE_ADDRESS) {
+                    return Err(Self::error("SIP servers addresses list is abrupted"));
+                }
+                Ok(SipServers::Addresses(
+                    list.chunks(SIZE_ADDRESS)
+                        .map(|chunk| Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3]))
+                        .collect(),
+                ))
+            }
+            _ => Err(Self::error("SIP servers encoding is unknown")),
+        }
+    }
+
+    /// Encodes the option value compressing the domain names.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            SipServers::DomainNames(names) => {
+                let mut dst = vec![ENCODING_DOMAIN_NAMES];
+                dst.extend_from_slice(&DomainName::to_compressed_list(names));
+                dst
+            }
+            SipServers::Addresses(addresses) => {
+                let mut dst = Vec::with_capacity(1 + addresses.len() * SIZE_ADDRESS);
+                dst.push(ENCODING_ADDRESSES);
+                for address in addresses.iter() {
+                    dst.extend_from_slice(&address.octets());
+                }
+                dst
+            }
+        }
+    }
+
+    /// Checks if there are no servers in the list.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            SipServers::DomainNames(names) => names.is_empty(),
+            SipServers::Addresses(addresses) => addresses.is_empty(),
+        }
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+impl fmt::Display for SipServers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let servers = match self {
+            SipServers::DomainNames(names) => names.iter().map(|name| name.to_string()).collect(),
+            SipServers::Addresses(addresses) => addresses
+                .iter()
+                .map(|address| address.to_string())
+                .collect::<Vec<String>>(),
+        };
+        write!(f, "{}", servers.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_names_are_compressed() {
+        let sip_servers = SipServers::DomainNames(vec![
+            "sip.example.com.".parse().unwrap(),
+            "sip2.example.com.".parse().unwrap(),
+        ]);
+        let mut expected = vec![0];
+        expected.extend_from_slice(b"\x03sip\x07example\x03com\x00\x04sip2\xc0\x04");
+        assert_eq!(sip_servers.to_bytes(), expected);
+        assert_eq!(SipServers::from_bytes(&expected).unwrap(), sip_servers);
+        assert_eq!(sip_servers.to_string(), "sip.example.com., sip2.example.com.");
+    }
+
+    #[test]
+    fn addresses() {
+        let src = [1, 192, 168, 0, 1, 10, 0, 0, 1];
+        let sip_servers = SipServers::from_bytes(&src).unwrap();
+        assert_eq!(
+            sip_servers,
+            SipServers::Addresses(vec![Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(10, 0, 0, 1)])
+        );
+        assert_eq!(sip_servers.to_bytes(), src.to_vec());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(SipServers::from_bytes(&[1]).is_err());
+        assert!(SipServers::from_bytes(&[1, 192, 168, 0]).is_err());
+        assert!(SipServers::from_bytes(&[2, 192, 168, 0, 1]).is_err());
+        assert!(SipServers::from_bytes(&[0, 3, b's', b'i', b'p']).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! DHCP user class option module.
+
+use std::{fmt, io};
+
+/// The instance length octet limit.
+const SIZE_INSTANCE_MAX: usize = 255;
+
+/// DHCP user class option.
+///
+/// [RFC 3004](https://tools.ietf.org/html/rfc3004)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UserClass {
+    /// The user class data instances in the order of appearance.
+    pub classes: Vec<Vec<u8>>,
+    /// The value is a single class without the length octet.
+    ///
+    /// Some clients like the Windows and iPXE ones send the option this way,
+    /// which violates RFC 3004. Such a value is written back the same way.
+    pub is_single_string: bool,
+}
+
+impl UserClass {
+    /// Creates an RFC 3004 compliant option value.
+    pub fn new(classes: Vec<Vec<u8>>) -> Self {
+        UserClass {
+            classes,
+            is_single_string: false,
+        }
+    }
+
+    /// Decodes the option value.
+    ///
+    /// If the value is not a valid list of length-prefixed instances,
+    /// it is treated as a single class without the length octet.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is empty.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "User class is empty",
+            ));
+        }
+        Ok(match Self::get_instances(src) {
+            Some(classes) => UserClass::new(classes),
+            None => UserClass {
+                classes: vec![src.to_vec()],
+                is_single_string: true,
+            },
+        })
+    }
+
+    /// Encodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if an instance is empty or too long or there are several single strings.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        if self.is_single_string {
+            return match self.classes.as_slice() {
+                [class] if !class.is_empty() => Ok(class.to_owned()),
+                _ => Err(Self::error("User class single string is invalid")),
+            };
+        }
+        let mut dst = Vec::new();
+        for class in self.classes.iter() {
+            if class.is_empty() || class.len() > SIZE_INSTANCE_MAX {
+                return Err(Self::error("User class instance length is invalid"));
+            }
+            dst.push(class.len() as u8);
+            dst.extend_from_slice(class);
+        }
+        Ok(dst)
+    }
+
+    /// Checks if the client belongs to the class.
+    pub fn contains(&self, class: &[u8]) -> bool {
+        self.classes.iter().any(|c| c.as_slice() == class)
+    }
+
+    /// RFC 3004 §2
+    /// Each instance of UC_Len_i MUST be a non-zero value.
+    fn get_instances(src: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let mut classes = Vec::new();
+        let mut i = 0;
+        while i < src.len() {
+            let len = src[i] as usize;
+            i += 1;
+            if len == 0 || i + len > src.len() {
+                return None;
+            }
+            classes.push(src[i..i + len].to_vec());
+            i += len;
+        }
+        Some(classes)
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, message)
+    }
+}
+
+impl fmt::Display for UserClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let classes: Vec<String> = self
+            .classes
+            .iter()
+            .map(|class| format!("{:?}", String::from_utf8_lossy(class)))
+            .collect();
+        write!(f, "[{}]", classes.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instances_are_length_prefixed() {
+        let src = b"\x04iPXE\x07LAB-PXE";
+        let user_class = UserClass::from_bytes(src).unwrap();
+        assert!(!user_class.is_single_string);
+        assert!(user_class.contains(b"LAB-PXE"));
+        assert_eq!(user_class.classes, vec![b"iPXE".to_vec(), b"LAB-PXE".to_vec()]);
+        assert_eq!(user_class.to_bytes().unwrap(), src.to_vec());
+    }
+
+    #[test]
+    fn single_string_is_kept() {
+        let user_class = UserClass::from_bytes(b"iPXE").unwrap();
+        assert!(user_class.is_single_string);
+        assert!(user_class.contains(b"iPXE"));
+        assert_eq!(user_class.to_bytes().unwrap(), b"iPXE".to_vec());
+
+        // a zero length octet is not allowed in the compliant form
+        let user_class = UserClass::from_bytes(b"\x00\x01a").unwrap();
+        assert!(user_class.is_single_string);
+
+        assert!(UserClass::from_bytes(&[]).is_err());
+        assert!(UserClass::new(vec![Vec::new()]).to_bytes().is_err());
+    }
+}
@@ -0,0 +1,244 @@
+//! DHCP vendor-identifying vendor options module.
+
+use std::{fmt, io, mem};
+
+use bytes::{Buf, BufMut};
+
+/// The enterprise number and the data length octet.
+const SIZE_BLOCK_PREFIX: usize = mem::size_of::<u32>() + mem::size_of::<u8>();
+/// The data length octet limit.
+const SIZE_DATA_MAX: usize = 255;
+
+/// A vendor class data block of the V-I Vendor Class option.
+///
+/// [RFC 3925 §3](https://tools.ietf.org/html/rfc3925#section-3)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VendorClassData {
+    /// The IANA enterprise number.
+    pub enterprise_number: u32,
+    /// Opaque vendor class data items.
+    pub data: Vec<Vec<u8>>,
+}
+
+impl VendorClassData {
+    /// Decodes the enterprise blocks from the concatenated option value.
+    ///
+    /// # Errors
+    /// `io::Error` if a block or a data item is abrupted.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Vec<Self>> {
+        let mut result = Vec::new();
+        let mut cursor = io::Cursor::new(src);
+        while cursor.remaining() > 0 {
+            let (enterprise_number, block) = get_block(&mut cursor)?;
+            let mut data = Vec::new();
+            let mut cursor = io::Cursor::new(block);
+            while cursor.remaining() > 0 {
+                let len = cursor.get_u8() as usize;
+                if cursor.remaining() < len {
+                    return Err(error("Vendor class data is abrupted"));
+                }
+                data.push(cursor.bytes()[..len].to_vec());
+                cursor.advance(len);
+            }
+            result.push(VendorClassData {
+                enterprise_number,
+                data,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Encodes the enterprise blocks into the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if a block is too long.
+    pub fn to_bytes(blocks: &[Self]) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::new();
+        for block in blocks.iter() {
+            let mut data = Vec::new();
+            for item in block.data.iter() {
+                if item.len() > SIZE_DATA_MAX {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Vendor class data is too long",
+                    ));
+                }
+                data.put_u8(item.len() as u8);
+                data.put(item);
+            }
+            put_block(&mut dst, block.enterprise_number, &data)?;
+        }
+        Ok(dst)
+    }
+}
+
+impl fmt::Display for VendorClassData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.enterprise_number)?;
+        let data: Vec<String> = self
+            .data
+            .iter()
+            .map(|item| format!("{:?}", String::from_utf8_lossy(item)))
+            .collect();
+        write!(f, "[{}]", data.join(" "))
+    }
+}
+
+/// An enterprise data block of the V-I Vendor-Specific Information option.
+///
+/// [RFC 3925 §4](https://tools.ietf.org/html/rfc3925#section-4)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VendorSpecificData {
+    /// The IANA enterprise number.
+    pub enterprise_number: u32,
+    /// Raw `(code, value)` sub-options in the order of appearance.
+    pub sub_options: Vec<(u8, Vec<u8>)>,
+}
+
+impl VendorSpecificData {
+    /// Decodes the enterprise blocks from the concatenated option value.
+    ///
+    /// # Errors
+    /// `io::Error` if a block or a sub-option is abrupted.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Vec<Self>> {
+        let mut result = Vec::new();
+        let mut cursor = io::Cursor::new(src);
+        while cursor.remaining() > 0 {
+            let (enterprise_number, block) = get_block(&mut cursor)?;
+            let mut sub_options = Vec::new();
+            let mut cursor = io::Cursor::new(block);
+            while cursor.remaining() > 0 {
+                if cursor.remaining() < mem::size_of::<u8>() * 2 {
+                    return Err(error("Sub-option is abrupted"));
+                }
+                let code = cursor.get_u8();
+                let len = cursor.get_u8() as usize;
+                if cursor.remaining() < len {
+                    return Err(error("Sub-option is abrupted"));
+                }
+                sub_options.push((code, cursor.bytes()[..len].to_vec()));
+                cursor.advance(len);
+            }
+            result.push(VendorSpecificData {
+                enterprise_number,
+                sub_options,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Encodes the enterprise blocks into the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if a block or a sub-option is too long.
+    pub fn to_bytes(blocks: &[Self]) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::new();
+        for block in blocks.iter() {
+            let mut data = Vec::new();
+            for (code, value) in block.sub_options.iter() {
+                if value.len() > SIZE_DATA_MAX {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "Sub-option is too long",
+                    ));
+                }
+                data.put_u8(*code);
+                data.put_u8(value.len() as u8);
+                data.put(value);
+            }
+            put_block(&mut dst, block.enterprise_number, &data)?;
+        }
+        Ok(dst)
+    }
+}
+
+impl fmt::Display for VendorSpecificData {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.enterprise_number)?;
+        let sub_options: Vec<String> = self
+            .sub_options
+            .iter()
+            .map(|(code, value)| format!("{}={:?}", code, value))
+            .collect();
+        write!(f, "[{}]", sub_options.join(" "))
+    }
+}
+
+/// Reads the enterprise number and the data the length octet refers to.
+fn get_block<'a>(cursor: &mut io::Cursor<&'a [u8]>) -> io::Result<(u32, &'a [u8])> {
+    if cursor.remaining() < SIZE_BLOCK_PREFIX {
+        return Err(error("Enterprise block is abrupted"));
+    }
+    let enterprise_number = cursor.get_u32_be();
+    let len = cursor.get_u8() as usize;
+    if cursor.remaining() < len {
+        return Err(error("Enterprise block is abrupted"));
+    }
+    let position = cursor.position() as usize;
+    let block = &cursor.get_ref()[position..position + len];
+    cursor.advance(len);
+    Ok((enterprise_number, block))
+}
+
+fn put_block(dst: &mut Vec<u8>, enterprise_number: u32, data: &[u8]) -> io::Result<()> {
+    if data.len() > SIZE_DATA_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Enterprise block is too long",
+        ));
+    }
+    dst.put_u32_be(enterprise_number);
+    dst.put_u8(data.len() as u8);
+    dst.put(data);
+    Ok(())
+}
+
+fn error(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vendor_class_round_trip() {
+        let blocks = vec![
+            VendorClassData {
+                enterprise_number: 4491,
+                data: vec![b"docsis3.0".to_vec(), Vec::new()],
+            },
+            VendorClassData {
+                enterprise_number: 9,
+                data: Vec::new(),
+            },
+        ];
+        let bytes = VendorClassData::to_bytes(&blocks).unwrap();
+        assert_eq!(&bytes[..5], &[0, 0, 0x11, 0x8b, 11]);
+        assert_eq!(VendorClassData::from_bytes(&bytes).unwrap(), blocks);
+    }
+
+    #[test]
+    fn vendor_specific_round_trip() {
+        let blocks = vec![VendorSpecificData {
+            enterprise_number: 3561,
+            sub_options: vec![
+                (1, b"ABCDEF".to_vec()),
+                (2, b"SN0001".to_vec()),
+                (6, Vec::new()),
+            ],
+        }];
+        let bytes = VendorSpecificData::to_bytes(&blocks).unwrap();
+        assert_eq!(&bytes[..5], &[0, 0, 0x0d, 0xe9, 18]);
+        assert_eq!(VendorSpecificData::from_bytes(&bytes).unwrap(), blocks);
+    }
+
+    #[test]
+    fn abrupted_block_is_rejected() {
+        assert!(VendorClassData::from_bytes(&[0, 0, 0, 9, 3, 5, b'a']).is_err());
+        assert!(VendorSpecificData::from_bytes(&[0, 0, 0, 9, 3, 1, 5, b'a']).is_err());
+        assert!(VendorSpecificData::from_bytes(&[0, 0, 0]).is_err());
+    }
+}
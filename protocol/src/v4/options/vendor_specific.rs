@@ -0,0 +1,245 @@
+//! DHCP vendor-specific information module.
+
+use std::{any::Any, fmt, io, mem};
+
+use bytes::{Buf, BufMut};
+
+/// The padding sub-option code.
+const CODE_PAD: u8 = 0;
+/// The code terminating the sub-options.
+const CODE_END: u8 = 255;
+
+/// The sub-option length limit.
+const SIZE_SUB_OPTION_MAX: usize = 255;
+
+/// Encapsulated vendor-specific sub-options of the vendor-specific information option.
+///
+/// [RFC 2132 §8.4](https://tools.ietf.org/html/rfc2132#section-8.4)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VendorOptions {
+    /// Raw `(code, value)` sub-options in the order of appearance.
+    pub sub_options: Vec<(u8, Vec<u8>)>,
+}
+
+impl VendorOptions {
+    /// Creates an empty sub-option list.
+    pub fn new() -> Self {
+        VendorOptions::default()
+    }
+
+    /// Decodes the sub-options from the option value.
+    ///
+    /// The padding is skipped and the end sub-option terminates the list.
+    ///
+    /// # Errors
+    /// `io::Error` if a sub-option is abrupted.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        let mut sub_options = Vec::new();
+        let mut cursor = io::Cursor::new(src);
+        while cursor.remaining() > 0 {
+            let code = cursor.get_u8();
+            match code {
+                CODE_PAD => continue,
+                CODE_END => break,
+                _ => {}
+            }
+            if cursor.remaining() < mem::size_of::<u8>() {
+                return Err(Self::error("Sub-option is abrupted"));
+            }
+            let len = cursor.get_u8() as usize;
+            if cursor.remaining() < len {
+                return Err(Self::error("Sub-option is abrupted"));
+            }
+            sub_options.push((code, cursor.bytes()[..len].to_vec()));
+            cursor.advance(len);
+        }
+        Ok(VendorOptions { sub_options })
+    }
+
+    /// Encodes the sub-options into the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if a sub-option is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::new();
+        for (code, value) in self.sub_options.iter() {
+            if value.len() > SIZE_SUB_OPTION_MAX {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Sub-option is too long",
+                ));
+            }
+            dst.put_u8(*code);
+            dst.put_u8(value.len() as u8);
+            dst.put(value);
+        }
+        Ok(dst)
+    }
+
+    /// Returns the value of the first sub-option with the code.
+    pub fn get(&self, code: u8) -> Option<&[u8]> {
+        self.sub_options
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Replaces the value of the sub-option with the code or appends a new one.
+    pub fn set(&mut self, code: u8, value: Vec<u8>) {
+        match self.sub_options.iter_mut().find(|(c, _)| *c == code) {
+            Some(sub_option) => sub_option.1 = value,
+            None => self.sub_options.push((code, value)),
+        }
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+/// A typed representation of some vendor's encapsulated sub-options.
+pub trait VendorInformation: fmt::Debug {
+    /// Encodes the value into the sub-options.
+    ///
+    /// # Errors
+    /// `io::Error` if the value cannot be represented with sub-options.
+    fn to_options(&self) -> io::Result<VendorOptions>;
+
+    /// Allows downcasting to the concrete vendor type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Decodes the sub-options into a typed value.
+pub type VendorDecoder = fn(&VendorOptions) -> io::Result<Box<dyn VendorInformation>>;
+
+/// Maps vendor class identifiers (option 60) to values, which are typed vendor-specific
+/// information decoders by default.
+///
+/// Vendor class identifiers often carry versions or hardware details after a common
+/// vendor prefix like `PXEClient:Arch:00007`, so values are registered for prefixes
+/// and the longest matching one is used.
+pub struct VendorRegistry<T = VendorDecoder> {
+    values: Vec<(Vec<u8>, T)>,
+}
+
+impl<T> Default for VendorRegistry<T> {
+    fn default() -> Self {
+        VendorRegistry { values: Vec::new() }
+    }
+}
+
+impl<T> VendorRegistry<T> {
+    /// Registers the value for the class identifier prefix replacing the previous one.
+    pub fn register(&mut self, class_id_prefix: &[u8], value: T) -> &mut Self {
+        self.values
+            .retain(|(prefix, _)| prefix.as_slice() != class_id_prefix);
+        self.values.push((class_id_prefix.to_vec(), value));
+        self
+    }
+
+    /// Finds the value registered for the longest prefix of the class identifier.
+    pub fn get(&self, class_id: &[u8]) -> Option<&T> {
+        self.values
+            .iter()
+            .filter(|(prefix, _)| class_id.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, value)| value)
+    }
+}
+
+impl VendorRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        VendorRegistry::default()
+    }
+
+    /// Finds the decoder registered for the longest prefix of the class identifier.
+    pub fn decoder(&self, class_id: &[u8]) -> Option<VendorDecoder> {
+        self.get(class_id).cloned()
+    }
+
+    /// Decodes the vendor-specific information option value sent to or by the vendor class.
+    ///
+    /// Returns `None` if there is no decoder for the vendor class.
+    ///
+    /// # Errors
+    /// `io::Error` if the sub-options or their typed representation are invalid.
+    pub fn decode(
+        &self,
+        class_id: &[u8],
+        vendor_specific: &[u8],
+    ) -> io::Result<Option<Box<dyn VendorInformation>>> {
+        match self.decoder(class_id) {
+            Some(decoder) => Ok(Some(decoder(&VendorOptions::from_bytes(vendor_specific)?)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Controller {
+        address: Vec<u8>,
+    }
+
+    impl VendorInformation for Controller {
+        fn to_options(&self) -> io::Result<VendorOptions> {
+            let mut options = VendorOptions::new();
+            options.set(1, self.address.to_owned());
+            Ok(options)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn decode_controller(options: &VendorOptions) -> io::Result<Box<dyn VendorInformation>> {
+        let address = options
+            .get(1)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No controller"))?;
+        Ok(Box::new(Controller {
+            address: address.to_vec(),
+        }))
+    }
+
+    fn decode_nothing(_: &VendorOptions) -> io::Result<Box<dyn VendorInformation>> {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected"))
+    }
+
+    #[test]
+    fn sub_options_round_trip() {
+        let src = [0, 1, 2, 0xaa, 0xbb, 0, 2, 0, 255, 7, 7];
+        let options = VendorOptions::from_bytes(&src).unwrap();
+        assert_eq!(
+            options.sub_options,
+            vec![(1, vec![0xaa, 0xbb]), (2, vec![])]
+        );
+        assert_eq!(options.to_bytes().unwrap(), vec![1, 2, 0xaa, 0xbb, 2, 0]);
+        assert!(VendorOptions::from_bytes(&[1, 3, 0]).is_err());
+    }
+
+    #[test]
+    fn registry_picks_longest_prefix() {
+        let mut registry = VendorRegistry::new();
+        registry
+            .register(b"Vendor", decode_nothing)
+            .register(b"Vendor:AP", decode_controller);
+
+        let controller = Controller {
+            address: vec![192, 168, 0, 1],
+        };
+        let bytes = controller.to_options().unwrap().to_bytes().unwrap();
+        let decoded = registry.decode(b"Vendor:AP:1.2", &bytes).unwrap().unwrap();
+        assert_eq!(
+            decoded.as_any().downcast_ref::<Controller>(),
+            Some(&controller)
+        );
+
+        assert!(registry.decode(b"Vendor:Switch", &bytes).is_err());
+        assert!(registry.decode(b"Other", &bytes).unwrap().is_none());
+    }
+}
@@ -1,12 +1,18 @@
 //! DHCP message serialization module.
 
-use std::{io, mem, net::Ipv4Addr};
+use std::{cmp, io, mem, net::Ipv4Addr};
 
 use bytes::{Buf, BufMut};
 
 use super::{
     constants::*,
-    options::{OptionTag, Overload as OverloadEnum},
+    options::{
+        Authentication as AuthenticationStruct, ClientArchitecture, ClientFqdn as ClientFqdnStruct,
+        CustomOption, DomainName, MachineIdentifier, NetworkInterfaceIdentifier, OptionTag,
+        Overload as OverloadEnum, RelayAgentInformation as RelayAgentInformationStruct,
+        SipServers as SipServersStruct, UserClass as UserClassStruct, VendorClassData,
+        VendorSpecificData,
+    },
     Message,
 };
 
@@ -23,33 +29,28 @@ macro_rules! check_remaining(
 const SIZE_OPTION_PREFIX: usize = 2;
 /// The end octet which may occur after any option.
 const SIZE_OPTION_SUFFIX: usize = 1;
-/// Both of the above.
-const SIZE_OPTION_AFFIXES: usize = SIZE_OPTION_PREFIX + SIZE_OPTION_SUFFIX;
 
 /// The overload option which is written last by the main cursor.
 const SIZE_OPTION_OVERLOAD: usize = mem::size_of::<u8>() * 3;
-/// The above and the required space for the `overload` option, which is written last.
-const SIZE_OPTION_MAIN_AFFIXES: usize = SIZE_OPTION_AFFIXES + SIZE_OPTION_OVERLOAD;
 
 /// The maximal option size.
 const SIZE_OPTION_MAX: usize = 255;
 
+/// The main cursor position in the cursors array.
+const CURSOR_INDEX_MAIN: usize = 0;
 /// The boot filename cursor position in the cursors array.
-const CURSOR_INDEX_FILE: usize = 0;
+const CURSOR_INDEX_FILE: usize = 1;
 /// The server name cursor position in the cursors array.
-const CURSOR_INDEX_SNAME: usize = 1;
-/// The main cursor position in the cursors array.
-const CURSOR_INDEX_MAIN: usize = 2;
+const CURSOR_INDEX_SNAME: usize = 2;
 /// The cursors array size.
 const CURSOR_INDEX_TOTAL: usize = 3;
 
 impl Message {
     /// DHCP message serialization.
     ///
-    /// Options encoded with `put_opt_*` methods called with the `?`
-    /// operator are mandatory and throw an error on unsuccessful write.
-    /// Options encoded with `put_opt_*` methods called without the `?` operator are optional
-    /// and are written to the packet only if there is enough space left.
+    /// Options are encoded with `put_opt_*` methods in the order of importance and then
+    /// placed to the packet by `put_options`, which overloads the `file` and `sname` fields
+    /// if they are empty and splits the values longer than 255 bytes as per RFC 3396.
     /// The order of options and behavior of the encoder may be changed in the future.
     ///
     /// If `max_size` is specified, `dst` is truncated to it.
@@ -66,648 +67,722 @@ impl Message {
             dst
         };
 
-        // cursors are initialized in the way they must be filled
-        let mut cursors: [io::Cursor<&mut [u8]>; CURSOR_INDEX_TOTAL] = [
-            io::Cursor::new(unsafe {
-                &mut *(&mut dst[OFFSET_BOOT_FILENAME..OFFSET_MAGIC_COOKIE] as *mut [u8])
-            }),
-            io::Cursor::new(unsafe {
-                &mut *(&mut dst[OFFSET_SERVER_NAME..OFFSET_BOOT_FILENAME] as *mut [u8])
-            }),
-            io::Cursor::new(unsafe { &mut *(dst as *mut [u8]) }),
-        ];
-
-        check_remaining!(cursors[CURSOR_INDEX_MAIN], OFFSET_OPTIONS);
-        cursors[CURSOR_INDEX_MAIN].put_u8(self.operation_code as u8);
-        cursors[CURSOR_INDEX_MAIN].put_u8(self.hardware_type as u8);
-        cursors[CURSOR_INDEX_MAIN].put_u8(self.hardware_address_length);
-        cursors[CURSOR_INDEX_MAIN].put_u8(self.hardware_options);
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(self.transaction_id);
-        cursors[CURSOR_INDEX_MAIN].put_u16_be(self.seconds);
+        let mut cursor = io::Cursor::new(dst);
+        check_remaining!(cursor, OFFSET_OPTIONS);
+        cursor.put_u8(self.operation_code as u8);
+        cursor.put_u8(u8::from(self.hardware_type));
+        cursor.put_u8(self.client_hardware_address.len() as u8);
+        cursor.put_u8(self.hardware_options);
+        cursor.put_u32_be(self.transaction_id);
+        cursor.put_u16_be(self.seconds);
         // https://tools.ietf.org/html/rfc2131#section-2
         // https://tools.ietf.org/html/rfc1700#page-3
         // Leftmost bit (0 bit) is most significant
-        cursors[CURSOR_INDEX_MAIN].put_u16_be(if self.is_broadcast { 0x8000 } else { 0x0000 });
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(u32::from(self.client_ip_address));
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(u32::from(self.your_ip_address));
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(u32::from(self.server_ip_address));
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(u32::from(self.gateway_ip_address));
-        cursors[CURSOR_INDEX_MAIN].put(self.client_hardware_address.as_bytes()); // 6 byte MAC-48
-        cursors[CURSOR_INDEX_MAIN].put(vec![
+        cursor.put_u16_be(if self.is_broadcast { 0x8000 } else { 0x0000 });
+        cursor.put_u32_be(u32::from(self.client_ip_address));
+        cursor.put_u32_be(u32::from(self.your_ip_address));
+        cursor.put_u32_be(u32::from(self.server_ip_address));
+        cursor.put_u32_be(u32::from(self.gateway_ip_address));
+        cursor.put(self.client_hardware_address.as_bytes());
+        cursor.put(vec![
             0u8;
             SIZE_HARDWARE_ADDRESS
-                - self.client_hardware_address.as_bytes().len()
-        ]); // 10 byte padding
-        cursors[CURSOR_INDEX_MAIN].put(&self.server_name);
-        cursors[CURSOR_INDEX_MAIN].put(vec![0u8; SIZE_SERVER_NAME - self.server_name.len()]); // (64 - length) byte padding
-        cursors[CURSOR_INDEX_MAIN].put(&self.boot_filename);
-        cursors[CURSOR_INDEX_MAIN].put(vec![0u8; SIZE_BOOT_FILENAME - self.boot_filename.len()]); // (128 - length) byte padding
-        cursors[CURSOR_INDEX_MAIN].put_u32_be(MAGIC_COOKIE);
+                - self.client_hardware_address.len()
+        ]); // (16 - length) byte padding
+        if self.server_name.len() > SIZE_SERVER_NAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The server name does not fit the sname field",
+            ));
+        }
+        if self.boot_filename.len() > SIZE_BOOT_FILENAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The boot file name does not fit the file field",
+            ));
+        }
+        cursor.put(&self.server_name);
+        cursor.put(vec![0u8; SIZE_SERVER_NAME - self.server_name.len()]); // (64 - length) byte padding
+        cursor.put(&self.boot_filename);
+        cursor.put(vec![0u8; SIZE_BOOT_FILENAME - self.boot_filename.len()]); // (128 - length) byte padding
+        cursor.put_u32_be(MAGIC_COOKIE);
 
+        // the options are encoded in the order of importance and placed to the areas afterwards
+        let mut options = Vec::new();
         // the most important and required options are encoded first
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             DhcpMessageType,
             &self.options.dhcp_message_type.to_owned().map(|v| v as u8),
-        )?;
+        );
         Self::put_opt_u16(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             DhcpMaxMessageSize,
             &self.options.dhcp_max_message_size,
-        )?;
-        Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            DhcpServerId,
-            &self.options.dhcp_server_id,
-        )?;
-        Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            AddressRequest,
-            &self.options.address_request,
-        )?;
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            AddressTime,
-            &self.options.address_time,
-        )?;
-        Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ParameterList,
-            &self.options.parameter_list,
-        )?;
-        Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ClientId,
-            &self.options.client_id,
-        )?;
+        );
+        Self::put_opt_ipv4(&mut options, DhcpServerId, &self.options.dhcp_server_id);
+        Self::put_opt_ipv4(&mut options, AddressRequest, &self.options.address_request);
+        Self::put_opt_u32(&mut options, AddressTime, &self.options.address_time);
+        Self::put_opt_vec(&mut options, ParameterList, &self.options.parameter_list);
+        Self::put_opt_vec(&mut options, ClientId, &self.options.client_id);
 
         // the mandatory implemented network configuration options are encoded next
-        Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            SubnetMask,
-            &self.options.subnet_mask,
-        )?;
+        Self::put_opt_ipv4(&mut options, SubnetMask, &self.options.subnet_mask);
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             DomainNameServers,
             &self.options.domain_name_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            Routers,
-            &self.options.routers,
-        )?;
-        Self::put_opt_vec_ipv4_pairs(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            StaticRoutes,
-            &self.options.static_routes,
-        )?;
+        );
+        Self::put_opt_vec_ipv4(&mut options, Routers, &self.options.routers);
+        Self::put_opt_vec_ipv4_pairs(&mut options, StaticRoutes, &self.options.static_routes);
 
-        // the splittable options are encoded after, leaving space for the 'overload' option
+        // the long options are encoded after and are likely to be splitted
         Self::put_opt_classless_static_routes(
-            &mut cursors,
+            &mut options,
             ClasslessStaticRoutes,
             &self.options.classless_static_routes,
-        )?;
-
-        // the overload options is written last by the main cursor
-        let overload = if cursors[CURSOR_INDEX_FILE].position() > 0
-            && cursors[CURSOR_INDEX_SNAME].position() > 0
-        {
-            Some(OverloadEnum::Both)
-        } else if cursors[CURSOR_INDEX_FILE].position() > 0 {
-            Some(OverloadEnum::File)
-        } else if cursors[CURSOR_INDEX_SNAME].position() > 0 {
-            Some(OverloadEnum::Sname)
-        } else {
-            None
-        };
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            Overload,
-            &overload.map(|v| v as u8),
-        )?;
+        );
 
         // some helpful and optional options are encoded next
+        Self::put_opt_u32(&mut options, RenewalTime, &self.options.renewal_time);
+        Self::put_opt_u32(&mut options, RebindingTime, &self.options.rebinding_time);
+        Self::put_opt_string(&mut options, Hostname, &self.options.hostname);
+        Self::put_opt_empty(&mut options, RapidCommit, &self.options.rapid_commit);
+        Self::put_opt_authentication(&mut options, Authentication, &self.options.authentication);
+        Self::put_opt_vec(
+            &mut options,
+            ForcerenewNonceCapable,
+            &self.options.forcerenew_nonce_capable,
+        );
+        Self::put_opt_client_fqdn(&mut options, ClientFqdn, &self.options.client_fqdn);
+        Self::put_opt_domain_search(&mut options, DomainSearch, &self.options.domain_search);
+        Self::put_opt_sip_servers(&mut options, SipServers, &self.options.sip_servers);
         Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            RenewalTime,
-            &self.options.renewal_time,
-        )?;
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            RebindingTime,
-            &self.options.rebinding_time,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            Hostname,
-            &self.options.hostname,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            DhcpMessage,
-            &self.options.dhcp_message,
-        )?;
+            &mut options,
+            ClientLastTransactionTime,
+            &self.options.client_last_transaction_time,
+        );
+        Self::put_opt_vec_ipv4(&mut options, AssociatedIp, &self.options.associated_ip);
+        Self::put_opt_string(&mut options, DhcpMessage, &self.options.dhcp_message);
 
         // unimplemented options are encoded next
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            TimeOffset,
-            &self.options.time_offset,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            TimeServers,
-            &self.options.time_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NameServers,
-            &self.options.name_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            LogServers,
-            &self.options.log_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            QuotesServers,
-            &self.options.quotes_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            LprServers,
-            &self.options.lpr_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ImpressServers,
-            &self.options.impress_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            RlpServers,
-            &self.options.rlp_servers,
-        )?;
-        Self::put_opt_u16(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            BootFileSize,
-            &self.options.boot_file_size,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MeritDumpFile,
-            &self.options.merit_dump_file,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            DomainName,
-            &self.options.domain_name,
-        )?;
-        Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            SwapServer,
-            &self.options.swap_server,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            RootPath,
-            &self.options.root_path,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ExtensionsPath,
-            &self.options.extensions_path,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ForwardOnOff,
-            &self.options.forward_on_off,
-        )?;
+        Self::put_opt_u32(&mut options, TimeOffset, &self.options.time_offset);
+        Self::put_opt_vec_ipv4(&mut options, TimeServers, &self.options.time_servers);
+        Self::put_opt_vec_ipv4(&mut options, NameServers, &self.options.name_servers);
+        Self::put_opt_vec_ipv4(&mut options, LogServers, &self.options.log_servers);
+        Self::put_opt_vec_ipv4(&mut options, QuotesServers, &self.options.quotes_servers);
+        Self::put_opt_vec_ipv4(&mut options, LprServers, &self.options.lpr_servers);
+        Self::put_opt_vec_ipv4(&mut options, ImpressServers, &self.options.impress_servers);
+        Self::put_opt_vec_ipv4(&mut options, RlpServers, &self.options.rlp_servers);
+        Self::put_opt_u16(&mut options, BootFileSize, &self.options.boot_file_size);
+        Self::put_opt_string(&mut options, MeritDumpFile, &self.options.merit_dump_file);
+        Self::put_opt_string(&mut options, DomainName, &self.options.domain_name);
+        Self::put_opt_ipv4(&mut options, SwapServer, &self.options.swap_server);
+        Self::put_opt_string(&mut options, RootPath, &self.options.root_path);
+        Self::put_opt_string(&mut options, ExtensionsPath, &self.options.extensions_path);
+        Self::put_opt_u8(&mut options, ForwardOnOff, &self.options.forward_on_off);
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NonLocalSourceRouteOnOff,
             &self.options.non_local_source_route_on_off,
-        )?;
-        Self::put_opt_vec_ipv4_pairs(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            PolicyFilters,
-            &self.options.policy_filters,
-        )?;
+        );
+        Self::put_opt_vec_ipv4_pairs(&mut options, PolicyFilters, &self.options.policy_filters);
         Self::put_opt_u16(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             MaxDatagramReassemblySize,
             &self.options.max_datagram_reassembly_size,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            DefaultIpTtl,
-            &self.options.default_ip_ttl,
-        )?;
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MtuTimeout,
-            &self.options.mtu_timeout,
-        )?;
-        Self::put_opt_vec_u16(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MtuPlateau,
-            &self.options.mtu_plateau,
-        )?;
-        Self::put_opt_u16(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MtuInterface,
-            &self.options.mtu_interface,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MtuSubnet,
-            &self.options.mtu_subnet,
-        )?;
+        );
+        Self::put_opt_u8(&mut options, DefaultIpTtl, &self.options.default_ip_ttl);
+        Self::put_opt_u32(&mut options, MtuTimeout, &self.options.mtu_timeout);
+        Self::put_opt_vec_u16(&mut options, MtuPlateau, &self.options.mtu_plateau);
+        Self::put_opt_u16(&mut options, MtuInterface, &self.options.mtu_interface);
+        Self::put_opt_u8(&mut options, MtuSubnet, &self.options.mtu_subnet);
         Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             BroadcastAddress,
             &self.options.broadcast_address,
-        )?;
+        );
+        Self::put_opt_u8(&mut options, MaskRecovery, &self.options.mask_recovery);
+        Self::put_opt_u8(&mut options, MaskSupplier, &self.options.mask_supplier);
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MaskRecovery,
-            &self.options.mask_recovery,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            MaskSupplier,
-            &self.options.mask_supplier,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             PerformRouterDiscovery,
             &self.options.perform_router_discovery,
-        )?;
+        );
         Self::put_opt_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             RouterSolicitationAddress,
             &self.options.router_solicitation_address,
-        )?;
+        );
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             TrailerEncapsulation,
             &self.options.trailer_encapsulation,
-        )?;
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ArpTimeout,
-            &self.options.arp_timeout,
-        )?;
+        );
+        Self::put_opt_u32(&mut options, ArpTimeout, &self.options.arp_timeout);
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             EthernetEncapsulation,
             &self.options.ethernet_encapsulation,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            DefaultTcpTtl,
-            &self.options.default_tcp_ttl,
-        )?;
-        Self::put_opt_u32(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            KeepaliveTime,
-            &self.options.keepalive_time,
-        )?;
-        Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            KeepaliveData,
-            &self.options.keepalive_data,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NisDomain,
-            &self.options.nis_domain,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NisServers,
-            &self.options.nis_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NtpServers,
-            &self.options.ntp_servers,
-        )?;
-        Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            VendorSpecific,
-            &self.options.vendor_specific,
-        )?;
+        );
+        Self::put_opt_u8(&mut options, DefaultTcpTtl, &self.options.default_tcp_ttl);
+        Self::put_opt_u32(&mut options, KeepaliveTime, &self.options.keepalive_time);
+        Self::put_opt_u8(&mut options, KeepaliveData, &self.options.keepalive_data);
+        Self::put_opt_string(&mut options, NisDomain, &self.options.nis_domain);
+        Self::put_opt_vec_ipv4(&mut options, NisServers, &self.options.nis_servers);
+        Self::put_opt_vec_ipv4(&mut options, NtpServers, &self.options.ntp_servers);
+        Self::put_opt_vec(&mut options, VendorSpecific, &self.options.vendor_specific);
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NetbiosNameServers,
             &self.options.netbios_name_servers,
-        )?;
+        );
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NetbiosDistributionServers,
             &self.options.netbios_distribution_servers,
-        )?;
+        );
         Self::put_opt_u8(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NetbiosNodeType,
             &self.options.netbios_node_type,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NetbiosScope,
-            &self.options.netbios_scope,
-        )?;
+        );
+        Self::put_opt_string(&mut options, NetbiosScope, &self.options.netbios_scope);
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             XWindowFontServers,
             &self.options.x_window_font_servers,
-        )?;
+        );
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             XWindowManagerServers,
             &self.options.x_window_manager_servers,
+        );
+        Self::put_opt_vec(&mut options, ClassId, &self.options.class_id);
+        Self::put_opt_client_system_architecture(
+            &mut options,
+            ClientSystemArchitecture,
+            &self.options.client_system_architecture,
+        );
+        Self::put_opt_network_interface_identifier(
+            &mut options,
+            ClientNetworkInterfaceIdentifier,
+            &self.options.client_network_interface_identifier,
+        );
+        Self::put_opt_machine_identifier(
+            &mut options,
+            ClientMachineIdentifier,
+            &self.options.client_machine_identifier,
+        );
+        Self::put_opt_u32(
+            &mut options,
+            Ipv6OnlyPreferred,
+            &self.options.ipv6_only_preferred,
+        );
+        Self::put_opt_string(&mut options, CaptivePortal, &self.options.captive_portal);
+        Self::put_opt_ipv4(
+            &mut options,
+            SubnetSelection,
+            &self.options.subnet_selection,
+        );
+        Self::put_opt_vi_vendor_class(&mut options, ViVendorClass, &self.options.vi_vendor_class)?;
+        Self::put_opt_vi_vendor_specific(
+            &mut options,
+            ViVendorSpecific,
+            &self.options.vi_vendor_specific,
         )?;
         Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ClassId,
-            &self.options.class_id,
-        )?;
-        Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NetwareIpDomain,
             &self.options.netware_ip_domain,
-        )?;
+        );
         Self::put_opt_vec(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NetwareIpOption,
             &self.options.netware_ip_option,
-        )?;
+        );
         Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             NisDomainName,
             &self.options.nis_v3_domain_name,
-        )?;
+        );
+        Self::put_opt_vec_ipv4(&mut options, NisServerAddress, &self.options.nis_v3_servers);
+        Self::put_opt_string(&mut options, ServerName, &self.options.server_name);
+        Self::put_opt_string(&mut options, BootfileName, &self.options.bootfile_name);
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NisServerAddress,
-            &self.options.nis_v3_servers,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            ServerName,
-            &self.options.server_name,
-        )?;
-        Self::put_opt_string(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            BootfileName,
-            &self.options.bootfile_name,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             HomeAgentAddresses,
             &self.options.home_agent_addresses,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            SmtpServers,
-            &self.options.smtp_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            Pop3Servers,
-            &self.options.pop3_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            NntpServers,
-            &self.options.nntp_servers,
-        )?;
+        );
+        Self::put_opt_vec_ipv4(&mut options, SmtpServers, &self.options.smtp_servers);
+        Self::put_opt_vec_ipv4(&mut options, Pop3Servers, &self.options.pop3_servers);
+        Self::put_opt_vec_ipv4(&mut options, NntpServers, &self.options.nntp_servers);
+        Self::put_opt_vec_ipv4(&mut options, WwwServers, &self.options.www_servers);
+        Self::put_opt_vec_ipv4(&mut options, FingerServers, &self.options.finger_servers);
+        Self::put_opt_vec_ipv4(&mut options, IrcServers, &self.options.irc_servers);
         Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            WwwServers,
-            &self.options.www_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            FingerServers,
-            &self.options.finger_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            IrcServers,
-            &self.options.irc_servers,
-        )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
+            &mut options,
             StreetTalkServers,
             &self.options.street_talk_servers,
+        );
+        Self::put_opt_vec_ipv4(&mut options, StdaServers, &self.options.stda_servers);
+        Self::put_opt_user_class(&mut options, UserClass, &self.options.user_class)?;
+
+        // unknown options are written back in the order they were received
+        Self::put_opt_unknown(&mut options, &self.options.unknown);
+        Self::put_opt_custom(&mut options, &self.options.custom)?;
+
+        // the relay agent information must be the last option (RFC 3046 §2.1)
+        let mut last = Vec::new();
+        Self::put_opt_relay_agent_information(
+            &mut last,
+            RelayAgentInformation,
+            &self.options.relay_agent_information,
         )?;
-        Self::put_opt_vec_ipv4(
-            &mut cursors[CURSOR_INDEX_MAIN],
-            StdaServers,
-            &self.options.stda_servers,
-        )?;
 
-        check_remaining!(cursors[CURSOR_INDEX_MAIN], mem::size_of::<u8>());
+        // `file` and `sname` are used for options only if they are not occupied by their values
+        let use_file = self.boot_filename.iter().all(|byte| *byte == 0)
+            || matches!(
+                self.options.overload,
+                Some(OverloadEnum::File) | Some(OverloadEnum::Both)
+            );
+        let use_sname = self.server_name.iter().all(|byte| *byte == 0)
+            || matches!(
+                self.options.overload,
+                Some(OverloadEnum::Sname) | Some(OverloadEnum::Both)
+            );
+
+        Self::put_options(cursor.into_inner(), &options, &last, use_file, use_sname)
+    }
+
+    /// Places the encoded options to the areas as per [RFC 3396](https://tools.ietf.org/html/rfc3396).
+    ///
+    /// The main area is filled first, then `file` and `sname` if they are allowed to be used.
+    /// An option is put as a whole to the first area it fits in, otherwise it is splitted
+    /// into several instances which are placed in the order the receiver concatenates them.
+    ///
+    /// The `last` options are written to the end of the main area just before the `end` option.
+    fn put_options(
+        dst: &mut [u8],
+        options: &[(u8, Vec<u8>)],
+        last: &[(u8, Vec<u8>)],
+        use_file: bool,
+        use_sname: bool,
+    ) -> io::Result<usize> {
+        use OptionTag::*;
+
+        let (header, main) = dst.split_at_mut(OFFSET_OPTIONS);
+        let (header, file) = header[..OFFSET_MAGIC_COOKIE].split_at_mut(OFFSET_BOOT_FILENAME);
+        let sname = &mut header[OFFSET_SERVER_NAME..];
+        let mut cursors: [io::Cursor<&mut [u8]>; CURSOR_INDEX_TOTAL] = [
+            io::Cursor::new(main),
+            io::Cursor::new(file),
+            io::Cursor::new(sname),
+        ];
+
+        let mut order = vec![CURSOR_INDEX_MAIN];
+        if use_file {
+            order.push(CURSOR_INDEX_FILE);
+        }
+        if use_sname {
+            order.push(CURSOR_INDEX_SNAME);
+        }
+        // the space for the `end` option and the `overload` one which is written last
+        let mut reserved = [SIZE_OPTION_SUFFIX; CURSOR_INDEX_TOTAL];
+        if order.len() > 1 {
+            reserved[CURSOR_INDEX_MAIN] += SIZE_OPTION_OVERLOAD;
+        }
+        for (_, value) in last.iter() {
+            let instances = cmp::max(value.len().div_ceil(SIZE_OPTION_MAX), 1);
+            reserved[CURSOR_INDEX_MAIN] += instances * SIZE_OPTION_PREFIX + value.len();
+        }
+
+        for (tag, value) in options.iter() {
+            if value.len() <= SIZE_OPTION_MAX {
+                let size = SIZE_OPTION_PREFIX + value.len();
+                if let Some(&c) = order
+                    .iter()
+                    .find(|&&c| cursors[c].remaining() >= reserved[c] + size)
+                {
+                    cursors[c].put_u8(*tag);
+                    cursors[c].put_u8(value.len() as u8);
+                    cursors[c].put(value.as_slice());
+                    continue;
+                }
+            }
+
+            let mut offset = 0;
+            let mut areas = order.iter();
+            let mut c = areas.next().cloned();
+            while offset < value.len() {
+                let index = match c {
+                    Some(index) => index,
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "No more space left",
+                        ))
+                    }
+                };
+                let space = cursors[index]
+                    .remaining()
+                    .saturating_sub(reserved[index] + SIZE_OPTION_PREFIX);
+                if space == 0 {
+                    c = areas.next().cloned();
+                    continue;
+                }
+
+                let len = cmp::min(cmp::min(space, SIZE_OPTION_MAX), value.len() - offset);
+                cursors[index].put_u8(*tag);
+                cursors[index].put_u8(len as u8);
+                cursors[index].put(&value[offset..offset + len]);
+                offset += len;
+            }
+            if value.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "No more space left",
+                ));
+            }
+        }
+
+        // the overload option is written last by the main cursor
+        let overload = if cursors[CURSOR_INDEX_FILE].position() > 0
+            && cursors[CURSOR_INDEX_SNAME].position() > 0
+        {
+            Some(OverloadEnum::Both)
+        } else if cursors[CURSOR_INDEX_FILE].position() > 0 {
+            Some(OverloadEnum::File)
+        } else if cursors[CURSOR_INDEX_SNAME].position() > 0 {
+            Some(OverloadEnum::Sname)
+        } else {
+            None
+        };
+        if let Some(overload) = overload {
+            check_remaining!(cursors[CURSOR_INDEX_MAIN], SIZE_OPTION_OVERLOAD);
+            cursors[CURSOR_INDEX_MAIN].put_u8(Overload as u8);
+            cursors[CURSOR_INDEX_MAIN].put_u8(mem::size_of::<u8>() as u8);
+            cursors[CURSOR_INDEX_MAIN].put_u8(overload as u8);
+        }
+
+        for (tag, value) in last.iter() {
+            let mut chunks = value.chunks(SIZE_OPTION_MAX).peekable();
+            if chunks.peek().is_none() {
+                check_remaining!(cursors[CURSOR_INDEX_MAIN], SIZE_OPTION_PREFIX);
+                cursors[CURSOR_INDEX_MAIN].put_u8(*tag);
+                cursors[CURSOR_INDEX_MAIN].put_u8(0);
+            }
+            for chunk in chunks {
+                check_remaining!(cursors[CURSOR_INDEX_MAIN], SIZE_OPTION_PREFIX + chunk.len());
+                cursors[CURSOR_INDEX_MAIN].put_u8(*tag);
+                cursors[CURSOR_INDEX_MAIN].put_u8(chunk.len() as u8);
+                cursors[CURSOR_INDEX_MAIN].put(chunk);
+            }
+        }
+
+        check_remaining!(cursors[CURSOR_INDEX_MAIN], SIZE_OPTION_SUFFIX);
         cursors[CURSOR_INDEX_MAIN].put_u8(End as u8);
-        if cursors[CURSOR_INDEX_FILE].position() > 0 {
-            cursors[CURSOR_INDEX_FILE].put_u8(End as u8);
+        for c in [CURSOR_INDEX_FILE, CURSOR_INDEX_SNAME].iter() {
+            let cursor = &mut cursors[*c];
+            if cursor.position() > 0 {
+                cursor.put_u8(End as u8);
+                // the rest of the field is padded to erase its previous contents
+                while cursor.has_remaining_mut() {
+                    cursor.put_u8(Pad as u8);
+                }
+            }
+        }
+        Ok(OFFSET_OPTIONS + cursors[CURSOR_INDEX_MAIN].position() as usize)
+    }
+
+    /// Only the presence of the option is written.
+    fn put_opt_empty(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<()>) {
+        if value.is_some() {
+            options.push((tag as u8, Vec::new()));
+        }
+    }
+
+    fn put_opt_u8(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<u8>) {
+        if let Some(ref value) = value {
+            options.push((tag as u8, vec![*value]));
+        }
+    }
+
+    fn put_opt_u16(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<u16>) {
+        if let Some(ref value) = value {
+            let mut bytes = Vec::with_capacity(mem::size_of::<u16>());
+            bytes.put_u16_be(*value);
+            options.push((tag as u8, bytes));
+        }
+    }
+
+    fn put_opt_u32(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<u32>) {
+        if let Some(ref value) = value {
+            let mut bytes = Vec::with_capacity(mem::size_of::<u32>());
+            bytes.put_u32_be(*value);
+            options.push((tag as u8, bytes));
+        }
+    }
+
+    fn put_opt_ipv4(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<Ipv4Addr>) {
+        if let Some(ref value) = value {
+            options.push((tag as u8, value.octets().to_vec()));
+        }
+    }
+
+    /// Empty values are not written.
+    fn put_opt_string(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<String>) {
+        if let Some(ref value) = value {
+            if !value.is_empty() {
+                options.push((tag as u8, value.as_bytes().to_vec()));
+            }
+        }
+    }
+
+    /// Empty values are not written.
+    fn put_opt_vec(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<Vec<u8>>) {
+        if let Some(ref value) = value {
+            if !value.is_empty() {
+                options.push((tag as u8, value.to_owned()));
+            }
+        }
+    }
+
+    /// Empty values are not written.
+    fn put_opt_vec_u16(options: &mut Vec<(u8, Vec<u8>)>, tag: OptionTag, value: &Option<Vec<u16>>) {
+        if let Some(ref value) = value {
+            if value.is_empty() {
+                return;
+            }
+            let mut bytes = Vec::with_capacity(value.len() * mem::size_of::<u16>());
+            for element in value.iter() {
+                bytes.put_u16_be(*element);
+            }
+            options.push((tag as u8, bytes));
         }
-        if cursors[CURSOR_INDEX_SNAME].position() > 0 {
-            cursors[CURSOR_INDEX_SNAME].put_u8(End as u8);
+    }
+
+    /// Empty values are not written.
+    fn put_opt_vec_ipv4(
+        options: &mut Vec<(u8, Vec<u8>)>,
+        tag: OptionTag,
+        value: &Option<Vec<Ipv4Addr>>,
+    ) {
+        if let Some(ref value) = value {
+            if value.is_empty() {
+                return;
+            }
+            let mut bytes = Vec::with_capacity(value.len() * mem::size_of::<u32>());
+            for element in value.iter() {
+                bytes.put_u32_be(u32::from(element.to_owned()));
+            }
+            options.push((tag as u8, bytes));
         }
-        Ok(cursors[CURSOR_INDEX_MAIN].position() as usize)
     }
 
-    /// Cannot be splitted.
-    fn put_opt_u8(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// Empty values are not written.
+    fn put_opt_vec_ipv4_pairs(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<u8>,
-    ) -> io::Result<()> {
+        value: &Option<Vec<(Ipv4Addr, Ipv4Addr)>>,
+    ) {
         if let Some(ref value) = value {
-            let size = mem::size_of::<u8>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put_u8(*value);
+            if value.is_empty() {
+                return;
+            }
+            let mut bytes = Vec::with_capacity(value.len() * mem::size_of::<u32>() * 2);
+            for element in value.iter() {
+                bytes.put_u32_be(u32::from(element.0.to_owned()));
+                bytes.put_u32_be(u32::from(element.1.to_owned()));
+            }
+            options.push((tag as u8, bytes));
+        }
+    }
+
+    /// Written as they were received, including the zero-length ones.
+    fn put_opt_unknown(options: &mut Vec<(u8, Vec<u8>)>, value: &[(u8, Vec<u8>)]) {
+        for (tag, value) in value.iter() {
+            options.push((*tag, value.to_owned()));
+        }
+    }
+
+    /// The values are encoded by `CustomOption::to_bytes`.
+    fn put_opt_custom(
+        options: &mut Vec<(u8, Vec<u8>)>,
+        value: &[(u8, Box<dyn CustomOption>)],
+    ) -> io::Result<()> {
+        for (tag, value) in value.iter() {
+            options.push((*tag, value.to_bytes()?));
         }
         Ok(())
     }
 
-    /// Cannot be splitted.
-    fn put_opt_u16(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// The instances are encoded by `UserClass::to_bytes`.
+    fn put_opt_user_class(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<u16>,
+        value: &Option<UserClassStruct>,
     ) -> io::Result<()> {
         if let Some(ref value) = value {
-            let size = mem::size_of::<u16>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put_u16_be(*value);
+            options.push((tag as u8, value.to_bytes()?));
         }
         Ok(())
     }
 
-    /// Cannot be splitted.
-    fn put_opt_u32(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// The flags and the domain name are encoded by `ClientFqdn::to_bytes`.
+    fn put_opt_client_fqdn(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<u32>,
-    ) -> io::Result<()> {
+        value: &Option<ClientFqdnStruct>,
+    ) {
         if let Some(ref value) = value {
-            let size = mem::size_of::<u32>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put_u32_be(*value);
+            options.push((tag as u8, value.to_bytes()));
         }
-        Ok(())
     }
 
-    /// Cannot be splitted.
-    fn put_opt_ipv4(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    fn put_opt_authentication(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<Ipv4Addr>,
-    ) -> io::Result<()> {
+        value: &Option<AuthenticationStruct>,
+    ) {
         if let Some(ref value) = value {
-            let size = mem::size_of::<u32>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put_u32_be(u32::from(*value));
+            options.push((tag as u8, value.to_bytes()));
         }
-        Ok(())
     }
 
-    /// Can be splitted.
-    fn put_opt_string(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// Empty values are not written.
+    fn put_opt_client_system_architecture(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<String>,
-    ) -> io::Result<()> {
+        value: &Option<Vec<ClientArchitecture>>,
+    ) {
         if let Some(ref value) = value {
             if value.is_empty() {
-                return Ok(());
+                return;
             }
-            let size = value.len();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put(value);
+            let mut bytes = Vec::with_capacity(value.len() * mem::size_of::<u16>());
+            for element in value.iter() {
+                bytes.put_u16_be(u16::from(*element));
+            }
+            options.push((tag as u8, bytes));
         }
-        Ok(())
     }
 
-    /// Can be splitted.
-    fn put_opt_vec(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    fn put_opt_network_interface_identifier(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<Vec<u8>>,
-    ) -> io::Result<()> {
+        value: &Option<NetworkInterfaceIdentifier>,
+    ) {
+        if let Some(ref value) = value {
+            options.push((tag as u8, value.to_bytes()));
+        }
+    }
+
+    fn put_opt_machine_identifier(
+        options: &mut Vec<(u8, Vec<u8>)>,
+        tag: OptionTag,
+        value: &Option<MachineIdentifier>,
+    ) {
+        if let Some(ref value) = value {
+            options.push((tag as u8, value.to_bytes()));
+        }
+    }
+
+    /// The names are compressed by `DomainName::to_compressed_list`.
+    ///
+    /// Empty values are not written.
+    fn put_opt_domain_search(
+        options: &mut Vec<(u8, Vec<u8>)>,
+        tag: OptionTag,
+        value: &Option<Vec<DomainName>>,
+    ) {
         if let Some(ref value) = value {
             if value.is_empty() {
-                return Ok(());
+                return;
             }
-            let size = value.len();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            cursor.put(value);
+            options.push((tag as u8, DomainName::to_compressed_list(value)));
         }
-        Ok(())
     }
 
-    /// Can be splitted.
-    fn put_opt_vec_u16(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// The servers are encoded by `SipServers::to_bytes`.
+    ///
+    /// Empty values are not written.
+    fn put_opt_sip_servers(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<Vec<u16>>,
-    ) -> io::Result<()> {
+        value: &Option<SipServersStruct>,
+    ) {
         if let Some(ref value) = value {
             if value.is_empty() {
-                return Ok(());
+                return;
             }
-            let size = value.len() * mem::size_of::<u16>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            for element in value.iter() {
-                cursor.put_u16_be(*element);
+            options.push((tag as u8, value.to_bytes()));
+        }
+    }
+
+    /// The enterprise blocks are encoded by `VendorClassData::to_bytes`.
+    ///
+    /// Empty values are not written.
+    fn put_opt_vi_vendor_class(
+        options: &mut Vec<(u8, Vec<u8>)>,
+        tag: OptionTag,
+        value: &Option<Vec<VendorClassData>>,
+    ) -> io::Result<()> {
+        if let Some(ref value) = value {
+            if !value.is_empty() {
+                options.push((tag as u8, VendorClassData::to_bytes(value)?));
             }
         }
         Ok(())
     }
 
-    /// Can be splitted.
-    fn put_opt_vec_ipv4(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// The enterprise blocks are encoded by `VendorSpecificData::to_bytes`.
+    ///
+    /// Empty values are not written.
+    fn put_opt_vi_vendor_specific(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<Vec<Ipv4Addr>>,
+        value: &Option<Vec<VendorSpecificData>>,
     ) -> io::Result<()> {
         if let Some(ref value) = value {
-            if value.is_empty() {
-                return Ok(());
-            }
-            let size = value.len() * mem::size_of::<u32>();
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            for element in value.iter() {
-                cursor.put_u32_be(u32::from(element.to_owned()));
+            if !value.is_empty() {
+                options.push((tag as u8, VendorSpecificData::to_bytes(value)?));
             }
         }
         Ok(())
     }
 
-    /// Can be splitted.
-    fn put_opt_vec_ipv4_pairs(
-        cursor: &mut io::Cursor<&mut [u8]>,
+    /// The sub-options are encoded by `RelayAgentInformation::to_bytes`.
+    fn put_opt_relay_agent_information(
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
-        value: &Option<Vec<(Ipv4Addr, Ipv4Addr)>>,
+        value: &Option<RelayAgentInformationStruct>,
     ) -> io::Result<()> {
         if let Some(ref value) = value {
-            if value.is_empty() {
-                return Ok(());
-            }
-            let size = value.len() * mem::size_of::<u32>() * 2;
-            check_remaining!(cursor, SIZE_OPTION_AFFIXES + size);
-            cursor.put_u8(tag as u8);
-            cursor.put_u8(size as u8);
-            for element in value.iter() {
-                cursor.put_u32_be(u32::from(element.0.to_owned()));
-                cursor.put_u32_be(u32::from(element.1.to_owned()));
-            }
+            options.push((tag as u8, value.to_bytes()?));
         }
         Ok(())
     }
 
-    /// Can be splitted.
     /// The encoding algorithm explained at [RFC 3442](https://tools.ietf.org/html/rfc3442).
     ///
-    /// The option is splitted by default.
+    /// Empty values are not written.
     fn put_opt_classless_static_routes(
-        cursors: &mut [io::Cursor<&mut [u8]>; CURSOR_INDEX_TOTAL],
+        options: &mut Vec<(u8, Vec<u8>)>,
         tag: OptionTag,
         value: &Option<Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>>,
-    ) -> io::Result<()> {
+    ) {
         if let Some(ref value) = value {
             if value.is_empty() {
-                return Ok(());
+                return;
             }
 
             const BITS_IN_BYTE: usize = 8;
             const IPV4_BITSIZE: usize = mem::size_of::<u32>() * BITS_IN_BYTE;
             const MAX_DESCRIPTOR_SIZE: usize = 1 + mem::size_of::<u32>();
 
-            let mut descriptors = Vec::<Vec<u8>>::with_capacity(value.len());
+            let mut bytes =
+                Vec::with_capacity(value.len() * (MAX_DESCRIPTOR_SIZE + mem::size_of::<u32>()));
             for element in value.iter() {
                 let subnet_number = element.0;
                 let i_subnet_mask = u32::from(element.1);
@@ -719,65 +794,356 @@ impl Message {
                         break;
                     }
                 }
-                let mut descriptor = Vec::<u8>::with_capacity(MAX_DESCRIPTOR_SIZE);
-                descriptor.push(subnet_mask_size as u8);
+                bytes.push(subnet_mask_size as u8);
                 for i in 0..mem::size_of::<u32>() {
                     if subnet_mask_size > i * BITS_IN_BYTE {
-                        descriptor.push(subnet_number.octets()[i]);
+                        bytes.push(subnet_number.octets()[i]);
                     }
                 }
-                descriptors.push(descriptor);
+                bytes.put_u32_be(u32::from(element.2));
             }
+            options.push((tag as u8, bytes));
+        }
+    }
+}
 
-            let (mut i, mut j, mut c) = (0, 0, 0); // iterators
-            while c < cursors.len() {
-                let mut cursor = &mut cursors[c];
-                let affix_len = if c != CURSOR_INDEX_MAIN {
-                    SIZE_OPTION_AFFIXES // only the tag, the length and the END
-                } else {
-                    SIZE_OPTION_MAIN_AFFIXES // also some space for the 'overload' option
-                };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                let mut len: usize = 0; // the length to be written by each cursor
-                let mut repeat = false;
-                while j < descriptors.len() {
-                    let size = descriptors.get(j).unwrap().len() + mem::size_of::<u32>();
-
-                    // find the range that can be written to the current buffer and the current option instance
-                    if cursor.remaining() >= affix_len + len + size && len + size <= SIZE_OPTION_MAX
-                    {
-                        len += size;
-                        j += 1;
-                    } else {
-                        repeat = len + size > SIZE_OPTION_MAX;
-                        break;
-                    }
-                }
+    use super::super::{
+        options::{
+            MessageType, Options, Overload, RelayAgentInformation, RELAY_AGENT_FLAG_UNICAST,
+        },
+        HardwareAddress, HardwareType, OperationCode,
+    };
 
-                if len > 0 {
-                    cursor.put_u8(tag as u8);
-                    cursor.put_u8(len as u8);
-                    for k in i..j {
-                        cursor.put(descriptors.get(k).unwrap());
-                        cursor.put_u32_be(u32::from(value.get(k).unwrap().2.to_owned()));
-                    }
-                    i = j;
-                    if !repeat {
-                        c += 1;
-                    }
-                }
+    fn message() -> Message {
+        Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: false,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options: Options::default(),
+        }
+    }
 
-                if j >= descriptors.len() {
-                    break;
-                }
-            }
-            if j < descriptors.len() {
-                return Err(io::Error::new(
-                    io::ErrorKind::UnexpectedEof,
-                    "No more space left",
-                ));
-            }
+    #[test]
+    fn unknown_options_round_trip() {
+        let mut message = message();
+        message.options.unknown = vec![(224, vec![1, 2, 3]), (84, vec![]), (250, vec![0xff; 255])];
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL * 2];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+
+        assert_eq!(decoded.options.unknown, message.options.unknown);
+    }
+
+    #[test]
+    fn too_long_boot_filename_is_error() {
+        let mut message = message();
+        message.boot_filename = vec![b'a'; SIZE_BOOT_FILENAME + 1];
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let error = message.to_bytes(&mut buffer, None).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        message.boot_filename.pop();
+        assert!(message.to_bytes(&mut buffer, None).is_ok());
+    }
+
+    #[test]
+    fn long_option_is_splitted() {
+        let mut message = message();
+        let servers: Vec<Ipv4Addr> = (0..100).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        message.options.domain_name_servers = Some(servers.clone());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL * 2];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let instances = buffer[OFFSET_OPTIONS..amount]
+            .windows(2)
+            .filter(|w| w[0] == OptionTag::DomainNameServers as u8 && w[1] == 255)
+            .count();
+        assert_eq!(instances, 1);
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert!(decoded.options.overload.is_none());
+        assert_eq!(decoded.options.domain_name_servers, Some(servers));
+    }
+
+    #[test]
+    fn long_option_is_splitted_over_overloaded_areas() {
+        let mut message = message();
+        let servers: Vec<Ipv4Addr> = (0..100).map(|i| Ipv4Addr::new(10, 0, 1, i)).collect();
+        let routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)> = (0..4)
+            .map(|i| {
+                (
+                    Ipv4Addr::new(192, 168, i, 0),
+                    Ipv4Addr::new(255, 255, 255, 0),
+                    Ipv4Addr::new(192, 168, 0, 1),
+                )
+            })
+            .collect();
+        message.options.dhcp_message_type = Some(MessageType::DhcpOffer);
+        message.options.domain_name_servers = Some(servers.clone());
+        message.options.classless_static_routes = Some(routes.clone());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message
+            .to_bytes(&mut buffer, Some(SIZE_MESSAGE_MINIMAL as u16))
+            .unwrap();
+        assert!(amount <= SIZE_MESSAGE_MINIMAL - SIZE_HEADER_IP - SIZE_HEADER_UDP);
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        match decoded.options.overload {
+            Some(Overload::Both) => {}
+            _ => panic!("Both `file` and `sname` must be overloaded"),
         }
-        Ok(())
+        match decoded.options.dhcp_message_type {
+            Some(MessageType::DhcpOffer) => {}
+            _ => panic!("The message type is lost"),
+        }
+        assert_eq!(decoded.options.domain_name_servers, Some(servers));
+        assert_eq!(decoded.options.classless_static_routes, Some(routes));
+    }
+
+    #[test]
+    fn occupied_areas_are_not_overloaded() {
+        let mut message = message();
+        let servers: Vec<Ipv4Addr> = (0..90).map(|i| Ipv4Addr::new(10, 0, 2, i)).collect();
+        message.boot_filename = b"pxelinux.0".to_vec();
+        message.options.domain_name_servers = Some(servers.clone());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message
+            .to_bytes(&mut buffer, Some(SIZE_MESSAGE_MINIMAL as u16))
+            .unwrap();
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        match decoded.options.overload {
+            Some(Overload::Sname) => {}
+            _ => panic!("Only `sname` must be overloaded"),
+        }
+        assert_eq!(&decoded.boot_filename[..10], b"pxelinux.0");
+        assert_eq!(decoded.options.domain_name_servers, Some(servers));
+    }
+
+    #[test]
+    fn domain_search_is_splitted() {
+        let mut message = message();
+        let names: Vec<DomainName> = (0..12)
+            .map(|i| {
+                format!("{}-{}.example.com", "x".repeat(20), i)
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+        message.options.domain_search = Some(names.clone());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL * 2];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+
+        let decoded = decoded.options.domain_search.unwrap();
+        assert_eq!(decoded.len(), names.len());
+        for (decoded, name) in decoded.iter().zip(names.iter()) {
+            assert_eq!(decoded.labels(), name.labels());
+            assert!(decoded.is_fully_qualified());
+        }
+    }
+
+    #[test]
+    fn vendor_identifying_options_round_trip() {
+        let mut message = message();
+        let class = vec![VendorClassData {
+            enterprise_number: 4491,
+            data: vec![b"docsis3.0".to_vec()],
+        }];
+        let specific = vec![
+            VendorSpecificData {
+                enterprise_number: 4491,
+                sub_options: vec![(1, vec![0x01, 0x02]), (2, b"cm".to_vec())],
+            },
+            VendorSpecificData {
+                enterprise_number: 3561,
+                sub_options: vec![(4, b"SN0001".to_vec())],
+            },
+        ];
+        message.options.vi_vendor_class = Some(class.clone());
+        message.options.vi_vendor_specific = Some(specific.clone());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.options.vi_vendor_class, Some(class));
+        assert_eq!(decoded.options.vi_vendor_specific, Some(specific));
+
+        let output = decoded.to_string();
+        assert!(output.contains("[124] vi_vendor_class"));
+        assert!(output.contains("4491: [\"docsis3.0\"]"));
+        assert!(output.contains("[125] vi_vendor_specific"));
+        assert!(output.contains("3561: [4=[83, 78, 48, 48, 48, 49]]"));
+    }
+
+    #[test]
+    fn pxe_client_options_round_trip() {
+        let mut message = message();
+        message.options.client_system_architecture = Some(vec![
+            ClientArchitecture::EfiX64,
+            ClientArchitecture::X86Bios,
+            // the unknown types are kept as is
+            ClientArchitecture::Other(0x1234),
+        ]);
+        message.options.client_network_interface_identifier =
+            Some(NetworkInterfaceIdentifier::undi(3, 16));
+        message.options.client_machine_identifier = Some(MachineIdentifier::guid([0x5a; 16]));
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(
+            decoded.options.client_system_architecture,
+            message.options.client_system_architecture
+        );
+        assert_eq!(
+            decoded.options.client_network_interface_identifier,
+            message.options.client_network_interface_identifier
+        );
+        assert_eq!(
+            decoded.options.client_machine_identifier,
+            message.options.client_machine_identifier
+        );
+        assert!(decoded.to_string().contains("x64 UEFI, x86 BIOS"));
+    }
+
+    #[test]
+    fn ipv6_only_preferred_and_captive_portal_round_trip() {
+        let mut message = message();
+        message.operation_code = OperationCode::BootReply;
+        message.options.dhcp_message_type = Some(MessageType::DhcpOffer);
+        message.options.ipv6_only_preferred = Some(1800);
+        message.options.captive_portal = Some("https://example.org/portal".to_owned());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.options.ipv6_only_preferred, Some(1800));
+        assert_eq!(
+            decoded.options.captive_portal,
+            message.options.captive_portal
+        );
+        // the wait time is encoded as a 32-bit number of seconds (RFC 8925 §3.1)
+        assert!(buffer[OFFSET_OPTIONS..amount]
+            .windows(6)
+            .any(|window| window == [108, 4, 0, 0, 0x07, 0x08]));
+    }
+
+    #[test]
+    fn hardware_address_honours_length() {
+        // RFC 4390 clients send a zero-length address and identify themselves with `client_id`
+        let mut message = message();
+        message.options.dhcp_message_type = Some(MessageType::DhcpDiscover);
+        message.hardware_type = HardwareType::InfiniBand;
+        message.client_hardware_address = HardwareAddress::empty();
+        message.options.client_id = Some(vec![0xff; 20]);
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        assert_eq!(&buffer[1..3], &[32, 0]);
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.hardware_type, HardwareType::InfiniBand);
+        assert!(decoded.client_hardware_address.is_empty());
+        assert!(decoded.validate().is_ok());
+
+        let eui64 = [0x00, 0x11, 0x22, 0xff, 0xfe, 0x33, 0x44, 0x55];
+        message.hardware_type = HardwareType::Ieee1394;
+        message.client_hardware_address = HardwareAddress::new(&eui64).unwrap();
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        assert_eq!(buffer[2], 8);
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.client_hardware_address.as_bytes(), &eui64);
+        assert_eq!(decoded.client_hardware_address.to_mac_address(), None);
+        assert!(decoded.validate().is_ok());
+
+        message.hardware_type = HardwareType::Ethernet;
+        assert!(message.validate().is_err());
+
+        buffer[2] = 17;
+        assert!(Message::from_bytes(&buffer[..amount]).is_err());
+
+        // the unknown types are kept as is
+        message.hardware_type = HardwareType::Other(0xf0);
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        assert_eq!(buffer[1], 0xf0);
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded.hardware_type, HardwareType::Other(0xf0));
+    }
+
+    #[test]
+    fn relay_agent_information_is_last() {
+        let mut message = message();
+        let servers: Vec<Ipv4Addr> = (0..100).map(|i| Ipv4Addr::new(10, 0, 3, i)).collect();
+        message.options.domain_name_servers = Some(servers.clone());
+        message.options.unknown = vec![(224, vec![1, 2, 3])];
+        message.options.relay_agent_information = Some(RelayAgentInformation {
+            circuit_id: Some(b"eth0/1/2".to_vec()),
+            remote_id: Some(vec![0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]),
+            link_selection: Some(Ipv4Addr::new(192, 168, 10, 0)),
+            subscriber_id: Some(b"subscriber".to_vec()),
+            radius_attributes: Some(vec![(26, vec![0, 0, 0, 9, 1, 2]), (1, b"user".to_vec())]),
+            relay_agent_flags: Some(RELAY_AGENT_FLAG_UNICAST),
+            server_identifier_override: Some(Ipv4Addr::new(192, 168, 0, 1)),
+            unknown: vec![(151, vec![4, 2])],
+            ..RelayAgentInformation::default()
+        });
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message
+            .to_bytes(&mut buffer, Some(SIZE_MESSAGE_MINIMAL as u16))
+            .unwrap();
+
+        let value = message
+            .options
+            .relay_agent_information
+            .as_ref()
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+        let start = amount - 1 - value.len() - 2;
+        assert_eq!(buffer[start], OptionTag::RelayAgentInformation as u8);
+        assert_eq!(&buffer[start + 2..amount - 1], value.as_slice());
+        assert_eq!(buffer[amount - 1], OptionTag::End as u8);
+
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert!(decoded.options.overload.is_some());
+        assert_eq!(decoded.options.domain_name_servers, Some(servers));
+        let information = decoded.options.relay_agent_information.unwrap();
+        assert_eq!(information.circuit_id, Some(b"eth0/1/2".to_vec()));
+        assert_eq!(
+            information.link_selection,
+            Some(Ipv4Addr::new(192, 168, 10, 0))
+        );
+        assert_eq!(information.subscriber_id, Some(b"subscriber".to_vec()));
+        assert_eq!(
+            information.radius_attributes,
+            Some(vec![(26, vec![0, 0, 0, 9, 1, 2]), (1, b"user".to_vec())])
+        );
+        assert!(information.is_unicast());
+        assert_eq!(
+            information.server_identifier_override,
+            Some(Ipv4Addr::new(192, 168, 0, 1))
+        );
+        assert_eq!(information.unknown, vec![(151, vec![4, 2])]);
     }
 }
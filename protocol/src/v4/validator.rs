@@ -1,79 +1,586 @@
 //! DHCP message validation module.
 
-use super::{constants::SIZE_MESSAGE_MINIMAL, options::MessageType, Message};
+use std::{fmt, net::Ipv4Addr};
+
+use super::{
+    constants::SIZE_MESSAGE_MINIMAL,
+    options::{
+        MessageType, OptionTag, Options, AUTHENTICATION_PROTOCOL_DELAYED,
+        AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY, AUTHENTICATION_RDM_MONOTONIC,
+    },
+    Message, OperationCode,
+};
 
 /// The error type returned by `Message::validate`.
+///
+/// Carries the subject of the first violation which is not tolerated, i.e. the name
+/// of the option or field or the description of the invalid value. The kinds of
+/// all the violations are reported by `Message::validation_report`.
 #[derive(Fail, Debug)]
 pub enum Error {
     #[fail(display = "Validation error: {}", _0)]
     Validation(&'static str),
 }
 
+/// How strictly the violations are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enforcement {
+    /// Any violation makes the message invalid.
+    Strict,
+    /// The forbidden options and field values are ignored
+    /// and only the messages which cannot be processed are invalid.
+    Lenient,
+}
+
+/// A requirement the message does not meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    /// A required option or field value is absent.
+    Absent(&'static str),
+    /// An option or field value is present where it is not allowed.
+    Forbidden(&'static str),
+    /// A value is malformed or contradicts the other ones.
+    Invalid(&'static str),
+}
+
+impl Violation {
+    /// Checks if the violation does not make the message invalid.
+    pub fn is_tolerated(&self, enforcement: Enforcement) -> bool {
+        matches!(
+            (self, enforcement),
+            (Violation::Forbidden(_), Enforcement::Lenient)
+        )
+    }
+
+    /// The name of the option or field or the description of the invalid value.
+    pub fn subject(&self) -> &'static str {
+        match *self {
+            Violation::Absent(subject)
+            | Violation::Forbidden(subject)
+            | Violation::Invalid(subject) => subject,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Absent(name) => write!(f, "{} is absent", name),
+            Violation::Forbidden(name) => write!(f, "{} is forbidden", name),
+            Violation::Invalid(description) => write!(f, "{}", description),
+        }
+    }
+}
+
+/// All the violations found in a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// `None` if the DHCP message type is absent or zero.
+    pub dhcp_message_type: Option<MessageType>,
+    /// The violations in the order they have been found.
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Checks if the message is valid with the enforcement.
+    pub fn is_valid(&self, enforcement: Enforcement) -> bool {
+        self.to_result(enforcement).is_ok()
+    }
+
+    /// Returns the DHCP message type if the message is valid with the enforcement.
+    ///
+    /// # Errors
+    /// Returns `Error::Validation` with the subject of the first violation which is not tolerated.
+    pub fn to_result(&self, enforcement: Enforcement) -> Result<MessageType, Error> {
+        if let Some(violation) = self
+            .violations
+            .iter()
+            .find(|violation| !violation.is_tolerated(enforcement))
+        {
+            return Err(Error::Validation(violation.subject()));
+        }
+        self.dhcp_message_type
+            .ok_or(Error::Validation("DHCP message type is absent or zero"))
+    }
+}
+
+/// The fields which RFC 2131 requires to be zero or unused in some messages.
+trait IsZero {
+    fn is_zero(&self) -> bool;
+}
+
+impl IsZero for u8 {
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl IsZero for u16 {
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+}
+
+impl IsZero for bool {
+    fn is_zero(&self) -> bool {
+        !*self
+    }
+}
+
+impl IsZero for Ipv4Addr {
+    fn is_zero(&self) -> bool {
+        self.is_unspecified()
+    }
+}
+
+impl IsZero for Vec<u8> {
+    fn is_zero(&self) -> bool {
+        self.is_empty()
+    }
+}
+
 /// Checks if required options are present for each message type.
 macro_rules! must_set_option (
-    ($name:expr) => (
+    ($report:expr, $name:expr) => (
         if $name.is_none() {
-            return Err(Error::Validation(stringify!($name)));
+            $report.violations.push(Violation::Absent(stringify!($name)));
+        }
+    );
+);
+
+/// Checks if forbidden options are absent for each message type.
+macro_rules! must_not_set_option (
+    ($report:expr, $name:expr) => (
+        if $name.is_some() {
+            $report.violations.push(Violation::Forbidden(stringify!($name)));
         }
     );
 );
 
+/// Checks if required field values are nonzero for each message type.
+macro_rules! must_set_field (
+    ($report:expr, $name:expr) => (
+        if $name.is_zero() {
+            $report.violations.push(Violation::Absent(stringify!($name)));
+        }
+    );
+);
+
+/// Checks if the fields are zero or unused for each message type.
+macro_rules! must_not_set_field (
+    ($report:expr, $name:expr) => (
+        if !$name.is_zero() {
+            $report.violations.push(Violation::Forbidden(stringify!($name)));
+        }
+    );
+);
+
+/// The options defined after RFC 2131 which may appear in any message.
+///
+/// The relay agents append the Relay Agent Information option to any client message,
+/// RFC 3118 authenticates all the messages and RFC 3011 requires servers
+/// to echo the Subnet Selection option in all replies.
+const EXTENSIONS_ALLOWED: &[OptionTag] = &[
+    OptionTag::RelayAgentInformation,
+    OptionTag::Authentication,
+    OptionTag::SubnetSelection,
+];
+
+/// The options RFC 2131 table 5 allows in `DHCPDECLINE` and `DHCPRELEASE`.
+const CLIENT_RELEASE_ALLOWED: &[OptionTag] = &[
+    OptionTag::AddressRequest,
+    OptionTag::Overload,
+    OptionTag::DhcpMessageType,
+    OptionTag::ClientId,
+    OptionTag::DhcpServerId,
+    OptionTag::DhcpMessage,
+];
+
+/// The options RFC 2131 table 3 allows in `DHCPNAK`.
+const SERVER_NAK_ALLOWED: &[OptionTag] = &[
+    OptionTag::DhcpMessageType,
+    OptionTag::DhcpMessage,
+    OptionTag::ClientId,
+    OptionTag::ClassId,
+    OptionTag::DhcpServerId,
+];
+
 impl Message {
     /// DHCP message validation.
     ///
+    /// The options and field values forbidden by RFC 2131 are tolerated.
     /// Returns the DHCP message type on successful validation.
     ///
     /// # Errors
     /// Returns `Error::Validation` if any option is invalid.
     pub fn validate(&self) -> Result<MessageType, Error> {
-        let message = self; // for the stringify! macro above
+        self.validate_with(Enforcement::Lenient)
+    }
+
+    /// DHCP message validation with the chosen enforcement.
+    ///
+    /// Returns the DHCP message type on successful validation.
+    ///
+    /// # Errors
+    /// Returns `Error::Validation` with the first violation which is not tolerated.
+    pub fn validate_with(&self, enforcement: Enforcement) -> Result<MessageType, Error> {
+        self.validation_report().to_result(enforcement)
+    }
+
+    /// Checks the message against RFC 2131 tables 3 and 5 and the extensions
+    /// and collects all the violations.
+    ///
+    /// The 'hops' and 'giaddr' fields of client messages are not checked,
+    /// since they are modified by relay agents.
+    pub fn validation_report(&self) -> ValidationReport {
+        let message = self; // for the stringify! macros above
+        let mut report = ValidationReport::default();
 
         let dhcp_message_type = match message.options.dhcp_message_type {
             None | Some(MessageType::Undefined) => {
-                return Err(Error::Validation("DHCP message type is absent or zero"));
+                report.violations.push(Violation::Invalid("DHCP message type is absent or zero"));
+                return report;
             }
             Some(dhcp_message_type) => dhcp_message_type,
         };
+        report.dhcp_message_type = Some(dhcp_message_type);
 
         if let Some(dhcp_max_message_size) = message.options.dhcp_max_message_size {
             if (dhcp_max_message_size as usize) < SIZE_MESSAGE_MINIMAL {
-                return Err(Error::Validation("DHCP maximal message size is too low"));
+                report.violations.push(Violation::Invalid("DHCP maximal message size is too low"));
             }
         }
 
-        match dhcp_message_type {
-            // client generated packets section
-            MessageType::DhcpDiscover => {}
-            MessageType::DhcpRequest => if message.client_ip_address.is_unspecified()
-                || message.options.dhcp_server_id.is_some()
+        // zero-length addresses are allowed in replies restored from client identifiers
+        if let Some(address_length) = message.hardware_type.address_length() {
+            if !message.client_hardware_address.is_empty()
+                && message.client_hardware_address.len() != address_length
             {
-                must_set_option!(message.options.address_request);
+                report.violations.push(Violation::Invalid(
+                    "Hardware address length does not match the hardware type",
+                ));
+            }
+        }
+
+        /*
+        RFC 4039 §4
+        A client MUST include this option in a DHCPDISCOVER message if the
+        client is prepared to perform the DHCPDISCOVER-DHCPACK message
+        exchange described earlier.
+
+        A server MUST include this option in a DHCPACK message sent in a
+        response to a DHCPDISCOVER message when completing the
+        DHCPDISCOVER-DHCPACK message exchange.
+        */
+        // not tolerated, since the address would be committed outside the two-message exchange
+        match dhcp_message_type {
+            MessageType::DhcpDiscover | MessageType::DhcpAck => {}
+            _ => if message.options.rapid_commit.is_some() {
+                report.violations.push(Violation::Invalid("Rapid Commit option is misplaced"));
             },
-            MessageType::DhcpInform => {}
+        }
+
+        // RFC 3118 only defines the monotonically increasing counter replay detection method
+        if let Some(ref authentication) = message.options.authentication {
+            if authentication.rdm != AUTHENTICATION_RDM_MONOTONIC {
+                report.violations.push(Violation::Invalid(
+                    "Authentication replay detection method is unknown",
+                ));
+            }
+            match authentication.protocol {
+                AUTHENTICATION_PROTOCOL_DELAYED
+                    if !authentication.is_delayed_request()
+                        && authentication.delayed_secret_id().is_none() =>
+                {
+                    report.violations.push(Violation::Invalid(
+                        "Delayed authentication information is invalid",
+                    ));
+                }
+                AUTHENTICATION_PROTOCOL_RECONFIGURE_KEY
+                    if authentication.reconfigure_key_value().is_none()
+                        && !authentication.is_reconfigure_hmac_md5() =>
+                {
+                    report.violations.push(Violation::Invalid(
+                        "Reconfigure key authentication information is invalid",
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // RFC 4390 clients have no hardware address in 'chaddr' and must be identified otherwise
+        match dhcp_message_type {
+            MessageType::DhcpDiscover
+            | MessageType::DhcpRequest
+            | MessageType::DhcpInform
+            | MessageType::DhcpRelease
+            | MessageType::DhcpDecline
+                if message.client_hardware_address.is_empty()
+                    && message.options.client_id.as_ref().is_none_or(Vec::is_empty) =>
+            {
+                report.violations.push(Violation::Absent("Client identifier"));
+            }
+            _ => {}
+        }
+
+        match dhcp_message_type {
+            MessageType::DhcpDiscover
+            | MessageType::DhcpRequest
+            | MessageType::DhcpInform
+            | MessageType::DhcpRelease
+            | MessageType::DhcpDecline => {
+                if message.operation_code != OperationCode::BootRequest {
+                    report.violations.push(Violation::Invalid("Client message is not BOOTREQUEST"));
+                }
+                must_not_set_field!(report, message.your_ip_address);
+                must_not_set_field!(report, message.server_ip_address);
+            }
+            MessageType::DhcpOffer | MessageType::DhcpAck | MessageType::DhcpNak => {
+                if message.operation_code != OperationCode::BootReply {
+                    report.violations.push(Violation::Invalid("Server message is not BOOTREPLY"));
+                }
+                must_not_set_field!(report, message.hardware_options);
+                must_not_set_field!(report, message.seconds);
+                must_not_set_option!(report, message.options.address_request);
+                must_not_set_option!(report, message.options.parameter_list);
+                must_not_set_option!(report, message.options.dhcp_max_message_size);
+                must_set_option!(report, message.options.dhcp_server_id);
+            }
+            _ => {}
+        }
+
+        match dhcp_message_type {
+            // client generated packets section (RFC 2131 table 5)
+            MessageType::DhcpDiscover => {
+                must_not_set_field!(report, message.client_ip_address);
+                must_not_set_option!(report, message.options.dhcp_server_id);
+            }
+            MessageType::DhcpRequest => {
+                // 'ciaddr' is only filled in BOUND, RENEWING or REBINDING
+                // and the server identifier is only sent in SELECTING
+                if !message.client_ip_address.is_unspecified() {
+                    must_not_set_option!(report, message.options.dhcp_server_id);
+                }
+                if message.client_ip_address.is_unspecified()
+                    || message.options.dhcp_server_id.is_some()
+                {
+                    must_set_option!(report, message.options.address_request);
+                } else {
+                    must_not_set_option!(report, message.options.address_request);
+                }
+            }
+            MessageType::DhcpInform => {
+                must_set_field!(report, message.client_ip_address);
+                must_not_set_option!(report, message.options.address_request);
+                must_not_set_option!(report, message.options.address_time);
+                must_not_set_option!(report, message.options.dhcp_server_id);
+            }
             MessageType::DhcpRelease => {
-                must_set_option!(message.options.dhcp_server_id);
+                must_set_field!(report, message.client_ip_address);
+                must_not_set_field!(report, message.seconds);
+                must_not_set_field!(report, message.is_broadcast);
+                must_not_set_option!(report, message.options.address_request);
+                must_set_option!(report, message.options.dhcp_server_id);
+                Self::forbid_other_options(&mut report, &message.options, CLIENT_RELEASE_ALLOWED);
             }
             MessageType::DhcpDecline => {
-                must_set_option!(message.options.address_request);
-                must_set_option!(message.options.dhcp_server_id);
+                must_not_set_field!(report, message.client_ip_address);
+                must_not_set_field!(report, message.seconds);
+                must_not_set_field!(report, message.is_broadcast);
+                must_set_option!(report, message.options.address_request);
+                must_set_option!(report, message.options.dhcp_server_id);
+                Self::forbid_other_options(&mut report, &message.options, CLIENT_RELEASE_ALLOWED);
             }
 
-            // server generated packets section
+            // server generated packets section (RFC 2131 table 3)
             MessageType::DhcpOffer => {
-                must_set_option!(message.options.address_time);
-                must_set_option!(message.options.dhcp_server_id);
-            }
-            MessageType::DhcpAck => {
-                must_set_option!(message.options.address_time);
-                must_set_option!(message.options.dhcp_server_id);
+                must_not_set_field!(report, message.client_ip_address);
+                must_set_field!(report, message.your_ip_address);
+                must_set_option!(report, message.options.address_time);
             }
+            // the acknowledgements of `DHCPINFORM` are the only ones with zero 'yiaddr'
+            MessageType::DhcpAck => if message.your_ip_address.is_unspecified() {
+                must_not_set_option!(report, message.options.address_time);
+            } else {
+                must_set_option!(report, message.options.address_time);
+            },
             MessageType::DhcpNak => {
-                must_set_option!(message.options.dhcp_server_id);
+                must_not_set_field!(report, message.client_ip_address);
+                must_not_set_field!(report, message.your_ip_address);
+                must_not_set_field!(report, message.server_ip_address);
+                must_not_set_field!(report, message.server_name);
+                must_not_set_field!(report, message.boot_filename);
+                Self::forbid_other_options(&mut report, &message.options, SERVER_NAK_ALLOWED);
             }
+            MessageType::DhcpForceRenew => {
+                must_set_option!(report, message.options.dhcp_server_id);
+                must_set_option!(report, message.options.authentication);
+            }
+
+            // leasequery packets section (RFC 4388)
+            MessageType::DhcpLeaseQuery => {
+                if message.gateway_ip_address.is_unspecified() {
+                    report.violations.push(Violation::Absent("Leasequery requestor address"));
+                }
+                if message.client_ip_address.is_unspecified()
+                    && message.options.client_id.is_none()
+                    && message.client_hardware_address.is_empty()
+                {
+                    report.violations.push(Violation::Absent("Leasequery criterion"));
+                }
+            }
+            MessageType::DhcpLeaseActive => {
+                must_set_option!(report, message.options.address_time);
+                must_set_option!(report, message.options.dhcp_server_id);
+            }
+            MessageType::DhcpLeaseUnassigned | MessageType::DhcpLeaseUnknown => {
+                must_set_option!(report, message.options.dhcp_server_id);
+            }
+
+            MessageType::Undefined => {}
+        }
+
+        report
+    }
 
-            _ => return Err(Error::Validation("Unknown DHCP message type")),
+    /// Reports the options which are not listed as allowed,
+    /// since tables 3 and 5 say "All others: MUST NOT" for some message types.
+    fn forbid_other_options(
+        report: &mut ValidationReport,
+        options: &Options,
+        allowed: &[OptionTag],
+    ) {
+        for (code, _) in options.iter() {
+            let tag = OptionTag::from(code);
+            if !allowed.contains(&tag) && !EXTENSIONS_ALLOWED.contains(&tag) {
+                report.violations.push(Violation::Forbidden(tag.name()));
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::{HardwareAddress, HardwareType};
+
+    fn message(message_type: MessageType) -> Message {
+        let options = Options {
+            dhcp_message_type: Some(message_type),
+            ..Options::default()
+        };
+        Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: false,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options,
+        }
+    }
+
+    #[test]
+    fn rapid_commit() {
+        let mut message = message(MessageType::DhcpDiscover);
+        message.options.rapid_commit = Some(());
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        assert!(decoded.options.rapid_commit.is_some());
+        assert!(decoded.validate().is_ok());
+
+        message.options.dhcp_message_type = Some(MessageType::DhcpInform);
+        assert!(message.validate().is_err());
+    }
+
+    #[test]
+    fn leasequery_round_trip() {
+        let mut message = message(MessageType::DhcpLeaseQuery);
+        assert!(message.validate().is_err());
+        message.gateway_ip_address = Ipv4Addr::new(192, 168, 0, 1);
+        assert!(message.validate().is_ok());
+
+        message.operation_code = OperationCode::BootReply;
+        message.options.dhcp_message_type = Some(MessageType::DhcpLeaseActive);
+        message.options.dhcp_server_id = Some(Ipv4Addr::new(192, 168, 0, 2));
+        message.options.address_time = Some(3600);
+        message.options.client_last_transaction_time = Some(120);
+        message.options.associated_ip = Some(vec![Ipv4Addr::new(192, 168, 0, 10)]);
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message.to_bytes(&mut buffer, None).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        match decoded.validate() {
+            Ok(MessageType::DhcpLeaseActive) => {}
+            _ => panic!("The message type is not DHCPLEASEACTIVE"),
+        }
+        assert_eq!(decoded.options.client_last_transaction_time, Some(120));
+        assert_eq!(decoded.options.associated_ip, message.options.associated_ip);
+    }
+
+    #[test]
+    fn validation_report_collects_rfc2131_violations() {
+        let mut discover = message(MessageType::DhcpDiscover);
+        discover.client_ip_address = Ipv4Addr::new(192, 168, 0, 100);
+        discover.options.dhcp_server_id = Some(Ipv4Addr::new(192, 168, 0, 2));
+        let report = discover.validation_report();
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::Forbidden("message.client_ip_address"),
+                Violation::Forbidden("message.options.dhcp_server_id"),
+            ]
+        );
+        assert!(report.is_valid(Enforcement::Lenient));
+        match discover.validate_with(Enforcement::Strict) {
+            Err(Error::Validation(subject)) => assert_eq!(subject, "message.client_ip_address"),
+            result => panic!("The strict validation result is {:?}", result),
+        }
+
+        let mut release = message(MessageType::DhcpRelease);
+        release.options.parameter_list = Some(vec![1, 3]);
+        release.options.hostname = Some("client".to_owned());
+        let report = release.validation_report();
+        assert_eq!(
+            report.violations,
+            vec![
+                Violation::Absent("message.client_ip_address"),
+                Violation::Absent("message.options.dhcp_server_id"),
+                Violation::Forbidden("hostname"),
+                Violation::Forbidden("parameter_list"),
+            ]
+        );
+        assert!(release.validate().is_err());
+
+        // the acknowledgements of `DHCPINFORM` have no lease
+        let mut ack = message(MessageType::DhcpAck);
+        ack.operation_code = OperationCode::BootReply;
+        ack.options.dhcp_server_id = Some(Ipv4Addr::new(192, 168, 0, 2));
+        assert!(ack.validate_with(Enforcement::Strict).is_ok());
+        ack.options.address_time = Some(3600);
+        assert!(ack.validate().is_ok());
+        assert!(ack.validate_with(Enforcement::Strict).is_err());
+        ack.your_ip_address = Ipv4Addr::new(192, 168, 0, 100);
+        assert!(ack.validate_with(Enforcement::Strict).is_ok());
 
-        Ok(dhcp_message_type)
+        ack.options.dhcp_message_type = Some(MessageType::DhcpNak);
+        assert_eq!(
+            ack.validation_report().violations,
+            vec![
+                Violation::Forbidden("message.your_ip_address"),
+                Violation::Forbidden("address_time"),
+            ]
+        );
     }
 }
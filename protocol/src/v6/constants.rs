@@ -0,0 +1,24 @@
+//! DHCPv6 message constants.
+
+/// The UDP port the clients listen on.
+pub const DHCPV6_PORT_CLIENT: u16 = 546;
+/// The UDP port the servers and relay agents listen on.
+pub const DHCPV6_PORT_SERVER: u16 = 547;
+
+/// The link-scoped multicast address of all relay agents and servers (`ff02::1:2`).
+pub const ALL_DHCP_RELAY_AGENTS_AND_SERVERS: [u16; 8] = [0xff02, 0, 0, 0, 0, 0, 1, 2];
+/// The site-scoped multicast address of all servers (`ff05::1:3`).
+pub const ALL_DHCP_SERVERS: [u16; 8] = [0xff05, 0, 0, 0, 0, 0, 1, 3];
+
+/// The maximal number of relay agents a message may pass through.
+pub const HOP_COUNT_LIMIT: u8 = 8;
+
+/// The message type and the transaction ID.
+pub const SIZE_HEADER: usize = 4;
+/// The message type, the hop count and the link and peer addresses.
+pub const SIZE_HEADER_RELAY: usize = 34;
+/// The option code and the option length.
+pub const SIZE_OPTION_PREFIX: usize = 4;
+
+/// The transaction ID occupies only 3 octets.
+pub const TRANSACTION_ID_MAX: u32 = 0x00ff_ffff;
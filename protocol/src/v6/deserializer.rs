@@ -0,0 +1,275 @@
+//! DHCPv6 message deserialization module.
+
+use std::{io, mem, net::Ipv6Addr};
+
+use bytes::Buf;
+
+use v4::options::DomainName;
+
+use super::{
+    constants::*,
+    options::{
+        Duid, IaAddress, IaPrefix as IaPrefixStruct, IdentityAssociation,
+        OptionTag::{self, *},
+        Options, StatusCode as StatusCodeStruct, TemporaryIdentityAssociation,
+    },
+    Message, MessageType, RelayMessage,
+};
+
+/// Checks if there is enough space in buffer to get a value.
+macro_rules! check_remaining(
+    ($cursor:expr, $length:expr) => (
+        if $cursor.remaining() < $length {
+            return Err(
+                io::Error::new(io::ErrorKind::UnexpectedEof,
+                "Buffer is too small or packet has invalid length fields",
+            ));
+        }
+    );
+);
+
+/// Checks if the length field contains correct length for each type and is not zero.
+macro_rules! check_length(
+    ($len:expr) => (
+        if $len == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Length field is zero"));
+        }
+    );
+    ($len:expr, $correct:expr) => (
+        if $len != $correct {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Length field is invalid"));
+        }
+    );
+);
+
+/// Checks if the vector size in bytes is divisible by the length of its element.
+macro_rules! check_divisibility(
+    ($len:expr, $divider:expr) => (
+        if $len % $divider != 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Divisibility check failed"));
+        }
+    );
+);
+
+/// The IPv6 address size in bytes.
+const SIZE_IPV6: usize = 16;
+
+impl Message {
+    /// DHCPv6 client/server message deserialization.
+    ///
+    /// # Errors
+    /// `io::Error` if the packet is abrupted, contains invalid length fields
+    /// or is a relay agent message.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        let mut cursor = io::Cursor::new(src);
+        check_remaining!(cursor, SIZE_HEADER);
+
+        let message_type = MessageType::from(cursor.get_u8());
+        if message_type.is_relay() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Relay agent message is not a client/server one",
+            ));
+        }
+        let transaction_id = u32::from(cursor.get_u8()) << 16 | u32::from(cursor.get_u16_be());
+
+        Ok(Message {
+            message_type,
+            transaction_id,
+            options: Options::from_bytes(&src[SIZE_HEADER..])?,
+        })
+    }
+}
+
+impl RelayMessage {
+    /// DHCPv6 relay agent message deserialization.
+    ///
+    /// The encapsulated message is kept as is in the relay message option.
+    ///
+    /// # Errors
+    /// `io::Error` if the packet is abrupted, contains invalid length fields
+    /// or is not a relay agent message.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        let mut cursor = io::Cursor::new(src);
+        check_remaining!(cursor, SIZE_HEADER_RELAY);
+
+        let message_type = MessageType::from(cursor.get_u8());
+        if !message_type.is_relay() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Client/server message is not a relay agent one",
+            ));
+        }
+
+        Ok(RelayMessage {
+            message_type,
+            hop_count: cursor.get_u8(),
+            link_address: get_ipv6(&mut cursor),
+            peer_address: get_ipv6(&mut cursor),
+            options: Options::from_bytes(&src[SIZE_HEADER_RELAY..])?,
+        })
+    }
+}
+
+/// The place where an options area is found, which limits the options it may contain.
+///
+/// IA_NA, IA_TA and IA_PD may only be found in a message, the IA Address and IA Prefix
+/// only in an identity association, and the latter two encapsulate only the Status Code
+/// (RFC 8415 §21.4-21.6, §21.21, §21.22). Thus the nesting depth is bounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Encapsulation {
+    /// The options of a message or a relay agent message.
+    Message,
+    /// The options of IA_NA, IA_TA or IA_PD.
+    IdentityAssociation,
+    /// The options of IA Address or IA Prefix.
+    Address,
+}
+
+impl Encapsulation {
+    /// Checks if a known option may be found in the options area.
+    ///
+    /// The unknown options are allowed everywhere, since they are kept as raw values.
+    fn allows(self, tag: OptionTag) -> bool {
+        match self {
+            Encapsulation::Message => !matches!(tag, IaAddr | IaPrefix),
+            Encapsulation::IdentityAssociation => {
+                matches!(tag, IaAddr | IaPrefix | StatusCode | Unknown)
+            }
+            Encapsulation::Address => matches!(tag, StatusCode | Unknown),
+        }
+    }
+}
+
+impl Options {
+    /// Decodes the options area of a message.
+    ///
+    /// The repeatable options are collected in the order of appearance.
+    /// If a single-instance option occurs several times, the last instance is kept.
+    ///
+    /// # Errors
+    /// `io::Error` if an option is abrupted, invalid or is not allowed where it is found.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        Self::from_bytes_in(src, Encapsulation::Message)
+    }
+
+    /// Decodes the options area found in `encapsulation`.
+    ///
+    /// # Errors
+    /// `io::Error` if an option is abrupted, invalid or is not allowed in `encapsulation`.
+    pub(crate) fn from_bytes_in(src: &[u8], encapsulation: Encapsulation) -> io::Result<Self> {
+        let mut options = Options::default();
+        let mut cursor = io::Cursor::new(src);
+        while cursor.remaining() > 0 {
+            check_remaining!(cursor, SIZE_OPTION_PREFIX);
+            let code = cursor.get_u16_be();
+            let len = cursor.get_u16_be() as usize;
+            check_remaining!(cursor, len);
+            let value = &cursor.bytes()[..len];
+
+            let tag = OptionTag::from(code);
+            if !encapsulation.allows(tag) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Option is not allowed in the encapsulating option",
+                ));
+            }
+            match tag {
+                ClientId => options.client_id = Some(Duid::from_bytes(value)?),
+                ServerId => options.server_id = Some(Duid::from_bytes(value)?),
+                IaNa => options.ia_na.push(IdentityAssociation::from_bytes(value)?),
+                IaTa => options
+                    .ia_ta
+                    .push(TemporaryIdentityAssociation::from_bytes(value)?),
+                IaAddr => options.ia_addresses.push(IaAddress::from_bytes(value)?),
+                Oro => options.option_request = Some(Self::get_opt_vec_u16(value)?),
+                Preference => options.preference = Some(Self::get_opt_u8(value)?),
+                ElapsedTime => options.elapsed_time = Some(Self::get_opt_u16(value)?),
+                RelayMsg => options.relay_message = Some(Self::get_opt_vec(value)?),
+                StatusCode => options.status_code = Some(StatusCodeStruct::from_bytes(value)?),
+                RapidCommit => options.rapid_commit = Some(Self::get_opt_empty(value)?),
+                InterfaceId => options.interface_id = Some(Self::get_opt_vec(value)?),
+                DnsServers => options.dns_servers = Some(Self::get_opt_vec_ipv6(value)?),
+                DomainList => options.domain_list = Some(Self::get_opt_domain_list(value)?),
+                IaPd => options.ia_pd.push(IdentityAssociation::from_bytes(value)?),
+                IaPrefix => options.ia_prefixes.push(IaPrefixStruct::from_bytes(value)?),
+
+                Unknown => options.unknown.push((code, value.to_vec())),
+            }
+            cursor.advance(len);
+        }
+        Ok(options)
+    }
+
+    fn get_opt_empty(value: &[u8]) -> io::Result<()> {
+        check_length!(value.len(), 0);
+        Ok(())
+    }
+
+    fn get_opt_u8(value: &[u8]) -> io::Result<u8> {
+        check_length!(value.len(), mem::size_of::<u8>());
+        Ok(value[0])
+    }
+
+    fn get_opt_u16(value: &[u8]) -> io::Result<u16> {
+        check_length!(value.len(), mem::size_of::<u16>());
+        Ok(io::Cursor::new(value).get_u16_be())
+    }
+
+    fn get_opt_vec(value: &[u8]) -> io::Result<Vec<u8>> {
+        check_length!(value.len());
+        Ok(value.to_vec())
+    }
+
+    /// The option request option may be empty.
+    fn get_opt_vec_u16(value: &[u8]) -> io::Result<Vec<u16>> {
+        let element_size = mem::size_of::<u16>();
+        check_divisibility!(value.len(), element_size);
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / element_size);
+        while cursor.remaining() > 0 {
+            result.push(cursor.get_u16_be());
+        }
+        Ok(result)
+    }
+
+    fn get_opt_vec_ipv6(value: &[u8]) -> io::Result<Vec<Ipv6Addr>> {
+        check_length!(value.len());
+        check_divisibility!(value.len(), SIZE_IPV6);
+        let mut cursor = io::Cursor::new(value);
+        let mut result = Vec::with_capacity(value.len() / SIZE_IPV6);
+        while cursor.remaining() > 0 {
+            result.push(get_ipv6(&mut cursor));
+        }
+        Ok(result)
+    }
+
+    /// The names are encoded without compression (RFC 8415 §10).
+    fn get_opt_domain_list(value: &[u8]) -> io::Result<Vec<DomainName>> {
+        check_length!(value.len());
+        let mut result = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < value.len() {
+            let len = value[i] as usize;
+            i += 1 + len;
+            if len == 0 {
+                result.push(DomainName::from_wire(&value[start..i])?);
+                start = i;
+            }
+        }
+        if start != value.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Domain name is abrupted",
+            ));
+        }
+        Ok(result)
+    }
+}
+
+fn get_ipv6(cursor: &mut io::Cursor<&[u8]>) -> Ipv6Addr {
+    let mut octets = [0u8; SIZE_IPV6];
+    cursor.copy_to_slice(&mut octets);
+    Ipv6Addr::from(octets)
+}
@@ -0,0 +1,290 @@
+//! The main DHCPv6 message module.
+pub mod constants;
+pub mod message_type;
+pub mod options;
+
+mod deserializer;
+mod serializer;
+mod validator;
+
+use std::{fmt, net::Ipv6Addr};
+
+pub use self::{
+    message_type::MessageType,
+    options::{OptionTag, Options},
+};
+
+/// DHCPv6 client/server message.
+///
+/// [RFC 8415 §8](https://tools.ietf.org/html/rfc8415#section-8)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub message_type: MessageType,
+    /// Only the lower 24 bits are used.
+    pub transaction_id: u32,
+    pub options: Options,
+}
+
+/// DHCPv6 relay agent message (`RELAY-FORW` or `RELAY-REPL`).
+///
+/// The relayed message is carried in the `relay_message` option.
+///
+/// [RFC 8415 §9](https://tools.ietf.org/html/rfc8415#section-9)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelayMessage {
+    pub message_type: MessageType,
+    pub hop_count: u8,
+    pub link_address: Ipv6Addr,
+    pub peer_address: Ipv6Addr,
+    pub options: Options,
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "{}HEADER{}", "_".repeat(30), "_".repeat(39))?;
+        writeln!(f, "{:32} | {}", "Message type", self.message_type)?;
+        writeln!(f, "{:32} | {:06x}", "Transaction ID", self.transaction_id)?;
+        writeln!(f, "{}OPTIONS{}", "_".repeat(30), "_".repeat(38))?;
+        write!(f, "{}", self.options)
+    }
+}
+
+impl fmt::Display for RelayMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "{}HEADER{}", "_".repeat(30), "_".repeat(39))?;
+        writeln!(f, "{:32} | {}", "Message type", self.message_type)?;
+        writeln!(f, "{:32} | {}", "Hop count", self.hop_count)?;
+        writeln!(f, "{:32} | {}", "Link address", self.link_address)?;
+        writeln!(f, "{:32} | {}", "Peer address", self.peer_address)?;
+        writeln!(f, "{}OPTIONS{}", "_".repeat(30), "_".repeat(38))?;
+        write!(f, "{}", self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use v4::options::DomainName;
+
+    use self::options::{
+        Duid, IaAddress, IaPrefix, IdentityAssociation, StatusCode, STATUS_NO_ADDRS_AVAIL,
+    };
+
+    fn solicit() -> Message {
+        let options = Options {
+            client_id: Some(Duid::LinkLayer {
+                hardware_type: 1,
+                link_layer_address: vec![0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37],
+            }),
+            elapsed_time: Some(0),
+            option_request: Some(vec![
+                OptionTag::DnsServers as u16,
+                OptionTag::DomainList as u16,
+            ]),
+            rapid_commit: Some(()),
+            ia_na: vec![IdentityAssociation {
+                iaid: 1,
+                t1: 0,
+                t2: 0,
+                options: Options::default(),
+            }],
+            ia_pd: vec![IdentityAssociation {
+                iaid: 2,
+                t1: 0,
+                t2: 0,
+                options: Options::default(),
+            }],
+            ..Options::default()
+        };
+        Message {
+            message_type: MessageType::Solicit,
+            transaction_id: 0x00ab_cdef,
+            options,
+        }
+    }
+
+    fn reply() -> Message {
+        let mut message = solicit();
+        message.message_type = MessageType::Reply;
+        message.options.option_request = None;
+        message.options.server_id = Some(Duid::LinkLayerTime {
+            hardware_type: 1,
+            time: 0x2345_6789,
+            link_layer_address: vec![0x00, 0x0c, 0x29, 0x13, 0x0e, 0x38],
+        });
+        message.options.ia_na[0].t1 = 1800;
+        message.options.ia_na[0].t2 = 2880;
+        message.options.ia_na[0]
+            .options
+            .ia_addresses
+            .push(IaAddress {
+                address: "2001:db8::10".parse().unwrap(),
+                preferred_lifetime: 3600,
+                valid_lifetime: 7200,
+                options: Options::default(),
+            });
+        message.options.ia_pd[0].t1 = 1800;
+        message.options.ia_pd[0].t2 = 2880;
+        message.options.ia_pd[0].options.ia_prefixes.push(IaPrefix {
+            preferred_lifetime: 3600,
+            valid_lifetime: 7200,
+            prefix_length: 56,
+            prefix: "2001:db8:1:100::".parse().unwrap(),
+            options: Options::default(),
+        });
+        message.options.dns_servers = Some(vec!["2001:db8::53".parse().unwrap()]);
+        message.options.domain_list = Some(vec![
+            "example.com.".parse::<DomainName>().unwrap(),
+            "lab.example.com.".parse::<DomainName>().unwrap(),
+        ]);
+        message.options.unknown = vec![(82, vec![0, 0, 14, 16]), (65000, vec![])];
+        message
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let mut buffer = vec![0u8; 1024];
+        for message in [solicit(), reply()].iter() {
+            assert!(message.validate().is_ok());
+            let amount = message.to_bytes(&mut buffer).unwrap();
+            let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+            assert_eq!(&decoded, message);
+        }
+        assert_eq!(&buffer[..4], &[7, 0xab, 0xcd, 0xef]);
+    }
+
+    #[test]
+    fn status_code_is_encapsulated() {
+        let mut message = reply();
+        message.options.ia_na[0].options.ia_addresses.clear();
+        message.options.ia_na[0].options.status_code =
+            Some(StatusCode::new(STATUS_NO_ADDRS_AVAIL, "No addresses"));
+
+        let mut buffer = vec![0u8; 1024];
+        let amount = message.to_bytes(&mut buffer).unwrap();
+        let decoded = Message::from_bytes(&buffer[..amount]).unwrap();
+        let status = decoded.options.ia_na[0]
+            .options
+            .status_code
+            .clone()
+            .unwrap();
+        assert_eq!(status.code, STATUS_NO_ADDRS_AVAIL);
+        assert!(!status.is_success());
+        assert_eq!(status.message, "No addresses");
+    }
+
+    #[test]
+    fn relay_forward_round_trip() {
+        let mut buffer = vec![0u8; 1024];
+        let amount = solicit().to_bytes(&mut buffer).unwrap();
+        let options = Options {
+            relay_message: Some(buffer[..amount].to_vec()),
+            interface_id: Some(b"eth0".to_vec()),
+            ..Options::default()
+        };
+        let relay = RelayMessage {
+            message_type: MessageType::RelayForw,
+            hop_count: 0,
+            link_address: "2001:db8::1".parse().unwrap(),
+            peer_address: "fe80::20c:29ff:fe13:e37".parse().unwrap(),
+            options,
+        };
+        assert!(relay.validate().is_ok());
+
+        let amount = relay.to_bytes(&mut buffer).unwrap();
+        let decoded = RelayMessage::from_bytes(&buffer[..amount]).unwrap();
+        assert_eq!(decoded, relay);
+        assert!(Message::from_bytes(&buffer[..amount]).is_err());
+
+        let inner = decoded.options.relay_message.unwrap();
+        assert_eq!(Message::from_bytes(&inner).unwrap(), solicit());
+        assert!(RelayMessage::from_bytes(&inner).is_err());
+    }
+
+    #[test]
+    fn validation() {
+        let mut message = solicit();
+        message.options.server_id = reply().options.server_id;
+        assert!(message.validate().is_err());
+
+        let mut message = solicit();
+        message.options.client_id = None;
+        assert!(message.validate().is_err());
+
+        let mut message = solicit();
+        message.options.preference = Some(255);
+        assert!(message.validate().is_err());
+
+        let mut message = reply();
+        message.options.server_id = None;
+        assert!(message.validate().is_err());
+
+        let mut message = reply();
+        message.options.ia_na[0].t1 = 3000;
+        assert!(message.validate().is_err());
+
+        let mut message = reply();
+        message.options.ia_pd[0].options.ia_prefixes[0].preferred_lifetime = 10000;
+        assert!(message.validate().is_err());
+
+        let mut message = solicit();
+        message.message_type = MessageType::InformationRequest;
+        message.options.rapid_commit = None;
+        assert!(message.validate().is_err());
+        message.options.ia_na.clear();
+        message.options.ia_pd.clear();
+        assert!(message.validate().is_ok());
+
+        let mut message = solicit();
+        message.transaction_id = 0x0100_0000;
+        assert!(message.validate().is_err());
+    }
+
+    #[test]
+    fn misplaced_options_are_rejected() {
+        // 300 IA_TA options, each encapsulating the next one
+        let mut option = Vec::new();
+        for _ in 0..300 {
+            let mut value = vec![0, 0, 0, 1];
+            value.extend_from_slice(&option);
+            let len = value.len() as u16;
+            option = vec![0, OptionTag::IaTa as u8, (len >> 8) as u8, len as u8];
+            option.extend_from_slice(&value);
+        }
+        let mut src = vec![1, 0, 0, 1];
+        src.extend_from_slice(&option);
+        assert!(Message::from_bytes(&src).is_err());
+
+        // an IA Address outside of an identity association
+        let mut message = reply();
+        let address = message.options.ia_na[0].options.ia_addresses[0].clone();
+        message.options.ia_addresses.push(address);
+        let mut buffer = vec![0u8; 1024];
+        let amount = message.to_bytes(&mut buffer).unwrap();
+        assert!(Message::from_bytes(&buffer[..amount]).is_err());
+
+        // an identity association inside of an IA Address
+        let mut message = reply();
+        let ia_na = message.options.ia_na[0].clone();
+        message.options.ia_na[0].options.ia_addresses[0]
+            .options
+            .ia_na
+            .push(ia_na);
+        let amount = message.to_bytes(&mut buffer).unwrap();
+        assert!(Message::from_bytes(&buffer[..amount]).is_err());
+    }
+
+    #[test]
+    fn invalid_lengths_are_rejected() {
+        // the elapsed time option is one octet long
+        let src = [1, 0, 0, 1, 0, 8, 0, 1, 0];
+        assert!(Message::from_bytes(&src).is_err());
+        // the option length exceeds the packet
+        let src = [1, 0, 0, 1, 0, 8, 0, 4, 0, 0];
+        assert!(Message::from_bytes(&src).is_err());
+        assert!(Message::from_bytes(&[1, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! DHCPv6 unique identifier module.
+
+use std::{fmt, io, mem};
+
+use bytes::{Buf, BufMut};
+
+/// Link-layer address plus time.
+pub const DUID_TYPE_LLT: u16 = 1;
+/// Vendor-assigned unique ID based on the enterprise number.
+pub const DUID_TYPE_EN: u16 = 2;
+/// Link-layer address.
+pub const DUID_TYPE_LL: u16 = 3;
+/// Universally unique identifier (RFC 6355).
+pub const DUID_TYPE_UUID: u16 = 4;
+
+/// The DUID content size limit excluding the type code.
+const SIZE_DUID_MAX: usize = 128;
+/// The UUID size in bytes.
+const SIZE_UUID: usize = 16;
+
+/// DHCP unique identifier used in the client and server identifier options.
+///
+/// The hardware types are the 16-bit ARP hardware types (see `HardwareType`).
+/// The DUID-LLT time is the number of seconds since midnight (UTC), January 1, 2000.
+///
+/// [RFC 8415 §11](https://tools.ietf.org/html/rfc8415#section-11)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Duid {
+    LinkLayerTime {
+        hardware_type: u16,
+        time: u32,
+        link_layer_address: Vec<u8>,
+    },
+    Enterprise {
+        enterprise_number: u32,
+        identifier: Vec<u8>,
+    },
+    LinkLayer {
+        hardware_type: u16,
+        link_layer_address: Vec<u8>,
+    },
+    Uuid([u8; SIZE_UUID]),
+    /// Kept as is, since DUIDs are compared as opaque values.
+    Unknown {
+        duid_type: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl Duid {
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is abrupted or too long.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() <= mem::size_of::<u16>() || src.len() > mem::size_of::<u16>() + SIZE_DUID_MAX {
+            return Err(Self::error("DUID length is invalid"));
+        }
+        let mut cursor = io::Cursor::new(src);
+        let duid_type = cursor.get_u16_be();
+        Ok(match duid_type {
+            DUID_TYPE_LLT => {
+                if cursor.remaining() < mem::size_of::<u16>() + mem::size_of::<u32>() {
+                    return Err(Self::error("DUID-LLT is abrupted"));
+                }
+                Duid::LinkLayerTime {
+                    hardware_type: cursor.get_u16_be(),
+                    time: cursor.get_u32_be(),
+                    link_layer_address: cursor.bytes().to_vec(),
+                }
+            }
+            DUID_TYPE_EN => {
+                if cursor.remaining() < mem::size_of::<u32>() {
+                    return Err(Self::error("DUID-EN is abrupted"));
+                }
+                Duid::Enterprise {
+                    enterprise_number: cursor.get_u32_be(),
+                    identifier: cursor.bytes().to_vec(),
+                }
+            }
+            DUID_TYPE_LL => {
+                if cursor.remaining() < mem::size_of::<u16>() {
+                    return Err(Self::error("DUID-LL is abrupted"));
+                }
+                Duid::LinkLayer {
+                    hardware_type: cursor.get_u16_be(),
+                    link_layer_address: cursor.bytes().to_vec(),
+                }
+            }
+            DUID_TYPE_UUID => {
+                if cursor.remaining() != SIZE_UUID {
+                    return Err(Self::error("DUID-UUID length is invalid"));
+                }
+                let mut uuid = [0u8; SIZE_UUID];
+                uuid.copy_from_slice(cursor.bytes());
+                Duid::Uuid(uuid)
+            }
+            duid_type => Duid::Unknown {
+                duid_type,
+                data: cursor.bytes().to_vec(),
+            },
+        })
+    }
+
+    /// Encodes the option value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(mem::size_of::<u16>() + SIZE_DUID_MAX);
+        match self {
+            Duid::LinkLayerTime {
+                hardware_type,
+                time,
+                link_layer_address,
+            } => {
+                dst.put_u16_be(DUID_TYPE_LLT);
+                dst.put_u16_be(*hardware_type);
+                dst.put_u32_be(*time);
+                dst.put(link_layer_address);
+            }
+            Duid::Enterprise {
+                enterprise_number,
+                identifier,
+            } => {
+                dst.put_u16_be(DUID_TYPE_EN);
+                dst.put_u32_be(*enterprise_number);
+                dst.put(identifier);
+            }
+            Duid::LinkLayer {
+                hardware_type,
+                link_layer_address,
+            } => {
+                dst.put_u16_be(DUID_TYPE_LL);
+                dst.put_u16_be(*hardware_type);
+                dst.put(link_layer_address);
+            }
+            Duid::Uuid(uuid) => {
+                dst.put_u16_be(DUID_TYPE_UUID);
+                dst.put(&uuid[..]);
+            }
+            Duid::Unknown { duid_type, data } => {
+                dst.put_u16_be(*duid_type);
+                dst.put(data);
+            }
+        }
+        dst
+    }
+
+    fn error(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+}
+
+/// Prints the bytes in hex separated with colons.
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+impl fmt::Display for Duid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Duid::LinkLayerTime {
+                hardware_type,
+                time,
+                link_layer_address,
+            } => write!(
+                f,
+                "DUID-LLT type {}, time {}, address {}",
+                hardware_type,
+                time,
+                hex(link_layer_address),
+            ),
+            Duid::Enterprise {
+                enterprise_number,
+                identifier,
+            } => write!(
+                f,
+                "DUID-EN enterprise {}, identifier {}",
+                enterprise_number,
+                hex(identifier),
+            ),
+            Duid::LinkLayer {
+                hardware_type,
+                link_layer_address,
+            } => write!(
+                f,
+                "DUID-LL type {}, address {}",
+                hardware_type,
+                hex(link_layer_address),
+            ),
+            Duid::Uuid(uuid) => write!(f, "DUID-UUID {}", hex(uuid)),
+            Duid::Unknown { duid_type, data } => {
+                write!(f, "DUID type {}, data {}", duid_type, hex(data))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duid_round_trip() {
+        let duids = [
+            Duid::LinkLayerTime {
+                hardware_type: 1,
+                time: 0x2345_6789,
+                link_layer_address: vec![0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37],
+            },
+            Duid::Enterprise {
+                enterprise_number: 9,
+                identifier: vec![0x0c, 0xc0, 0x84, 0xd3, 0x03, 0x00, 0x09, 0x12],
+            },
+            Duid::LinkLayer {
+                hardware_type: 1,
+                link_layer_address: vec![0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37],
+            },
+            Duid::Uuid([0x42; SIZE_UUID]),
+            Duid::Unknown {
+                duid_type: 0xffff,
+                data: vec![1, 2, 3],
+            },
+        ];
+        for duid in duids.iter() {
+            assert_eq!(&Duid::from_bytes(&duid.to_bytes()).unwrap(), duid);
+        }
+        assert_eq!(&duids[2].to_bytes()[..4], &[0, 3, 0, 1]);
+
+        assert!(Duid::from_bytes(&[0, 1, 0, 1, 0]).is_err());
+        assert!(Duid::from_bytes(&[0, 4, 1, 2]).is_err());
+        assert!(Duid::from_bytes(&[0, 3]).is_err());
+        assert!(Duid::from_bytes(&[0u8; 131]).is_err());
+    }
+}
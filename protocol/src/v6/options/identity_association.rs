@@ -0,0 +1,237 @@
+//! DHCPv6 identity association module.
+
+use std::{fmt, io, mem, net::Ipv6Addr};
+
+use bytes::{Buf, BufMut};
+
+use v6::deserializer::Encapsulation;
+
+use super::Options;
+
+/// The IAID, T1 and T2 fields of IA_NA and IA_PD.
+const SIZE_IA: usize = mem::size_of::<u32>() * 3;
+/// The IAID field of IA_TA.
+const SIZE_IA_TA: usize = mem::size_of::<u32>();
+/// The address and the lifetimes of IA Address.
+const SIZE_IA_ADDRESS: usize = 16 + mem::size_of::<u32>() * 2;
+/// The lifetimes, the prefix length and the prefix of IA Prefix.
+const SIZE_IA_PREFIX: usize = mem::size_of::<u32>() * 2 + mem::size_of::<u8>() + 16;
+
+/// The maximal IPv6 prefix length.
+pub const PREFIX_LENGTH_MAX: u8 = 128;
+
+/// Identity Association for Non-temporary Addresses or for Prefix Delegation.
+///
+/// The encapsulated options carry the `ia_addresses` (IA_NA) or the `ia_prefixes` (IA_PD)
+/// and the `status_code`.
+///
+/// [RFC 8415 §21.4](https://tools.ietf.org/html/rfc8415#section-21.4)
+/// [RFC 8415 §21.21](https://tools.ietf.org/html/rfc8415#section-21.21)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdentityAssociation {
+    pub iaid: u32,
+    pub t1: u32,
+    pub t2: u32,
+    pub options: Options,
+}
+
+/// Identity Association for Temporary Addresses.
+///
+/// [RFC 8415 §21.5](https://tools.ietf.org/html/rfc8415#section-21.5)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemporaryIdentityAssociation {
+    pub iaid: u32,
+    pub options: Options,
+}
+
+/// IA Address option.
+///
+/// [RFC 8415 §21.6](https://tools.ietf.org/html/rfc8415#section-21.6)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IaAddress {
+    pub address: Ipv6Addr,
+    pub preferred_lifetime: u32,
+    pub valid_lifetime: u32,
+    pub options: Options,
+}
+
+/// IA Prefix option.
+///
+/// [RFC 8415 §21.22](https://tools.ietf.org/html/rfc8415#section-21.22)
+#[derive(Debug, Clone, PartialEq)]
+pub struct IaPrefix {
+    pub preferred_lifetime: u32,
+    pub valid_lifetime: u32,
+    pub prefix_length: u8,
+    pub prefix: Ipv6Addr,
+    pub options: Options,
+}
+
+/// Checks if the value is long enough for the fixed fields.
+fn check_size(src: &[u8], size: usize) -> io::Result<()> {
+    if src.len() < size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Identity association option is abrupted",
+        ));
+    }
+    Ok(())
+}
+
+fn get_ipv6(cursor: &mut io::Cursor<&[u8]>) -> Ipv6Addr {
+    let mut octets = [0u8; 16];
+    cursor.copy_to_slice(&mut octets);
+    Ipv6Addr::from(octets)
+}
+
+impl IdentityAssociation {
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value or an encapsulated option is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        check_size(src, SIZE_IA)?;
+        let mut cursor = io::Cursor::new(src);
+        Ok(IdentityAssociation {
+            iaid: cursor.get_u32_be(),
+            t1: cursor.get_u32_be(),
+            t2: cursor.get_u32_be(),
+            options: Options::from_bytes_in(&src[SIZE_IA..], Encapsulation::IdentityAssociation)?,
+        })
+    }
+
+    /// Encodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if an encapsulated option is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::with_capacity(SIZE_IA);
+        dst.put_u32_be(self.iaid);
+        dst.put_u32_be(self.t1);
+        dst.put_u32_be(self.t2);
+        dst.put(self.options.to_bytes()?);
+        Ok(dst)
+    }
+}
+
+impl TemporaryIdentityAssociation {
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value or an encapsulated option is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        check_size(src, SIZE_IA_TA)?;
+        Ok(TemporaryIdentityAssociation {
+            iaid: io::Cursor::new(src).get_u32_be(),
+            options: Options::from_bytes_in(
+                &src[SIZE_IA_TA..],
+                Encapsulation::IdentityAssociation,
+            )?,
+        })
+    }
+
+    /// Encodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if an encapsulated option is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::with_capacity(SIZE_IA_TA);
+        dst.put_u32_be(self.iaid);
+        dst.put(self.options.to_bytes()?);
+        Ok(dst)
+    }
+}
+
+impl IaAddress {
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value or an encapsulated option is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        check_size(src, SIZE_IA_ADDRESS)?;
+        let mut cursor = io::Cursor::new(src);
+        Ok(IaAddress {
+            address: get_ipv6(&mut cursor),
+            preferred_lifetime: cursor.get_u32_be(),
+            valid_lifetime: cursor.get_u32_be(),
+            options: Options::from_bytes_in(&src[SIZE_IA_ADDRESS..], Encapsulation::Address)?,
+        })
+    }
+
+    /// Encodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if an encapsulated option is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::with_capacity(SIZE_IA_ADDRESS);
+        dst.put(&self.address.octets()[..]);
+        dst.put_u32_be(self.preferred_lifetime);
+        dst.put_u32_be(self.valid_lifetime);
+        dst.put(self.options.to_bytes()?);
+        Ok(dst)
+    }
+}
+
+impl IaPrefix {
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value or an encapsulated option is invalid.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        check_size(src, SIZE_IA_PREFIX)?;
+        let mut cursor = io::Cursor::new(src);
+        Ok(IaPrefix {
+            preferred_lifetime: cursor.get_u32_be(),
+            valid_lifetime: cursor.get_u32_be(),
+            prefix_length: cursor.get_u8(),
+            prefix: get_ipv6(&mut cursor),
+            options: Options::from_bytes_in(&src[SIZE_IA_PREFIX..], Encapsulation::Address)?,
+        })
+    }
+
+    /// Encodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if an encapsulated option is too long.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut dst = Vec::with_capacity(SIZE_IA_PREFIX);
+        dst.put_u32_be(self.preferred_lifetime);
+        dst.put_u32_be(self.valid_lifetime);
+        dst.put_u8(self.prefix_length);
+        dst.put(&self.prefix.octets()[..]);
+        dst.put(self.options.to_bytes()?);
+        Ok(dst)
+    }
+}
+
+impl fmt::Display for IdentityAssociation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IAID {}, T1 {}, T2 {}", self.iaid, self.t1, self.t2)
+    }
+}
+
+impl fmt::Display for TemporaryIdentityAssociation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IAID {}", self.iaid)
+    }
+}
+
+impl fmt::Display for IaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (preferred {}, valid {})",
+            self.address, self.preferred_lifetime, self.valid_lifetime,
+        )
+    }
+}
+
+impl fmt::Display for IaPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} (preferred {}, valid {})",
+            self.prefix, self.prefix_length, self.preferred_lifetime, self.valid_lifetime,
+        )
+    }
+}
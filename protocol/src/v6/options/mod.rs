@@ -0,0 +1,150 @@
+//! DHCPv6 options module.
+
+mod duid;
+mod identity_association;
+mod option_tag;
+mod status_code;
+
+pub use self::{
+    duid::{Duid, DUID_TYPE_EN, DUID_TYPE_LL, DUID_TYPE_LLT, DUID_TYPE_UUID},
+    identity_association::{
+        IaAddress, IaPrefix, IdentityAssociation, TemporaryIdentityAssociation, PREFIX_LENGTH_MAX,
+    },
+    option_tag::OptionTag,
+    status_code::{
+        StatusCode, STATUS_NOT_ON_LINK, STATUS_NO_ADDRS_AVAIL, STATUS_NO_BINDING,
+        STATUS_NO_PREFIX_AVAIL, STATUS_SUCCESS, STATUS_UNSPEC_FAIL, STATUS_USE_MULTICAST,
+    },
+};
+
+use std::{fmt, net::Ipv6Addr};
+
+use v4::options::DomainName;
+
+/// DHCPv6 options.
+///
+/// The same structure is used for the options of a message and for the ones
+/// encapsulated in the identity association, IA address and IA prefix options.
+/// The options which may occur several times are kept in vectors.
+///
+/// [RFC 8415](https://tools.ietf.org/html/rfc8415)
+/// [RFC 3646](https://tools.ietf.org/html/rfc3646)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    /*
+    RFC 8415
+    */
+    pub client_id: Option<Duid>,
+    pub server_id: Option<Duid>,
+    pub ia_na: Vec<IdentityAssociation>,
+    pub ia_ta: Vec<TemporaryIdentityAssociation>,
+    pub ia_addresses: Vec<IaAddress>,
+    // The option request option containing the requested option codes.
+    pub option_request: Option<Vec<u16>>,
+    pub preference: Option<u8>,
+    // In hundredths of a second.
+    pub elapsed_time: Option<u16>,
+    // The encapsulated message of a relay agent message.
+    pub relay_message: Option<Vec<u8>>,
+    pub status_code: Option<StatusCode>,
+    pub rapid_commit: Option<()>,
+    pub interface_id: Option<Vec<u8>>,
+    pub ia_pd: Vec<IdentityAssociation>,
+    pub ia_prefixes: Vec<IaPrefix>,
+
+    /*
+    RFC 3646 (DNS Configuration options for DHCPv6)
+    */
+    pub dns_servers: Option<Vec<Ipv6Addr>>,
+    pub domain_list: Option<Vec<DomainName>>,
+
+    /*
+    Unknown and unsupported options
+    */
+    // Kept as raw `(code, value)` pairs in the order of appearance and written back verbatim.
+    pub unknown: Vec<(u16, Vec<u8>)>,
+}
+
+/// Prints an option with `Display`.
+macro_rules! dsp_opt (
+    ($f:expr, $indent:expr, $tag:expr, $option:expr) => (
+        let name = stringify!($option).split(".").collect::<Vec<&str>>().last().cloned().unwrap();
+        if let Some(ref v) = $option {
+            writeln!($f, "{}[{:03}] {:027}| {}", $indent, $tag as u16, name, v)?;
+        }
+    );
+);
+
+/// Prints an option with `Debug`.
+macro_rules! dbg_opt (
+    ($f:expr, $indent:expr, $tag:expr, $option:expr) => (
+        let name = stringify!($option).split(".").collect::<Vec<&str>>().last().cloned().unwrap();
+        if let Some(ref v) = $option {
+            writeln!($f, "{}[{:03}] {:027}| {:?}", $indent, $tag as u16, name, v)?;
+        }
+    );
+);
+
+/// Prints a repeated option with `Display` followed by its encapsulated options.
+macro_rules! dsp_vec_opt (
+    ($f:expr, $indent:expr, $tag:expr, $option:expr) => (
+        let name = stringify!($option).split(".").collect::<Vec<&str>>().last().cloned().unwrap();
+        for v in $option.iter() {
+            writeln!($f, "{}[{:03}] {:027}| {}", $indent, $tag as u16, name, v)?;
+            v.options.fmt_indented($f, &format!("{}    ", $indent))?;
+        }
+    );
+);
+
+impl Options {
+    fn fmt_indented(&self, f: &mut fmt::Formatter, indent: &str) -> fmt::Result {
+        use self::OptionTag::*;
+
+        dsp_opt!(f, indent, ClientId, self.client_id);
+        dsp_opt!(f, indent, ServerId, self.server_id);
+        dsp_vec_opt!(f, indent, IaNa, self.ia_na);
+        dsp_vec_opt!(f, indent, IaTa, self.ia_ta);
+        dsp_vec_opt!(f, indent, IaAddr, self.ia_addresses);
+        dbg_opt!(f, indent, Oro, self.option_request);
+        dsp_opt!(f, indent, Preference, self.preference);
+        dsp_opt!(f, indent, ElapsedTime, self.elapsed_time);
+        if let Some(ref relay_message) = self.relay_message {
+            writeln!(
+                f,
+                "{}[{:03}] {:027}| {} bytes",
+                indent,
+                RelayMsg as u16,
+                "relay_message",
+                relay_message.len()
+            )?;
+        }
+        dsp_opt!(f, indent, StatusCode, self.status_code);
+        dbg_opt!(f, indent, RapidCommit, self.rapid_commit);
+        dbg_opt!(f, indent, InterfaceId, self.interface_id);
+        dbg_opt!(f, indent, DnsServers, self.dns_servers);
+        if let Some(ref domain_list) = self.domain_list {
+            let names = domain_list
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            writeln!(
+                f,
+                "{}[{:03}] {:027}| {}",
+                indent, DomainList as u16, "domain_list", names
+            )?;
+        }
+        dsp_vec_opt!(f, indent, IaPd, self.ia_pd);
+        dsp_vec_opt!(f, indent, IaPrefix, self.ia_prefixes);
+        for (code, value) in self.unknown.iter() {
+            writeln!(f, "{}[{:03}] {:027}| {:?}", indent, code, "unknown", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Options {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_indented(f, "")
+    }
+}
@@ -0,0 +1,59 @@
+//! DHCPv6 option codes module.
+
+/// DHCPv6 options codes.
+///
+/// [RFC 8415 §21](https://tools.ietf.org/html/rfc8415#section-21)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionTag {
+    Unknown = -1,
+
+    /*
+    RFC 8415
+    */
+    ClientId = 1,
+    ServerId = 2,
+    IaNa = 3,
+    IaTa = 4,
+    IaAddr = 5,
+    Oro = 6,
+    Preference = 7,
+    ElapsedTime = 8,
+    RelayMsg = 9,
+    StatusCode = 13,
+    RapidCommit = 14,
+    InterfaceId = 18,
+    IaPd = 25,
+    IaPrefix = 26,
+
+    /*
+    RFC 3646 (DNS Configuration options for DHCPv6)
+    */
+    DnsServers = 23,
+    DomainList = 24,
+}
+
+impl From<u16> for OptionTag {
+    fn from(value: u16) -> Self {
+        use self::OptionTag::*;
+        match value {
+            1 => ClientId,
+            2 => ServerId,
+            3 => IaNa,
+            4 => IaTa,
+            5 => IaAddr,
+            6 => Oro,
+            7 => Preference,
+            8 => ElapsedTime,
+            9 => RelayMsg,
+            13 => StatusCode,
+            14 => RapidCommit,
+            18 => InterfaceId,
+            23 => DnsServers,
+            24 => DomainList,
+            25 => IaPd,
+            26 => IaPrefix,
+
+            _ => Unknown,
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! DHCPv6 status code module.
+
+use std::{fmt, io, mem};
+
+use bytes::{Buf, BufMut};
+
+/// Success.
+pub const STATUS_SUCCESS: u16 = 0;
+/// Failure, reason unspecified.
+pub const STATUS_UNSPEC_FAIL: u16 = 1;
+/// The server has no addresses available to assign to the IA(s).
+pub const STATUS_NO_ADDRS_AVAIL: u16 = 2;
+/// Client record (binding) unavailable.
+pub const STATUS_NO_BINDING: u16 = 3;
+/// The prefix for the address is not appropriate for the link.
+pub const STATUS_NOT_ON_LINK: u16 = 4;
+/// Forces the client to send messages to the server using the multicast address.
+pub const STATUS_USE_MULTICAST: u16 = 5;
+/// The server has no prefixes available to assign to the IA_PD(s).
+pub const STATUS_NO_PREFIX_AVAIL: u16 = 6;
+
+/// DHCPv6 status code option.
+///
+/// [RFC 8415 §21.13](https://tools.ietf.org/html/rfc8415#section-21.13)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusCode {
+    pub code: u16,
+    /// The UTF-8 message for the end user, which may be empty.
+    pub message: String,
+}
+
+impl StatusCode {
+    /// Creates the option with the status message.
+    pub fn new(code: u16, message: &str) -> Self {
+        StatusCode {
+            code,
+            message: message.to_owned(),
+        }
+    }
+
+    /// Checks if the status is `Success`.
+    pub fn is_success(&self) -> bool {
+        self.code == STATUS_SUCCESS
+    }
+
+    /// Decodes the option value.
+    ///
+    /// # Errors
+    /// `io::Error` if the value is abrupted.
+    pub fn from_bytes(src: &[u8]) -> io::Result<Self> {
+        if src.len() < mem::size_of::<u16>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Status code is abrupted",
+            ));
+        }
+        let mut cursor = io::Cursor::new(src);
+        Ok(StatusCode {
+            code: cursor.get_u16_be(),
+            message: String::from_utf8_lossy(cursor.bytes()).to_string(),
+        })
+    }
+
+    /// Encodes the option value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(mem::size_of::<u16>() + self.message.len());
+        dst.put_u16_be(self.code);
+        dst.put(self.message.as_bytes());
+        dst
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.code {
+            STATUS_SUCCESS => "Success",
+            STATUS_UNSPEC_FAIL => "UnspecFail",
+            STATUS_NO_ADDRS_AVAIL => "NoAddrsAvail",
+            STATUS_NO_BINDING => "NoBinding",
+            STATUS_NOT_ON_LINK => "NotOnLink",
+            STATUS_USE_MULTICAST => "UseMulticast",
+            STATUS_NO_PREFIX_AVAIL => "NoPrefixAvail",
+            _ => "Unknown",
+        };
+        write!(f, "{} ({}): {}", name, self.code, self.message)
+    }
+}
@@ -0,0 +1,157 @@
+//! DHCPv6 message serialization module.
+
+use std::io;
+
+use bytes::{Buf, BufMut};
+
+use super::{
+    constants::*,
+    options::{OptionTag, Options},
+    Message, RelayMessage,
+};
+
+/// Checks if there is enough space in buffer to put a value.
+macro_rules! check_remaining(
+    ($cursor:expr, $distance:expr) => (
+        if $cursor.remaining() < $distance {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "No more space left"));
+        }
+    )
+);
+
+impl Message {
+    /// DHCPv6 client/server message serialization.
+    ///
+    /// Only the lower 24 bits of the transaction ID are written.
+    ///
+    /// # Errors
+    /// `io::Error` if the buffer is too small or an option is too long.
+    pub fn to_bytes(&self, dst: &mut [u8]) -> io::Result<usize> {
+        let options = self.options.to_bytes()?;
+
+        let mut cursor = io::Cursor::new(dst);
+        check_remaining!(cursor, SIZE_HEADER + options.len());
+        cursor.put_u8(self.message_type as u8);
+        cursor.put_u8((self.transaction_id >> 16) as u8);
+        cursor.put_u16_be(self.transaction_id as u16);
+        cursor.put(options);
+        Ok(cursor.position() as usize)
+    }
+}
+
+impl RelayMessage {
+    /// DHCPv6 relay agent message serialization.
+    ///
+    /// # Errors
+    /// `io::Error` if the buffer is too small or an option is too long.
+    pub fn to_bytes(&self, dst: &mut [u8]) -> io::Result<usize> {
+        let options = self.options.to_bytes()?;
+
+        let mut cursor = io::Cursor::new(dst);
+        check_remaining!(cursor, SIZE_HEADER_RELAY + options.len());
+        cursor.put_u8(self.message_type as u8);
+        cursor.put_u8(self.hop_count);
+        cursor.put(&self.link_address.octets()[..]);
+        cursor.put(&self.peer_address.octets()[..]);
+        cursor.put(options);
+        Ok(cursor.position() as usize)
+    }
+}
+
+impl Options {
+    /// Encodes the options area of a message or an encapsulating option.
+    ///
+    /// The known options are written in the order of their codes
+    /// and followed by the unknown ones in the order they were received.
+    ///
+    /// # Errors
+    /// `io::Error` if an option value does not fit the 16-bit length field.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        use self::OptionTag::*;
+
+        let mut dst = Vec::new();
+        if let Some(ref value) = self.client_id {
+            Self::put_opt(&mut dst, ClientId as u16, &value.to_bytes())?;
+        }
+        if let Some(ref value) = self.server_id {
+            Self::put_opt(&mut dst, ServerId as u16, &value.to_bytes())?;
+        }
+        for value in self.ia_na.iter() {
+            Self::put_opt(&mut dst, IaNa as u16, &value.to_bytes()?)?;
+        }
+        for value in self.ia_ta.iter() {
+            Self::put_opt(&mut dst, IaTa as u16, &value.to_bytes()?)?;
+        }
+        for value in self.ia_addresses.iter() {
+            Self::put_opt(&mut dst, IaAddr as u16, &value.to_bytes()?)?;
+        }
+        if let Some(ref value) = self.option_request {
+            let mut bytes = Vec::with_capacity(value.len() * 2);
+            for code in value.iter() {
+                bytes.put_u16_be(*code);
+            }
+            Self::put_opt(&mut dst, Oro as u16, &bytes)?;
+        }
+        if let Some(value) = self.preference {
+            Self::put_opt(&mut dst, Preference as u16, &[value])?;
+        }
+        if let Some(value) = self.elapsed_time {
+            let mut bytes = Vec::with_capacity(2);
+            bytes.put_u16_be(value);
+            Self::put_opt(&mut dst, ElapsedTime as u16, &bytes)?;
+        }
+        if let Some(ref value) = self.relay_message {
+            Self::put_opt(&mut dst, RelayMsg as u16, value)?;
+        }
+        if let Some(ref value) = self.status_code {
+            Self::put_opt(&mut dst, StatusCode as u16, &value.to_bytes())?;
+        }
+        if self.rapid_commit.is_some() {
+            Self::put_opt(&mut dst, RapidCommit as u16, &[])?;
+        }
+        if let Some(ref value) = self.interface_id {
+            Self::put_opt(&mut dst, InterfaceId as u16, value)?;
+        }
+        if let Some(ref value) = self.dns_servers {
+            let mut bytes = Vec::with_capacity(value.len() * 16);
+            for address in value.iter() {
+                bytes.put(&address.octets()[..]);
+            }
+            Self::put_opt(&mut dst, DnsServers as u16, &bytes)?;
+        }
+        if let Some(ref value) = self.domain_list {
+            let mut bytes = Vec::new();
+            for name in value.iter() {
+                bytes.put(name.to_wire());
+                // the names in the list are always fully qualified
+                if !name.is_fully_qualified() {
+                    bytes.put_u8(0);
+                }
+            }
+            Self::put_opt(&mut dst, DomainList as u16, &bytes)?;
+        }
+        for value in self.ia_pd.iter() {
+            Self::put_opt(&mut dst, IaPd as u16, &value.to_bytes()?)?;
+        }
+        for value in self.ia_prefixes.iter() {
+            Self::put_opt(&mut dst, IaPrefix as u16, &value.to_bytes()?)?;
+        }
+        for (code, value) in self.unknown.iter() {
+            Self::put_opt(&mut dst, *code, value)?;
+        }
+        Ok(dst)
+    }
+
+    fn put_opt(dst: &mut Vec<u8>, code: u16, value: &[u8]) -> io::Result<()> {
+        if value.len() > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Option is too long",
+            ));
+        }
+        dst.put_u16_be(code);
+        dst.put_u16_be(value.len() as u16);
+        dst.put(value);
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! DHCPv6 message validation module.
+
+use super::{
+    constants::{HOP_COUNT_LIMIT, TRANSACTION_ID_MAX},
+    options::{Options, PREFIX_LENGTH_MAX},
+    Message, MessageType, RelayMessage,
+};
+
+/// The error type returned by `Message::validate` and `RelayMessage::validate`.
+#[derive(Fail, Debug)]
+pub enum Error {
+    #[fail(display = "Validation error: {}", _0)]
+    Validation(&'static str),
+}
+
+/// Checks if required options are present for each message type.
+macro_rules! must_set_option (
+    ($name:expr) => (
+        if $name.is_none() {
+            return Err(Error::Validation(stringify!($name)));
+        }
+    );
+);
+
+/// Checks if forbidden options are absent for each message type.
+macro_rules! must_not_set_option (
+    ($name:expr) => (
+        if $name.is_some() {
+            return Err(Error::Validation(stringify!($name)));
+        }
+    );
+);
+
+impl Message {
+    /// DHCPv6 client/server message validation.
+    ///
+    /// Returns the message type on successful validation.
+    ///
+    /// [RFC 8415 §16](https://tools.ietf.org/html/rfc8415#section-16)
+    ///
+    /// # Errors
+    /// Returns `Error::Validation` if any option is invalid.
+    pub fn validate(&self) -> Result<MessageType, Error> {
+        let message = self; // for the stringify! macro above
+
+        if message.transaction_id > TRANSACTION_ID_MAX {
+            return Err(Error::Validation("Transaction ID does not fit 24 bits"));
+        }
+        message.options.validate()?;
+
+        // RFC 8415 Appendix B
+        match message.message_type {
+            MessageType::Advertise => {}
+            _ => must_not_set_option!(message.options.preference),
+        }
+        match message.message_type {
+            MessageType::Solicit | MessageType::Reply => {}
+            _ => must_not_set_option!(message.options.rapid_commit),
+        }
+        must_not_set_option!(message.options.relay_message);
+        must_not_set_option!(message.options.interface_id);
+
+        match message.message_type {
+            // client generated messages section
+            MessageType::Solicit | MessageType::Confirm | MessageType::Rebind => {
+                must_set_option!(message.options.client_id);
+                must_not_set_option!(message.options.server_id);
+            }
+            MessageType::Request
+            | MessageType::Renew
+            | MessageType::Decline
+            | MessageType::Release => {
+                must_set_option!(message.options.client_id);
+                must_set_option!(message.options.server_id);
+            }
+            MessageType::InformationRequest => {
+                if !message.options.ia_na.is_empty()
+                    || !message.options.ia_ta.is_empty()
+                    || !message.options.ia_pd.is_empty()
+                {
+                    return Err(Error::Validation(
+                        "Information-request contains an identity association",
+                    ));
+                }
+            }
+
+            // server generated messages section
+            MessageType::Advertise | MessageType::Reconfigure => {
+                must_set_option!(message.options.client_id);
+                must_set_option!(message.options.server_id);
+            }
+            MessageType::Reply => {
+                must_set_option!(message.options.server_id);
+            }
+
+            MessageType::RelayForw | MessageType::RelayRepl => {
+                return Err(Error::Validation(
+                    "Relay agent message type in a client/server message",
+                ));
+            }
+            MessageType::Undefined => return Err(Error::Validation("Unknown message type")),
+        }
+
+        Ok(message.message_type)
+    }
+}
+
+impl RelayMessage {
+    /// DHCPv6 relay agent message validation.
+    ///
+    /// The encapsulated message is not validated, since it may be a relay agent message as well.
+    ///
+    /// Returns the message type on successful validation.
+    ///
+    /// # Errors
+    /// Returns `Error::Validation` if any option is invalid.
+    pub fn validate(&self) -> Result<MessageType, Error> {
+        let message = self; // for the stringify! macro above
+
+        if !message.message_type.is_relay() {
+            return Err(Error::Validation(
+                "Client/server message type in a relay agent message",
+            ));
+        }
+        if message.hop_count > HOP_COUNT_LIMIT {
+            return Err(Error::Validation("Hop count limit is exceeded"));
+        }
+        message.options.validate()?;
+        must_set_option!(message.options.relay_message);
+
+        Ok(message.message_type)
+    }
+}
+
+impl Options {
+    /// Checks the lifetimes and the encapsulated options of the identity associations.
+    ///
+    /// [RFC 8415 §21.4](https://tools.ietf.org/html/rfc8415#section-21.4)
+    /// [RFC 8415 §21.6](https://tools.ietf.org/html/rfc8415#section-21.6)
+    /// [RFC 8415 §21.21](https://tools.ietf.org/html/rfc8415#section-21.21)
+    /// [RFC 8415 §21.22](https://tools.ietf.org/html/rfc8415#section-21.22)
+    fn validate(&self) -> Result<(), Error> {
+        for ia in self.ia_na.iter().chain(self.ia_pd.iter()) {
+            if ia.t1 > 0 && ia.t2 > 0 && ia.t1 > ia.t2 {
+                return Err(Error::Validation(
+                    "Identity association T1 is greater than T2",
+                ));
+            }
+            ia.options.validate()?;
+        }
+        for ia in self.ia_ta.iter() {
+            ia.options.validate()?;
+        }
+        for address in self.ia_addresses.iter() {
+            if address.preferred_lifetime > address.valid_lifetime {
+                return Err(Error::Validation(
+                    "IA address preferred lifetime is greater than the valid one",
+                ));
+            }
+            address.options.validate()?;
+        }
+        for prefix in self.ia_prefixes.iter() {
+            if prefix.preferred_lifetime > prefix.valid_lifetime {
+                return Err(Error::Validation(
+                    "IA prefix preferred lifetime is greater than the valid one",
+                ));
+            }
+            if prefix.prefix_length > PREFIX_LENGTH_MAX {
+                return Err(Error::Validation("IA prefix length is invalid"));
+            }
+            prefix.options.validate()?;
+        }
+        Ok(())
+    }
+}
@@ -73,23 +73,36 @@ impl BpfData {
 
     /// Sends a DHCP `message` from `source` to `destination` via BPF.
     ///
+    /// The message is signed with the `key` if it is specified.
+    ///
     /// # Errors
     /// `io::Error` on a message serializing error.
     /// `io::Error` on an Ethernet packet building error.
+    /// `io::Error` if the client hardware address is not MAC-48.
     pub fn send(
         &mut self,
         source: &Ipv4Addr,
         destination: &Ipv4Addr,
         message: Message,
         max_size: Option<u16>,
+        key: Option<Vec<u8>>,
     ) -> io::Result<()> {
         trace!("Sending to {} via BPF", destination);
 
         let mut payload = vec![0u8; DEFAULT_PACKET_BUFFER_SIZE];
-        let amount = message.to_bytes(payload.as_mut(), max_size)?;
+        let amount = match key {
+            Some(ref key) => message.to_bytes_signed(payload.as_mut(), max_size, key)?,
+            None => message.to_bytes(payload.as_mut(), max_size)?,
+        };
+        let client_hardware_address = message
+            .client_hardware_address
+            .to_mac_address()
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Hardware address is not MAC-48")
+            })?;
         let packet = Self::ethernet_packet(
             self.iface_hw_addr.to_owned(),
-            message.client_hardware_address.to_owned(),
+            client_hardware_address,
             source.to_owned(),
             destination.to_owned(),
             &payload[..amount],
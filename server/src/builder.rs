@@ -2,9 +2,32 @@
 
 use std::net::Ipv4Addr;
 
+use rand;
+
 use dhcp_protocol::*;
 
-use database::{Ack, Error, Offer};
+use database::{Ack, Binding, Error, Offer, Reconfigure};
+
+/// The `MessageBuilder` parameters, which are too many to be passed one by one.
+///
+/// See the `MessageBuilder` fields for their meaning.
+pub struct MessageParameters {
+    pub server_ip_address: Ipv4Addr,
+    pub hostname: Option<String>,
+    pub subnet_mask: Ipv4Addr,
+    pub routers: Vec<Ipv4Addr>,
+    pub domain_name_servers: Vec<Ipv4Addr>,
+    pub static_routes: Vec<(Ipv4Addr, Ipv4Addr)>,
+    pub classless_static_routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>,
+    pub client_fqdn_server_update: bool,
+    pub domain_search: Vec<DomainName>,
+    pub sip_servers: Option<SipServers>,
+    pub ipv6_only_preferred: Option<u32>,
+    pub captive_portal: Option<String>,
+    pub vendor_specific: VendorRegistry<Vec<u8>>,
+    pub boot_filenames: Vec<(ClientArchitecture, String)>,
+    pub user_class_boot_filenames: Vec<(Vec<u8>, String)>,
+}
 
 /// Builds common server messages with some parameters.
 pub struct MessageBuilder {
@@ -22,29 +45,44 @@ pub struct MessageBuilder {
     static_routes: Vec<(Ipv4Addr, Ipv4Addr)>,
     /// Sent to clients in options.
     classless_static_routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>,
+    /// Sent to clients in the echoed `client_fqdn` option `S` flag.
+    client_fqdn_server_update: bool,
+    /// Sent to clients in options.
+    domain_search: Vec<DomainName>,
+    /// Sent to clients in options.
+    sip_servers: Option<SipServers>,
+    /// Sent to clients in options if requested.
+    ipv6_only_preferred: Option<u32>,
+    /// Sent to clients in options.
+    captive_portal: Option<String>,
+    /// Sent to clients in options by the vendor class identifier prefix.
+    vendor_specific: VendorRegistry<Vec<u8>>,
+    /// Sent to network booting clients in `boot_filename` field by the client architecture.
+    boot_filenames: Vec<(ClientArchitecture, String)>,
+    /// Sent to network booting clients in `boot_filename` field by the user class.
+    user_class_boot_filenames: Vec<(Vec<u8>, String)>,
 }
 
 impl MessageBuilder {
     /// Creates a builder with message parameters which will not be changed.
-    pub fn new(
-        server_ip_address: Ipv4Addr,
-        hostname: Option<String>,
-
-        subnet_mask: Ipv4Addr,
-        routers: Vec<Ipv4Addr>,
-        domain_name_servers: Vec<Ipv4Addr>,
-        static_routes: Vec<(Ipv4Addr, Ipv4Addr)>,
-        classless_static_routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>,
-    ) -> Self {
+    pub fn new(parameters: MessageParameters) -> Self {
         MessageBuilder {
-            server_ip_address,
-            hostname,
-
-            subnet_mask,
-            routers,
-            domain_name_servers,
-            static_routes,
-            classless_static_routes,
+            server_ip_address: parameters.server_ip_address,
+            hostname: parameters.hostname,
+
+            subnet_mask: parameters.subnet_mask,
+            routers: parameters.routers,
+            domain_name_servers: parameters.domain_name_servers,
+            static_routes: parameters.static_routes,
+            classless_static_routes: parameters.classless_static_routes,
+            client_fqdn_server_update: parameters.client_fqdn_server_update,
+            domain_search: parameters.domain_search,
+            sip_servers: parameters.sip_servers,
+            ipv6_only_preferred: parameters.ipv6_only_preferred,
+            captive_portal: parameters.captive_portal,
+            vendor_specific: parameters.vendor_specific,
+            boot_filenames: parameters.boot_filenames,
+            user_class_boot_filenames: parameters.user_class_boot_filenames,
         }
     }
 
@@ -52,9 +90,13 @@ impl MessageBuilder {
     pub fn dhcp_discover_to_offer(&self, discover: &Message, offer: &Offer) -> Message {
         let mut options = Options::default();
         self.append_default_options(&mut options);
+        self.append_echoed_options(&mut options, discover);
         if let Some(ref parameter_list) = discover.options.parameter_list {
             self.append_requested_options(&mut options, parameter_list);
         }
+        self.append_vendor_specific(&mut options, discover);
+
+        self.append_client_fqdn(&mut options, discover);
 
         options.dhcp_message_type = Some(MessageType::DhcpOffer);
         options.dhcp_message = Some(offer.message.to_owned());
@@ -62,8 +104,7 @@ impl MessageBuilder {
 
         Message {
             operation_code: OperationCode::BootReply,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: discover.hardware_address_length,
+            hardware_type: discover.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id: discover.transaction_id,
@@ -77,7 +118,7 @@ impl MessageBuilder {
 
             client_hardware_address: discover.client_hardware_address,
             server_name: Default::default(),
-            boot_filename: Default::default(),
+            boot_filename: self.boot_filename(discover),
 
             options,
         }
@@ -87,9 +128,13 @@ impl MessageBuilder {
     pub fn dhcp_request_to_ack(&self, request: &Message, ack: &Ack) -> Message {
         let mut options = Options::default();
         self.append_default_options(&mut options);
+        self.append_echoed_options(&mut options, request);
         if let Some(ref parameter_list) = request.options.parameter_list {
             self.append_requested_options(&mut options, parameter_list);
         }
+        self.append_vendor_specific(&mut options, request);
+
+        self.append_client_fqdn(&mut options, request);
 
         options.dhcp_message_type = Some(MessageType::DhcpAck);
         options.dhcp_message = Some(ack.message.to_owned());
@@ -99,8 +144,7 @@ impl MessageBuilder {
 
         Message {
             operation_code: OperationCode::BootReply,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: request.hardware_address_length,
+            hardware_type: request.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id: request.transaction_id,
@@ -114,27 +158,35 @@ impl MessageBuilder {
 
             client_hardware_address: request.client_hardware_address,
             server_name: Default::default(),
-            boot_filename: Default::default(),
+            boot_filename: self.boot_filename(request),
 
             options,
         }
     }
 
+    /// Creates a committed `DHCPACK` message from a `DHCPDISCOVER` message with the Rapid Commit option.
+    pub fn dhcp_discover_to_ack(&self, discover: &Message, ack: &Ack) -> Message {
+        let mut response = self.dhcp_request_to_ack(discover, ack);
+        response.options.rapid_commit = Some(());
+        response
+    }
+
     /// Creates a `DHCPACK` message from a `DHCPINFORM` message.
     pub fn dhcp_inform_to_ack(&self, inform: &Message, message: &str) -> Message {
         let mut options = Options::default();
         self.append_default_options(&mut options);
+        self.append_echoed_options(&mut options, inform);
         if let Some(ref parameter_list) = inform.options.parameter_list {
             self.append_requested_options(&mut options, parameter_list);
         }
+        self.append_vendor_specific(&mut options, inform);
 
         options.dhcp_message_type = Some(MessageType::DhcpAck);
         options.dhcp_message = Some(message.to_owned());
 
         Message {
             operation_code: OperationCode::BootReply,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: inform.hardware_address_length,
+            hardware_type: inform.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id: inform.transaction_id,
@@ -155,17 +207,19 @@ impl MessageBuilder {
     }
 
     /// Creates a `DHCPNAK` message from a `DHCPREQUEST` message.
+    ///
+    /// The hostname is not appended, since RFC 2131 table 3 forbids it in `DHCPNAK`.
     pub fn dhcp_request_to_nak(&self, request: &Message, error: &Error) -> Message {
         let mut options = Options::default();
-        self.append_default_options(&mut options);
+        options.dhcp_server_id = Some(self.server_ip_address);
+        self.append_echoed_options(&mut options, request);
 
         options.dhcp_message_type = Some(MessageType::DhcpNak);
         options.dhcp_message = Some(error.to_string());
 
         Message {
             operation_code: OperationCode::BootReply,
-            hardware_type: HardwareType::Ethernet,
-            hardware_address_length: request.hardware_address_length,
+            hardware_type: request.hardware_type,
             hardware_options: Default::default(),
 
             transaction_id: request.transaction_id,
@@ -185,53 +239,541 @@ impl MessageBuilder {
         }
     }
 
+    /// Creates a `DHCPLEASEACTIVE` message from a `DHCPLEASEQUERY` message.
+    pub fn dhcp_leasequery_to_active(&self, query: &Message, binding: &Binding) -> Message {
+        let mut options = Options::default();
+        self.append_default_options(&mut options);
+        if let Some(ref parameter_list) = query.options.parameter_list {
+            self.append_requested_options(&mut options, parameter_list);
+
+            // only one address may be bound to a client, but the requestor asks for all of them
+            if query.client_ip_address.is_unspecified()
+                && parameter_list.contains(&(OptionTag::AssociatedIp as u8))
+            {
+                options.associated_ip = Some(vec![binding.address]);
+            }
+        }
+
+        options.dhcp_message_type = Some(MessageType::DhcpLeaseActive);
+        options.address_time = Some(binding.lease_time);
+        options.client_last_transaction_time = Some(binding.last_transaction_time);
+
+        let (hardware_type, client_hardware_address) = Self::client_hardware_address(
+            &mut options,
+            &binding.client_id,
+            binding.hardware_address,
+        );
+
+        Message {
+            operation_code: OperationCode::BootReply,
+            hardware_type,
+            hardware_options: Default::default(),
+
+            transaction_id: query.transaction_id,
+            seconds: Default::default(),
+            is_broadcast: false,
+
+            client_ip_address: binding.address,
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: query.gateway_ip_address,
+
+            client_hardware_address,
+            server_name: Default::default(),
+            boot_filename: Default::default(),
+
+            options,
+        }
+    }
+
+    /// Creates a `DHCPLEASEUNASSIGNED` message from a `DHCPLEASEQUERY` message.
+    pub fn dhcp_leasequery_to_unassigned(&self, query: &Message) -> Message {
+        self.dhcp_leasequery_to_inactive(query, MessageType::DhcpLeaseUnassigned)
+    }
+
+    /// Creates a `DHCPLEASEUNKNOWN` message from a `DHCPLEASEQUERY` message.
+    pub fn dhcp_leasequery_to_unknown(&self, query: &Message) -> Message {
+        self.dhcp_leasequery_to_inactive(query, MessageType::DhcpLeaseUnknown)
+    }
+
+    /// The query fields are copied back, so the requestor could match the reply.
+    fn dhcp_leasequery_to_inactive(
+        &self,
+        query: &Message,
+        dhcp_message_type: MessageType,
+    ) -> Message {
+        let mut options = Options::default();
+        self.append_default_options(&mut options);
+
+        options.dhcp_message_type = Some(dhcp_message_type);
+        options.client_id = query.options.client_id.to_owned();
+
+        Message {
+            operation_code: OperationCode::BootReply,
+            hardware_type: query.hardware_type,
+            hardware_options: Default::default(),
+
+            transaction_id: query.transaction_id,
+            seconds: Default::default(),
+            is_broadcast: false,
+
+            client_ip_address: query.client_ip_address,
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: query.gateway_ip_address,
+
+            client_hardware_address: query.client_hardware_address,
+            server_name: Default::default(),
+            boot_filename: Default::default(),
+
+            options,
+        }
+    }
+
+    /// Creates a `DHCPFORCERENEW` message to be signed with the client reconfigure key
+    /// by `Message::to_bytes_signed`.
+    pub fn dhcp_forcerenew(&self, reconfigure: &Reconfigure) -> Message {
+        let mut options = Options::default();
+        self.append_default_options(&mut options);
+
+        options.dhcp_message_type = Some(MessageType::DhcpForceRenew);
+        options.authentication = Some(Authentication::reconfigure_hmac_md5(
+            Authentication::monotonic_counter(),
+        ));
+
+        let (hardware_type, client_hardware_address) = Self::client_hardware_address(
+            &mut options,
+            &reconfigure.client_id,
+            reconfigure.hardware_address,
+        );
+
+        Message {
+            operation_code: OperationCode::BootReply,
+            hardware_type,
+            hardware_options: Default::default(),
+
+            transaction_id: rand::random::<u32>(),
+            seconds: Default::default(),
+            is_broadcast: false,
+
+            client_ip_address: reconfigure.address,
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+
+            client_hardware_address,
+            server_name: Default::default(),
+            boot_filename: Default::default(),
+
+            options,
+        }
+    }
+
+    /// Appends the reconfigure key to a `DHCPACK` message.
+    ///
+    /// The client uses the key to authenticate `DHCPFORCERENEW` messages.
+    pub fn append_reconfigure_key(&self, ack: &mut Message, key: &[u8]) {
+        ack.options.authentication = Some(Authentication::reconfigure_key(
+            Authentication::monotonic_counter(),
+            key,
+        ));
+    }
+
+    /// The client hardware type and address recorded in the lease.
+    ///
+    /// The client is stored either by its hardware address or by its `client_id` option,
+    /// so the option is sent back in the latter case. If the lease has no hardware address
+    /// recorded, `chaddr` is left empty and the client is identified by the option only.
+    fn client_hardware_address(
+        options: &mut Options,
+        client_id: &[u8],
+        hardware_address: Option<(HardwareType, HardwareAddress)>,
+    ) -> (HardwareType, HardwareAddress) {
+        match hardware_address {
+            Some((hardware_type, hardware_address)) => {
+                if hardware_address.as_bytes() != client_id {
+                    options.client_id = Some(client_id.to_owned());
+                }
+                (hardware_type, hardware_address)
+            }
+            None => {
+                options.client_id = Some(client_id.to_owned());
+                (HardwareType::Undefined, HardwareAddress::empty())
+            }
+        }
+    }
+
     fn append_default_options(&self, options: &mut Options) {
         options.hostname = self.hostname.to_owned();
         options.dhcp_server_id = Some(self.server_ip_address);
     }
 
+    /// Appends the options which must be copied from the client request.
+    ///
+    /// RFC 3046 §2.2
+    /// DHCP servers claiming to support the Relay Agent Information option
+    /// SHALL echo the entire contents of the Relay Agent Information option
+    /// in all replies.
+    ///
+    /// RFC 3011 §3
+    /// Servers configured to support this option MUST return an
+    /// identical copy of the option to any client that sends it,
+    /// regardless of whether or not the client requests the option in
+    /// a parameter request list.
+    fn append_echoed_options(&self, options: &mut Options, request: &Message) {
+        // the decoded relay agent information is written back as it was received
+        options.relay_agent_information = request.options.relay_agent_information.to_owned();
+        options.subnet_selection = request.options.subnet_selection;
+    }
+
+    /// RFC 4702 §4
+    /// When a DHCP server receives a client message containing the Client
+    /// FQDN option, the server SHOULD include the Client FQDN option in its
+    /// DHCPOFFER and DHCPACK responses.
+    fn append_client_fqdn(&self, options: &mut Options, request: &Message) {
+        options.client_fqdn = request
+            .options
+            .client_fqdn
+            .as_ref()
+            .map(|client_fqdn| client_fqdn.reply(self.client_fqdn_server_update));
+    }
+
+    /// RFC 2132 §9.13
+    /// This option is used by DHCP clients to optionally identify the vendor
+    /// type and configuration of a DHCP client. [...] Servers that respond
+    /// SHOULD only use option 43 to return the vendor-specific information to
+    /// the client.
+    fn append_vendor_specific(&self, options: &mut Options, request: &Message) {
+        let is_requested = match request.options.parameter_list {
+            Some(ref parameter_list) => parameter_list.contains(&(OptionTag::VendorSpecific as u8)),
+            None => false,
+        };
+        let class_id = match request.options.class_id {
+            Some(ref class_id) if is_requested => class_id,
+            _ => return,
+        };
+        options.vendor_specific = self.vendor_specific.get(class_id).cloned();
+    }
+
+    /// RFC 4578 §2.1
+    /// The values of the client system architecture option are listed in
+    /// order of preference, with the most preferred listed first.
+    ///
+    /// The user class boot file names take precedence over the architecture ones.
+    fn boot_filename(&self, request: &Message) -> Vec<u8> {
+        if let Some(ref user_class) = request.options.user_class {
+            let boot_filename = self
+                .user_class_boot_filenames
+                .iter()
+                .find(|(class, _)| user_class.contains(class));
+            if let Some((_, boot_filename)) = boot_filename {
+                return boot_filename.as_bytes().to_vec();
+            }
+        }
+
+        let architectures = match request.options.client_system_architecture {
+            Some(ref architectures) => architectures,
+            None => return Vec::new(),
+        };
+        architectures
+            .iter()
+            .filter_map(|architecture| {
+                self.boot_filenames
+                    .iter()
+                    .find(|(element, _)| element == architecture)
+            })
+            .next()
+            .map(|(_, boot_filename)| boot_filename.as_bytes().to_vec())
+            .unwrap_or_default()
+    }
+
     fn append_requested_options(&self, options: &mut Options, parameter_list: &[u8]) {
-        for tag in parameter_list {
-            match (*tag).into() {
-                OptionTag::SubnetMask => options.subnet_mask = Some(self.subnet_mask),
-                OptionTag::DomainNameServers => if self.domain_name_servers.len() > 0 {
-                    options.domain_name_servers = Some(self.domain_name_servers.to_owned());
-                },
-
-                /*
-                RFC 3442
-                Many clients may not implement the Classless Static Routes option.
-                DHCP server administrators should therefore configure their DHCP
-                servers to send both a Router option and a Classless Static Routes
-                option, and should specify the default router(s) both in the Router
-                option and in the Classless Static Routes option.
-
-                When a DHCP client requests the Classless Static Routes option and
-                also requests either or both of the Router option and the Static
-                Routes option, and the DHCP server is sending Classless Static Routes
-                options to that client, the server SHOULD NOT include the Router or
-                Static Routes options.
-                */
-                OptionTag::ClasslessStaticRoutes => if self.classless_static_routes.len() > 0 {
-                    options.classless_static_routes = Some(self.classless_static_routes.to_owned())
-                },
-                OptionTag::Routers => if (!parameter_list
-                    .contains(&(OptionTag::ClasslessStaticRoutes as u8))
-                    || self.classless_static_routes.len() == 0)
-                    && self.routers.len() > 0
-                {
-                    options.routers = Some(self.routers.to_owned());
-                },
-                OptionTag::StaticRoutes => if (!parameter_list
-                    .contains(&(OptionTag::ClasslessStaticRoutes as u8))
-                    || self.classless_static_routes.len() == 0)
-                    && self.static_routes.len() > 0
-                {
-                    options.static_routes = Some(self.static_routes.to_owned())
-                },
-
-                _ => continue,
+        /*
+        RFC 3442
+        Many clients may not implement the Classless Static Routes option.
+        DHCP server administrators should therefore configure their DHCP
+        servers to send both a Router option and a Classless Static Routes
+        option, and should specify the default router(s) both in the Router
+        option and in the Classless Static Routes option.
+
+        When a DHCP client requests the Classless Static Routes option and
+        also requests either or both of the Router option and the Static
+        Routes option, and the DHCP server is sending Classless Static Routes
+        options to that client, the server SHOULD NOT include the Router or
+        Static Routes options.
+        */
+        let is_classless_sent = parameter_list.contains(&(OptionTag::ClasslessStaticRoutes as u8))
+            && !self.classless_static_routes.is_empty();
+
+        for tag in parameter_list.iter().map(|tag| OptionTag::from(*tag)) {
+            let value = match tag {
+                OptionTag::Routers | OptionTag::StaticRoutes if is_classless_sent => continue,
+                tag => self.configured_option(tag),
+            };
+            if let Some(value) = value {
+                options
+                    .set(tag, value)
+                    .expect("The configured value type matches the tag");
+            }
+        }
+    }
+
+    /// The configured value of a requested option or `None` if it is not configured.
+    fn configured_option(&self, tag: OptionTag) -> Option<OptionValue> {
+        match tag {
+            OptionTag::SubnetMask => Some(OptionValue::Ipv4(self.subnet_mask)),
+            OptionTag::DomainNameServers if !self.domain_name_servers.is_empty() => Some(
+                OptionValue::Ipv4List(self.domain_name_servers.to_owned()),
+            ),
+            OptionTag::DomainSearch if !self.domain_search.is_empty() => {
+                Some(OptionValue::DomainNames(self.domain_search.to_owned()))
+            }
+            OptionTag::SipServers => self.sip_servers.to_owned().map(OptionValue::SipServers),
+            // the option is sent only if it is requested, like the other ones here (RFC 8925 §3.3)
+            OptionTag::Ipv6OnlyPreferred => self.ipv6_only_preferred.map(OptionValue::U32),
+            OptionTag::CaptivePortal => self.captive_portal.to_owned().map(OptionValue::String),
+            OptionTag::ClasslessStaticRoutes if !self.classless_static_routes.is_empty() => Some(
+                OptionValue::ClasslessStaticRoutes(self.classless_static_routes.to_owned()),
+            ),
+            OptionTag::Routers if !self.routers.is_empty() => {
+                Some(OptionValue::Ipv4List(self.routers.to_owned()))
+            }
+            OptionTag::StaticRoutes if !self.static_routes.is_empty() => {
+                Some(OptionValue::Ipv4Pairs(self.static_routes.to_owned()))
             }
+            _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> MessageParameters {
+        MessageParameters {
+            server_ip_address: Ipv4Addr::new(192, 168, 0, 2),
+            hostname: None,
+            subnet_mask: Ipv4Addr::new(255, 255, 255, 0),
+            routers: Vec::new(),
+            domain_name_servers: Vec::new(),
+            static_routes: Vec::new(),
+            classless_static_routes: Vec::new(),
+            client_fqdn_server_update: false,
+            domain_search: Vec::new(),
+            sip_servers: None,
+            ipv6_only_preferred: Some(1800),
+            captive_portal: Some("https://example.org/portal".to_owned()),
+            vendor_specific: VendorRegistry::default(),
+            boot_filenames: Vec::new(),
+            user_class_boot_filenames: Vec::new(),
+        }
+    }
+
+    fn builder() -> MessageBuilder {
+        MessageBuilder::new(parameters())
+    }
+
+    fn request(dhcp_message_type: MessageType, parameter_list: Vec<u8>) -> Message {
+        let mut options = Options::default();
+        options.dhcp_message_type = Some(dhcp_message_type);
+        options.parameter_list = Some(parameter_list);
+        Message {
+            operation_code: OperationCode::BootRequest,
+            hardware_type: HardwareType::Ethernet,
+            hardware_options: 0,
+            transaction_id: 0xdead_beef,
+            seconds: 0,
+            is_broadcast: false,
+            client_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            your_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            server_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            gateway_ip_address: Ipv4Addr::new(0, 0, 0, 0),
+            client_hardware_address: HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37])
+                .unwrap(),
+            server_name: Vec::new(),
+            boot_filename: Vec::new(),
+            options,
+        }
+    }
+
+    fn offer() -> Offer {
+        Offer {
+            address: Ipv4Addr::new(192, 168, 0, 100),
+            lease_time: 3600,
+            message: "Offered".to_owned(),
+        }
+    }
+
+    fn ack() -> Ack {
+        Ack {
+            address: Ipv4Addr::new(192, 168, 0, 100),
+            lease_time: 3600,
+            renewal_time: 1800,
+            rebinding_time: 3150,
+            message: "Assigned".to_owned(),
+        }
+    }
+
+    #[test]
+    fn ipv6_only_preferred_is_sent_only_if_requested() {
+        let builder = builder();
+        let subnet_mask = OptionTag::SubnetMask as u8;
+
+        let discover = request(MessageType::DhcpDiscover, vec![subnet_mask]);
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert!(response.options.ipv6_only_preferred.is_none());
+        assert!(response.options.captive_portal.is_none());
+
+        let parameter_list = vec![
+            subnet_mask,
+            OptionTag::Ipv6OnlyPreferred as u8,
+            OptionTag::CaptivePortal as u8,
+        ];
+        let discover = request(MessageType::DhcpDiscover, parameter_list.clone());
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.options.ipv6_only_preferred, Some(1800));
+        assert_eq!(
+            response.options.captive_portal,
+            Some("https://example.org/portal".to_owned())
+        );
+
+        let request = request(MessageType::DhcpRequest, parameter_list);
+        let response = builder.dhcp_request_to_ack(&request, &ack());
+        assert_eq!(response.options.ipv6_only_preferred, Some(1800));
+    }
+
+    #[test]
+    fn leasequery_reply_carries_recorded_hardware_address() {
+        let builder = builder();
+        let query = request(MessageType::DhcpLeaseQuery, Vec::new());
+        let hardware_address = query.client_hardware_address;
+        let mut binding = Binding {
+            client_id: vec![0x01, 0x02, 0x03],
+            hardware_address: Some((HardwareType::Ieee802, hardware_address)),
+            address: Ipv4Addr::new(192, 168, 0, 100),
+            lease_time: 3600,
+            last_transaction_time: 60,
+        };
+
+        let response = builder.dhcp_leasequery_to_active(&query, &binding);
+        assert_eq!(response.hardware_type, HardwareType::Ieee802);
+        assert_eq!(response.client_hardware_address, hardware_address);
+        assert_eq!(response.options.client_id, Some(vec![0x01, 0x02, 0x03]));
+
+        // the hardware address is not guessed from the client ID
+        binding.hardware_address = None;
+        let response = builder.dhcp_leasequery_to_active(&query, &binding);
+        assert_eq!(response.hardware_type, HardwareType::Undefined);
+        assert!(response.client_hardware_address.is_empty());
+        assert_eq!(response.options.client_id, Some(vec![0x01, 0x02, 0x03]));
+    }
+
+    #[test]
+    fn forcerenew_is_signed_with_reconfigure_key() {
+        let builder = builder();
+        let reconfigure = Reconfigure {
+            client_id: vec![0x01, 0x02, 0x03],
+            hardware_address: None,
+            address: Ipv4Addr::new(192, 168, 0, 100),
+            key: vec![0x42; SIZE_HMAC_MD5],
+        };
+
+        let message = builder.dhcp_forcerenew(&reconfigure);
+        assert_eq!(
+            message.options.dhcp_message_type,
+            Some(MessageType::DhcpForceRenew)
+        );
+        assert_eq!(
+            message.options.dhcp_server_id,
+            Some(Ipv4Addr::new(192, 168, 0, 2))
+        );
+        assert_eq!(message.client_ip_address, reconfigure.address);
+
+        let mut buffer = vec![0u8; SIZE_MESSAGE_MINIMAL];
+        let amount = message
+            .to_bytes_signed(&mut buffer, None, &reconfigure.key)
+            .unwrap();
+        let packet = &buffer[..amount];
+        assert!(message.verify_reconfigure(packet, &reconfigure.key));
+        assert!(!message.verify_reconfigure(packet, &[0x24; SIZE_HMAC_MD5]));
+    }
+
+    #[test]
+    fn rapid_commit_ack_is_built_from_discover() {
+        let builder = builder();
+        let mut discover = request(MessageType::DhcpDiscover, Vec::new());
+        discover.options.rapid_commit = Some(());
+
+        let response = builder.dhcp_discover_to_ack(&discover, &ack());
+        assert_eq!(
+            response.options.dhcp_message_type,
+            Some(MessageType::DhcpAck)
+        );
+        assert!(response.options.rapid_commit.is_some());
+        assert_eq!(response.transaction_id, discover.transaction_id);
+        assert_eq!(response.your_ip_address, ack().address);
+        assert_eq!(response.options.address_time, Some(ack().lease_time));
+
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert!(response.options.rapid_commit.is_none());
+    }
+
+    #[test]
+    fn user_class_boot_filename_takes_precedence() {
+        let mut parameters = parameters();
+        parameters.boot_filenames = vec![
+            (ClientArchitecture::X86Bios, "undionly.kpxe".to_owned()),
+            (ClientArchitecture::EfiX64, "ipxe.efi".to_owned()),
+        ];
+        parameters.user_class_boot_filenames = vec![(b"iPXE".to_vec(), "boot.ipxe".to_owned())];
+        let builder = MessageBuilder::new(parameters);
+
+        let mut discover = request(MessageType::DhcpDiscover, Vec::new());
+        discover.options.client_system_architecture = Some(vec![
+            ClientArchitecture::EfiX64,
+            ClientArchitecture::X86Bios,
+        ]);
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.boot_filename, b"ipxe.efi".to_vec());
+
+        discover.options.user_class =
+            Some(UserClass::new(vec![b"other".to_vec(), b"iPXE".to_vec()]));
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.boot_filename, b"boot.ipxe".to_vec());
+
+        discover.options.user_class = Some(UserClass::new(vec![b"other".to_vec()]));
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.boot_filename, b"ipxe.efi".to_vec());
+    }
+
+    #[test]
+    fn vendor_specific_is_chosen_by_longest_class_id_prefix() {
+        let mut parameters = parameters();
+        parameters
+            .vendor_specific
+            .register(b"PXEClient", vec![6, 1, 8])
+            .register(b"PXEClient:Arch:00007", vec![6, 1, 3]);
+        let builder = MessageBuilder::new(parameters);
+
+        let vendor_specific = OptionTag::VendorSpecific as u8;
+        let mut discover = request(MessageType::DhcpDiscover, vec![vendor_specific]);
+        discover.options.class_id = Some(b"PXEClient:Arch:00007:UNDI:003016".to_vec());
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.options.vendor_specific, Some(vec![6, 1, 3]));
+
+        discover.options.class_id = Some(b"PXEClient:Arch:00000:UNDI:002001".to_vec());
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert_eq!(response.options.vendor_specific, Some(vec![6, 1, 8]));
+
+        discover.options.class_id = Some(b"MSFT 5.0".to_vec());
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert!(response.options.vendor_specific.is_none());
+
+        // the information is only sent if requested
+        let mut discover = request(MessageType::DhcpDiscover, Vec::new());
+        discover.options.class_id = Some(b"PXEClient:Arch:00007:UNDI:003016".to_vec());
+        let response = builder.dhcp_discover_to_offer(&discover, &offer());
+        assert!(response.options.vendor_specific.is_none());
+    }
+}
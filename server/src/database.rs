@@ -2,6 +2,11 @@
 
 use std::{cmp, net::Ipv4Addr};
 
+use chrono::prelude::*;
+use rand;
+
+use dhcp_protocol::{HardwareAddress, HardwareType, SIZE_HMAC_MD5};
+
 use lease::Lease;
 use storage::{self, Storage};
 
@@ -21,6 +26,8 @@ pub enum Error {
     Storage(storage::Error),
     #[fail(display = "The dynamic pool has been exhausted")]
     DynamicPoolExhausted,
+    #[fail(display = "The selected subnet is not served")]
+    SubnetNotServed,
 
     #[fail(display = "The requested address is not offered")]
     OfferNotFound,
@@ -33,6 +40,8 @@ pub enum Error {
     LeaseNotFound,
     #[fail(display = "The lease is invalid")]
     LeaseInvalid,
+    #[fail(display = "The client does not support reconfiguration")]
+    ReconfigureKeyNotFound,
 }
 
 impl From<storage::Error> for Error {
@@ -63,6 +72,40 @@ pub struct Ack {
     pub message: String,
 }
 
+/// Data required to create a `DHCPLEASEACTIVE` message.
+///
+/// Is returned by `Database::query_address` and `Database::query_client` methods.
+#[derive(Debug)]
+pub struct Binding {
+    pub client_id: Vec<u8>,
+    pub hardware_address: Option<(HardwareType, HardwareAddress)>,
+    pub address: Ipv4Addr,
+    pub lease_time: u32,
+    pub last_transaction_time: u32,
+}
+
+/// The lease state reported to a `DHCPLEASEQUERY` requestor.
+#[derive(Debug)]
+pub enum LeaseState {
+    /// The address is leased to the client.
+    Active(Binding),
+    /// The server is authoritative for the address or the client but there is no active lease.
+    Unassigned,
+    /// The server has no information about the address or the client.
+    Unknown,
+}
+
+/// Data required to create a `DHCPFORCERENEW` message.
+///
+/// Is returned by `Database::reconfigure` method.
+#[derive(Debug)]
+pub struct Reconfigure {
+    pub client_id: Vec<u8>,
+    pub hardware_address: Option<(HardwareType, HardwareAddress)>,
+    pub address: Ipv4Addr,
+    pub key: Vec<u8>,
+}
+
 /// DHCP persistent lease database.
 pub struct Database<S>
 where
@@ -132,12 +175,36 @@ where
     /// # Errors
     /// `self::Error` on internal storage error.
     /// `self::Error` on dynamic pool exhaustion.
+    #[allow(dead_code)]
     pub fn allocate(
         &mut self,
         client_id: &[u8],
         lease_time: Option<u32>,
         requested_address: Option<Ipv4Addr>,
     ) -> Result<Offer, Error> {
+        self.allocate_in_subnet(client_id, lease_time, requested_address, None)
+    }
+
+    /// Allocates an address like `allocate`, but the `(subnet address, subnet mask)`
+    /// of the Subnet Selection option restricts all the cases to the pools on that subnet
+    /// (RFC 3011 §3).
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    /// `self::Error` on dynamic pool exhaustion.
+    /// `self::Error` if no pool is on the selected subnet.
+    pub fn allocate_in_subnet(
+        &mut self,
+        client_id: &[u8],
+        lease_time: Option<u32>,
+        requested_address: Option<Ipv4Addr>,
+        subnet: Option<(Ipv4Addr, Ipv4Addr)>,
+    ) -> Result<Offer, Error> {
+        if let Some(ref subnet) = subnet {
+            if !self.serves_subnet(subnet) {
+                return Err(Error::SubnetNotServed);
+            }
+        }
         // for lease time case 1
         let reuse_lease_time = lease_time.is_none();
         // lease time case 2 or 3
@@ -145,7 +212,8 @@ where
 
         // address allocation case 1
         if let Some(address) = self.client_current_address(client_id)? {
-            if self.is_address_allocated_by(&address, client_id)?
+            if Self::is_address_in_subnet(&address, &subnet)
+                && self.is_address_allocated_by(&address, client_id)?
                 && !self.is_address_frozen(&address)?
             {
                 // lease time case 1
@@ -170,7 +238,9 @@ where
 
         // address allocation case 2
         if let Some(address) = self.client_last_address(client_id)? {
-            if self.is_address_available(&address)? {
+            if Self::is_address_in_subnet(&address, &subnet)
+                && self.is_address_available(&address)?
+            {
                 let lease_time = self.offer(&address, client_id, lease_time, false)?;
                 let offer = Offer {
                     address,
@@ -192,7 +262,9 @@ where
 
         // address allocation case 3
         if let Some(address) = requested_address {
-            if self.is_address_available(&address)? {
+            if Self::is_address_in_subnet(&address, &subnet)
+                && self.is_address_available(&address)?
+            {
                 let lease_time = self.offer(&address, client_id, lease_time, false)?;
                 let offer = Offer {
                     address,
@@ -213,7 +285,7 @@ where
         }
 
         // address allocation case 4, giaddr stuff not implemented
-        let address = self.get_dynamic_available()?
+        let address = self.get_dynamic_available(&subnet)?
             .ok_or(Error::DynamicPoolExhausted)?;
         let lease_time = self.offer(&address, client_id, lease_time, false)?;
         let offer = Offer {
@@ -234,12 +306,32 @@ where
     /// # Errors
     /// `self::Error` on internal storage error.
     /// `self::Error` on address assignment error.
+    #[allow(dead_code)]
     pub fn assign(
         &mut self,
         client_id: &[u8],
         address: &Ipv4Addr,
         lease_time: Option<u32>,
     ) -> Result<Ack, Error> {
+        self.assign_in_subnet(client_id, address, lease_time, None)
+    }
+
+    /// Assigns a previously offered address on the subnet selected by the client (RFC 3011 §3).
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    /// `self::Error` on address assignment error.
+    /// `self::Error` if the address is not on the selected subnet.
+    pub fn assign_in_subnet(
+        &mut self,
+        client_id: &[u8],
+        address: &Ipv4Addr,
+        lease_time: Option<u32>,
+        subnet: Option<(Ipv4Addr, Ipv4Addr)>,
+    ) -> Result<Ack, Error> {
+        if !Self::is_address_in_subnet(address, &subnet) {
+            return Err(Error::SubnetNotServed);
+        }
         if let Some(lease) = self.storage.get_lease(&client_id)? {
             if lease.is_offered() {
                 if lease.address() != *address {
@@ -277,12 +369,32 @@ where
     /// # Errors
     /// `self::Error` on internal storage error.
     /// `self::Error` on address renewal error.
+    #[allow(dead_code)]
     pub fn renew(
         &mut self,
         client_id: &[u8],
         address: &Ipv4Addr,
         lease_time: Option<u32>,
     ) -> Result<Ack, Error> {
+        self.renew_in_subnet(client_id, address, lease_time, None)
+    }
+
+    /// Renewes a previously assigned address on the subnet selected by the client (RFC 3011 §3).
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    /// `self::Error` on address renewal error.
+    /// `self::Error` if the address is not on the selected subnet.
+    pub fn renew_in_subnet(
+        &mut self,
+        client_id: &[u8],
+        address: &Ipv4Addr,
+        lease_time: Option<u32>,
+        subnet: Option<(Ipv4Addr, Ipv4Addr)>,
+    ) -> Result<Ack, Error> {
+        if !Self::is_address_in_subnet(address, &subnet) {
+            return Err(Error::SubnetNotServed);
+        }
         let lease_time = cmp::min(lease_time.unwrap_or(DEFAULT_LEASE_TIME), MAX_LEASE_TIME);
         if let Some(lease) = self.storage.get_lease(&client_id)? {
             if lease.address() == *address {
@@ -335,7 +447,27 @@ where
     /// # Errors
     /// `self::Error` on internal storage error.
     /// `self::Error` if the address is not leased to the client.
+    #[allow(dead_code)]
     pub fn check(&self, client_id: &[u8], address: &Ipv4Addr) -> Result<Ack, Error> {
+        self.check_in_subnet(client_id, address, None)
+    }
+
+    /// Checks the address of a client in the `INIT-REBOOT` state
+    /// on the subnet selected by the client (RFC 3011 §3).
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    /// `self::Error` if the address is not leased to the client.
+    /// `self::Error` if the address is not on the selected subnet.
+    pub fn check_in_subnet(
+        &self,
+        client_id: &[u8],
+        address: &Ipv4Addr,
+        subnet: Option<(Ipv4Addr, Ipv4Addr)>,
+    ) -> Result<Ack, Error> {
+        if !Self::is_address_in_subnet(address, &subnet) {
+            return Err(Error::SubnetNotServed);
+        }
         if let Some(lease) = self.storage.get_lease(&client_id)? {
             if lease.address() == *address && !lease.is_expired() && !lease.is_released() {
                 Ok(Ack {
@@ -353,6 +485,133 @@ where
         }
     }
 
+    /// Looks up the lease of an address for a `DHCPLEASEQUERY` message.
+    ///
+    /// The address is unassigned if it belongs to one of the pools but is not leased.
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    pub fn query_address(&self, address: &Ipv4Addr) -> Result<LeaseState, Error> {
+        if let Some(client_id) = self.storage.get_client(address)? {
+            if let Some(lease) = self.storage.get_lease(&client_id)? {
+                if lease.address() == *address && lease.is_active() {
+                    return Ok(LeaseState::Active(Self::binding(client_id, &lease)));
+                }
+            }
+        }
+        if self.is_address_in_static_pool(address) || self.is_address_in_dynamic_pool(address) {
+            Ok(LeaseState::Unassigned)
+        } else {
+            Ok(LeaseState::Unknown)
+        }
+    }
+
+    /// Looks up the lease of a client for a `DHCPLEASEQUERY` message.
+    ///
+    /// The client may be stored by any of the IDs, so they are tried in order.
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    pub fn query_client(&self, client_ids: &[Vec<u8>]) -> Result<LeaseState, Error> {
+        for client_id in client_ids.iter() {
+            if let Some(lease) = self.storage.get_lease(client_id)? {
+                if lease.is_active() {
+                    return Ok(LeaseState::Active(Self::binding(
+                        client_id.to_owned(),
+                        &lease,
+                    )));
+                }
+                return Ok(LeaseState::Unassigned);
+            }
+        }
+        Ok(LeaseState::Unknown)
+    }
+
+    /// Checks if any pool has addresses on the `(subnet address, subnet mask)` subnet.
+    pub fn serves_subnet(&self, subnet: &(Ipv4Addr, Ipv4Addr)) -> bool {
+        let mask = u32::from(subnet.1);
+        let first = u32::from(subnet.0) & mask;
+        let last = first | !mask;
+        [self.static_address_range, self.dynamic_address_range]
+            .iter()
+            .any(|(start, end)| u32::from(*start) <= last && u32::from(*end) >= first)
+    }
+
+    /// Generates a reconfigure key for the client lease and records it.
+    ///
+    /// The key is sent to the client in the `DHCPACK` and later used
+    /// to authenticate `DHCPFORCERENEW` messages.
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    pub fn issue_reconfigure_key(&mut self, client_id: &[u8]) -> Result<Vec<u8>, Error> {
+        let key: Vec<u8> = (0..SIZE_HMAC_MD5).map(|_| rand::random::<u8>()).collect();
+        self.storage
+            .update_lease(client_id, &mut |lease: &mut Lease| {
+                lease.set_reconfigure_key(key.to_owned())
+            })?;
+        Ok(key)
+    }
+
+    /// Records the hardware type and address of the client the lease is acknowledged to.
+    ///
+    /// They are sent back in `DHCPLEASEACTIVE` and `DHCPFORCERENEW` messages.
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    pub fn record_hardware_address(
+        &mut self,
+        client_id: &[u8],
+        hardware_type: HardwareType,
+        hardware_address: HardwareAddress,
+    ) -> Result<(), Error> {
+        self.storage
+            .update_lease(client_id, &mut |lease: &mut Lease| {
+                lease.set_hardware_address(hardware_type, hardware_address)
+            })?;
+        Ok(())
+    }
+
+    /// Looks up the active lease of an address to force its client to renew it.
+    ///
+    /// # Errors
+    /// `self::Error` on internal storage error.
+    /// `self::Error` if the address is not leased or the client cannot be reconfigured.
+    pub fn reconfigure(&self, address: &Ipv4Addr) -> Result<Reconfigure, Error> {
+        let client_id = self
+            .storage
+            .get_client(address)?
+            .ok_or(Error::LeaseNotFound)?;
+        let lease = self
+            .storage
+            .get_lease(&client_id)?
+            .ok_or(Error::LeaseNotFound)?;
+        if lease.address() != *address || !lease.is_active() {
+            return Err(Error::LeaseInvalid);
+        }
+        let key = lease
+            .reconfigure_key()
+            .ok_or(Error::ReconfigureKeyNotFound)?
+            .to_vec();
+        Ok(Reconfigure {
+            client_id,
+            hardware_address: lease.hardware_address(),
+            address: lease.address(),
+            key,
+        })
+    }
+
+    fn binding(client_id: Vec<u8>, lease: &Lease) -> Binding {
+        let now = Utc::now().timestamp() as u32;
+        Binding {
+            client_id,
+            hardware_address: lease.hardware_address(),
+            address: lease.address(),
+            lease_time: lease.expires_after(),
+            last_transaction_time: now.saturating_sub(lease.last_transaction_at()),
+        }
+    }
+
     fn offer(
         &mut self,
         address: &Ipv4Addr,
@@ -424,12 +683,17 @@ where
         Ok(self.storage.check_frozen(address)?)
     }
 
-    fn get_dynamic_available(&self) -> Result<Option<Ipv4Addr>, Error> {
+    fn get_dynamic_available(
+        &self,
+        subnet: &Option<(Ipv4Addr, Ipv4Addr)>,
+    ) -> Result<Option<Ipv4Addr>, Error> {
         for address in
             u32::from(self.dynamic_address_range.0)..=u32::from(self.dynamic_address_range.1)
         {
             let address = Ipv4Addr::from(address);
-            if self.is_address_available(&address)? {
+            if Self::is_address_in_subnet(&address, subnet)
+                && self.is_address_available(&address)?
+            {
                 return Ok(Some(address));
             }
         }
@@ -443,6 +707,16 @@ where
     fn is_address_in_dynamic_pool(&self, address: &Ipv4Addr) -> bool {
         self.dynamic_address_range.0 <= *address && *address <= self.dynamic_address_range.1
     }
+
+    /// Any address is allowed if no subnet is selected.
+    fn is_address_in_subnet(address: &Ipv4Addr, subnet: &Option<(Ipv4Addr, Ipv4Addr)>) -> bool {
+        match subnet {
+            Some((network, mask)) => {
+                u32::from(*address) & u32::from(*mask) == u32::from(*network) & u32::from(*mask)
+            }
+            None => true,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -682,4 +956,175 @@ mod tests {
 
         assert_ne!(ack4.address, requested);
     }
+
+    #[test]
+    fn answers_leasequery_by_address_and_client() {
+        let mut storage = Database::new(
+            (
+                Ipv4Addr::new(192, 168, 0, 2),
+                Ipv4Addr::new(192, 168, 0, 101),
+            ),
+            (
+                Ipv4Addr::new(192, 168, 0, 101),
+                Ipv4Addr::new(192, 168, 0, 200),
+            ),
+            RamStorage::new(),
+        );
+        let client_id = vec![1u8];
+
+        let offer = storage
+            .allocate(client_id.as_ref(), Some(1000), None)
+            .unwrap();
+        match storage.query_address(&offer.address).unwrap() {
+            LeaseState::Unassigned => {}
+            state => panic!("The offered address is {:?}", state),
+        }
+        let ack = storage
+            .assign(client_id.as_ref(), &offer.address, Some(offer.lease_time))
+            .unwrap();
+
+        let hardware_address = HardwareAddress::new(&[0x00, 0x0c, 0x29, 0x13, 0x0e, 0x37]).unwrap();
+        storage
+            .record_hardware_address(client_id.as_ref(), HardwareType::Ethernet, hardware_address)
+            .unwrap();
+
+        match storage.query_address(&ack.address).unwrap() {
+            LeaseState::Active(binding) => {
+                assert_eq!(binding.client_id, client_id);
+                assert!(binding.lease_time <= 1000);
+                assert_eq!(
+                    binding.hardware_address,
+                    Some((HardwareType::Ethernet, hardware_address))
+                );
+            }
+            state => panic!("The assigned address is {:?}", state),
+        }
+        match storage
+            .query_client(&[vec![2u8], client_id.clone()])
+            .unwrap()
+        {
+            LeaseState::Active(binding) => assert_eq!(binding.address, ack.address),
+            state => panic!("The client is {:?}", state),
+        }
+        match storage.query_address(&Ipv4Addr::new(10, 0, 0, 1)).unwrap() {
+            LeaseState::Unknown => {}
+            state => panic!("The foreign address is {:?}", state),
+        }
+
+        storage
+            .deallocate(client_id.as_ref(), &ack.address)
+            .unwrap();
+        match storage.query_client(&[client_id]).unwrap() {
+            LeaseState::Unassigned => {}
+            state => panic!("The released client is {:?}", state),
+        }
+    }
+
+    #[test]
+    fn reconfigures_only_clients_with_keys() {
+        let mut storage = Database::new(
+            (
+                Ipv4Addr::new(192, 168, 0, 2),
+                Ipv4Addr::new(192, 168, 0, 101),
+            ),
+            (
+                Ipv4Addr::new(192, 168, 0, 101),
+                Ipv4Addr::new(192, 168, 0, 200),
+            ),
+            RamStorage::new(),
+        );
+        let client_id = vec![1u8];
+
+        let offer = storage
+            .allocate(client_id.as_ref(), Some(1000), None)
+            .unwrap();
+        let ack = storage
+            .assign(client_id.as_ref(), &offer.address, Some(offer.lease_time))
+            .unwrap();
+        match storage.reconfigure(&ack.address) {
+            Err(Error::ReconfigureKeyNotFound) => {}
+            result => panic!("The client without a key is {:?}", result),
+        }
+
+        let key = storage.issue_reconfigure_key(client_id.as_ref()).unwrap();
+        let reconfigure = storage.reconfigure(&ack.address).unwrap();
+        assert_eq!(reconfigure.client_id, client_id);
+        assert_eq!(reconfigure.key, key);
+
+        storage
+            .deallocate(client_id.as_ref(), &ack.address)
+            .unwrap();
+        assert!(storage.reconfigure(&ack.address).is_err());
+    }
+
+    #[test]
+    fn allocates_on_selected_subnet() {
+        let mut storage = Database::new(
+            (Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 101)),
+            (Ipv4Addr::new(10, 0, 1, 1), Ipv4Addr::new(10, 0, 1, 100)),
+            RamStorage::new(),
+        );
+        let client_id = vec![1u8];
+        let mask = Ipv4Addr::new(255, 255, 255, 0);
+
+        let offer = storage
+            .allocate_in_subnet(
+                client_id.as_ref(),
+                Some(1000),
+                Some(Ipv4Addr::new(10, 0, 0, 5)),
+                Some((Ipv4Addr::new(10, 0, 1, 0), mask)),
+            )
+            .unwrap();
+        assert_eq!(offer.address, Ipv4Addr::new(10, 0, 1, 1));
+
+        assert!(!storage.serves_subnet(&(Ipv4Addr::new(172, 16, 0, 0), mask)));
+        match storage.allocate_in_subnet(
+            client_id.as_ref(),
+            Some(1000),
+            None,
+            Some((Ipv4Addr::new(172, 16, 0, 0), mask)),
+        ) {
+            Err(Error::SubnetNotServed) => {}
+            result => panic!("The unserved subnet offer is {:?}", result),
+        }
+    }
+
+    #[test]
+    fn leases_off_selected_subnet_are_rejected() {
+        let mut storage = Database::new(
+            (Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 101)),
+            (Ipv4Addr::new(10, 0, 1, 1), Ipv4Addr::new(10, 0, 1, 100)),
+            RamStorage::new(),
+        );
+        let client_id = vec![1u8];
+        let mask = Ipv4Addr::new(255, 255, 255, 0);
+        let selected = Some((Ipv4Addr::new(10, 0, 1, 0), mask));
+        let another = Some((Ipv4Addr::new(10, 0, 0, 0), mask));
+
+        let offer = storage
+            .allocate_in_subnet(client_id.as_ref(), Some(1000), None, selected)
+            .unwrap();
+        match storage.assign_in_subnet(client_id.as_ref(), &offer.address, None, another) {
+            Err(Error::SubnetNotServed) => {}
+            result => panic!("The assignment off the selected subnet is {:?}", result),
+        }
+        let ack = storage
+            .assign_in_subnet(client_id.as_ref(), &offer.address, None, selected)
+            .unwrap();
+
+        match storage.check_in_subnet(client_id.as_ref(), &ack.address, another) {
+            Err(Error::SubnetNotServed) => {}
+            result => panic!("The check off the selected subnet is {:?}", result),
+        }
+        match storage.renew_in_subnet(client_id.as_ref(), &ack.address, None, another) {
+            Err(Error::SubnetNotServed) => {}
+            result => panic!("The renewal off the selected subnet is {:?}", result),
+        }
+        assert!(storage
+            .check_in_subnet(client_id.as_ref(), &ack.address, selected)
+            .is_ok());
+        assert!(storage
+            .renew_in_subnet(client_id.as_ref(), &ack.address, None, selected)
+            .is_ok());
+    }
 }
@@ -4,6 +4,8 @@ use std::net::Ipv4Addr;
 
 use chrono::prelude::*;
 
+use dhcp_protocol::{HardwareAddress, HardwareType};
+
 /// The state of the `Lease`.
 #[derive(Clone)]
 enum State {
@@ -26,6 +28,8 @@ pub struct Lease {
     renewed_at: u32,
     released_at: u32,
     expires_at: u32,
+    reconfigure_key: Option<Vec<u8>>,
+    hardware_address: Option<(HardwareType, HardwareAddress)>,
 }
 
 #[allow(dead_code)]
@@ -43,6 +47,8 @@ impl Lease {
             renewed_at: 0,
             released_at: 0,
             expires_at: 0,
+            reconfigure_key: None,
+            hardware_address: None,
         }
     }
 
@@ -81,6 +87,46 @@ impl Lease {
         self.released_at = Utc::now().timestamp() as u32;
     }
 
+    /// The key the client uses to authenticate `DHCPFORCERENEW` messages.
+    ///
+    /// `None` if the client does not support reconfiguration.
+    pub fn reconfigure_key(&self) -> Option<&[u8]> {
+        self.reconfigure_key.as_deref()
+    }
+
+    /// Records the reconfigure key sent to the client in a `DHCPACK`.
+    pub fn set_reconfigure_key(&mut self, key: Vec<u8>) {
+        self.reconfigure_key = Some(key);
+    }
+
+    /// The client hardware type and address.
+    ///
+    /// `None` if the lease has not been acknowledged yet.
+    pub fn hardware_address(&self) -> Option<(HardwareType, HardwareAddress)> {
+        self.hardware_address
+    }
+
+    /// Records the hardware type and address of the client the lease is acknowledged to.
+    pub fn set_hardware_address(
+        &mut self,
+        hardware_type: HardwareType,
+        hardware_address: HardwareAddress,
+    ) {
+        self.hardware_address = Some((hardware_type, hardware_address));
+    }
+
+    /// The timestamp of the last client transaction (an offer, assignment, renewal or release).
+    pub fn last_transaction_at(&self) -> u32 {
+        *[
+            self.offered_at,
+            self.assigned_at,
+            self.renewed_at,
+            self.released_at,
+        ].iter()
+            .max()
+            .unwrap_or(&0)
+    }
+
     /// The timestamp when the lease is expired in milliseconds.
     pub fn expires_at(&self) -> u32 {
         self.expires_at
@@ -15,16 +15,16 @@ mod storage_ram;
 extern crate log;
 extern crate bytes;
 extern crate chrono;
+extern crate eui48;
 extern crate futures;
 extern crate hostname;
+extern crate rand;
 extern crate tokio;
 #[macro_use]
 extern crate failure;
 #[cfg(any(target_os = "freebsd", target_os = "macos"))]
 extern crate etherparse;
 #[cfg(any(target_os = "freebsd", target_os = "macos"))]
-extern crate eui48;
-#[cfg(any(target_os = "freebsd", target_os = "macos"))]
 extern crate futures_cpupool;
 #[cfg(target_os = "windows")]
 extern crate tokio_process;
@@ -42,7 +42,7 @@ extern crate dhcp_framed;
 extern crate dhcp_protocol;
 
 pub use self::{
-    server::{Server, ServerBuilder},
+    server::{Server, ServerBuilder, ServerHandle},
     storage::Storage,
     storage_ram::RamStorage,
 };
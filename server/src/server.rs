@@ -1,19 +1,32 @@
 //! The main DHCP server module.
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    cmp,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
+use futures::sync::mpsc;
 use hostname;
 use tokio::{io, prelude::*};
 
 #[cfg(any(target_os = "linux", target_os = "windows"))]
 use dhcp_arp;
 use dhcp_framed::DhcpFramed;
-use dhcp_protocol::{Message, MessageType, DHCP_PORT_CLIENT, DHCP_PORT_SERVER};
+use dhcp_protocol::{
+    Authentication, AuthenticationMode, ClientArchitecture, DomainName, KeyStore, Message,
+    MessageType, ReplayDetection, SipServers, VendorInformation, VendorRegistry,
+    AUTHENTICATION_ALGORITHM_HMAC_MD5, DHCP_PORT_CLIENT, DHCP_PORT_SERVER, SIZE_BOOT_FILENAME,
+    V6ONLY_WAIT_MINIMAL,
+};
 
 #[cfg(any(target_os = "freebsd", target_os = "macos"))]
 use bpf::BpfData;
-use builder::MessageBuilder;
-use database::{Database, Error::LeaseInvalid};
+use builder::{MessageBuilder, MessageParameters};
+use database::{
+    Database,
+    Error::{LeaseInvalid, SubnetNotServed},
+    LeaseState,
+};
 use storage::Storage;
 use tokio::net::UdpSocket;
 
@@ -32,6 +45,19 @@ where
     domain_name_servers: Vec<Ipv4Addr>,
     static_routes: Vec<(Ipv4Addr, Ipv4Addr)>,
     classless_static_routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>,
+    client_fqdn_server_update: bool,
+    domain_search: Vec<DomainName>,
+    sip_servers: Option<SipServers>,
+    ipv6_only_preferred: Option<u32>,
+    captive_portal: Option<String>,
+    vendor_specific: VendorRegistry<Vec<u8>>,
+    boot_filenames: Vec<(ClientArchitecture, String)>,
+    user_class_boot_filenames: Vec<(Vec<u8>, String)>,
+    rapid_commit: bool,
+    forcerenew: bool,
+    authentication_mode: AuthenticationMode,
+    authentication_keys: KeyStore,
+    authentication_secret_id: u32,
     #[allow(unused)]
     bpf_num_threads_size: Option<usize>,
 }
@@ -97,10 +123,172 @@ where
             domain_name_servers,
             static_routes,
             classless_static_routes,
+            client_fqdn_server_update: false,
+            domain_search: Vec::new(),
+            sip_servers: None,
+            ipv6_only_preferred: None,
+            captive_portal: None,
+            vendor_specific: VendorRegistry::default(),
+            boot_filenames: Vec::new(),
+            user_class_boot_filenames: Vec::new(),
+            rapid_commit: false,
+            forcerenew: false,
+            authentication_mode: AuthenticationMode::Ignore,
+            authentication_keys: KeyStore::new(),
+            authentication_secret_id: 0,
             bpf_num_threads_size: None,
         }
     }
 
+    /// Sets whether the server performs the A RR DNS updates for clients sending
+    /// the Client FQDN option. The option is echoed with the `S` and `O` flags set accordingly.
+    ///
+    /// If not called during building, the server leaves the A RR updates to clients.
+    pub fn with_client_fqdn_server_update(&mut self, server_update: bool) -> &mut Self {
+        self.client_fqdn_server_update = server_update;
+        self
+    }
+
+    /// Sets the domain search list sent to clients requesting the Domain Search option.
+    ///
+    /// If not called during building, the option is not sent.
+    pub fn with_domain_search(&mut self, domain_search: Vec<DomainName>) -> &mut Self {
+        self.domain_search = domain_search;
+        self
+    }
+
+    /// Sets the SIP servers sent to clients requesting the SIP Servers option.
+    ///
+    /// If not called during building, the option is not sent.
+    pub fn with_sip_servers(&mut self, sip_servers: SipServers) -> &mut Self {
+        self.sip_servers = Some(sip_servers);
+        self
+    }
+
+    /// Sets the `V6ONLY_WAIT` seconds sent to IPv6-only capable clients (RFC 8925).
+    /// The option is only sent to clients requesting it, since the others cannot operate
+    /// without IPv4 addresses. Values below `V6ONLY_WAIT_MINIMAL` are raised to it.
+    ///
+    /// If not called during building, the option is not sent.
+    pub fn with_ipv6_only_preferred(&mut self, ipv6_only_wait: u32) -> &mut Self {
+        self.ipv6_only_preferred = Some(cmp::max(ipv6_only_wait, V6ONLY_WAIT_MINIMAL));
+        self
+    }
+
+    /// Sets the captive portal API URI sent to clients requesting the option (RFC 8910).
+    ///
+    /// If not called during building, the option is not sent.
+    pub fn with_captive_portal(&mut self, uri: String) -> &mut Self {
+        self.captive_portal = Some(uri);
+        self
+    }
+
+    /// Adds the vendor-specific information sent to clients requesting it
+    /// with a vendor class identifier starting with `class_id_prefix`.
+    /// The longest matching prefix is chosen if there are several.
+    ///
+    /// If not called during building, the option is not sent.
+    ///
+    /// # Errors
+    /// `io::Error` if the information cannot be encoded.
+    pub fn with_vendor_specific(
+        &mut self,
+        class_id_prefix: &[u8],
+        information: &dyn VendorInformation,
+    ) -> io::Result<&mut Self> {
+        let vendor_specific = information.to_options()?.to_bytes()?;
+        self.vendor_specific.register(class_id_prefix, vendor_specific);
+        Ok(self)
+    }
+
+    /// Sets the boot file name sent to network booting clients of the architecture.
+    ///
+    /// The client system architecture option lists the architectures in the order
+    /// of the client preference, so the first one having a boot file name is chosen.
+    /// The name must fit the 128-byte `file` message field with its terminating zero.
+    ///
+    /// If not called during building, the boot file name is not sent.
+    ///
+    /// # Errors
+    /// `io::Error` if the name is 128 bytes long or longer.
+    pub fn with_boot_filename(
+        &mut self,
+        architecture: ClientArchitecture,
+        boot_filename: String,
+    ) -> io::Result<&mut Self> {
+        check_boot_filename(&boot_filename)?;
+        self.boot_filenames
+            .retain(|(element, _)| *element != architecture);
+        self.boot_filenames.push((architecture, boot_filename));
+        Ok(self)
+    }
+
+    /// Sets the boot file name sent to network booting clients of the user class (RFC 3004),
+    /// which takes precedence over the architecture ones.
+    ///
+    /// E.g. iPXE sends the `iPXE` user class, so it can be given a script
+    /// instead of being chainloaded again.
+    ///
+    /// The name must fit the 128-byte `file` message field with its terminating zero.
+    ///
+    /// If not called during building, the user class is not taken into account.
+    ///
+    /// # Errors
+    /// `io::Error` if the name is 128 bytes long or longer.
+    pub fn with_user_class_boot_filename(
+        &mut self,
+        user_class: &[u8],
+        boot_filename: String,
+    ) -> io::Result<&mut Self> {
+        check_boot_filename(&boot_filename)?;
+        self.user_class_boot_filenames
+            .retain(|(element, _)| element.as_slice() != user_class);
+        self.user_class_boot_filenames
+            .push((user_class.to_vec(), boot_filename));
+        Ok(self)
+    }
+
+    /// Sets whether the server commits addresses to clients sending the Rapid Commit
+    /// option in `DHCPDISCOVER`, answering with `DHCPACK` instead of `DHCPOFFER`.
+    ///
+    /// Should only be enabled if the server is the only one serving the network,
+    /// since the other servers would also commit addresses to the client.
+    ///
+    /// If not called during building, the server always answers with `DHCPOFFER`.
+    pub fn with_rapid_commit(&mut self, rapid_commit: bool) -> &mut Self {
+        self.rapid_commit = rapid_commit;
+        self
+    }
+
+    /// Sets whether the server sends reconfigure keys to clients supporting
+    /// the Forcerenew Nonce Authentication (RFC 6704), so they could be forced
+    /// to renew their leases with `ServerHandle::force_renew`.
+    ///
+    /// If not called during building, clients cannot be forced to renew their leases.
+    pub fn with_forcerenew(&mut self, forcerenew: bool) -> &mut Self {
+        self.forcerenew = forcerenew;
+        self
+    }
+
+    /// Sets the delayed authentication (RFC 3118) mode and the shared keys.
+    ///
+    /// Replies to clients requesting the authentication are signed with the key
+    /// of the client secret ID or the `secret_id` one if the client has not chosen a key yet.
+    /// In the `Require` mode unauthenticated client messages are discarded.
+    ///
+    /// If not called during building, the authentication option is ignored.
+    pub fn with_authentication(
+        &mut self,
+        mode: AuthenticationMode,
+        keys: KeyStore,
+        secret_id: u32,
+    ) -> &mut Self {
+        self.authentication_mode = mode;
+        self.authentication_keys = keys;
+        self.authentication_secret_id = secret_id;
+        self
+    }
+
     /// Sets the CPU pool size used for BPF communication.
     ///
     /// If not called during building, the BPF object will use its default pool size.
@@ -112,20 +300,19 @@ where
 
     /// Consumes the builder and returns the built server.
     pub fn finish(self) -> io::Result<Server<S>> {
-        Server::new(
-            self.server_ip_address,
-            self.iface_name,
-            self.static_address_range,
-            self.dynamic_address_range,
-            self.storage,
-            self.subnet_mask,
-            self.routers,
-            self.domain_name_servers,
-            self.static_routes,
-            self.classless_static_routes,
-            self.bpf_num_threads_size,
-        )
+        Server::new(self)
+    }
+}
+
+/// Checks if the boot file name fits the `file` field with its terminating zero.
+fn check_boot_filename(boot_filename: &str) -> io::Result<()> {
+    if boot_filename.len() >= SIZE_BOOT_FILENAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The boot file name does not fit the file field",
+        ));
     }
+    Ok(())
 }
 
 /// The struct implementing the `Future` trait.
@@ -137,6 +324,8 @@ where
     socket: DhcpFramed,
     /// The IP address the server is hosted on.
     server_ip_address: Ipv4Addr,
+    /// The mask of the subnets selected by clients with the Subnet Selection option.
+    subnet_mask: Ipv4Addr,
     /// The interface the server works on.
     #[cfg(any(target_os = "windows", target_os = "linux"))]
     iface_name: String,
@@ -144,6 +333,22 @@ where
     builder: MessageBuilder,
     /// The DHCP database using a persistent storage object.
     database: Database<S>,
+    /// Whether addresses are committed right after `DHCPDISCOVER` with the Rapid Commit option.
+    rapid_commit: bool,
+    /// Whether reconfigure keys are sent to clients in `DHCPACK` messages.
+    forcerenew: bool,
+    /// Cloned into `ServerHandle` objects to send the `DHCPFORCERENEW` requests.
+    forcerenew_sender: mpsc::UnboundedSender<Ipv4Addr>,
+    /// Receives the addresses whose clients must be forced to renew their leases.
+    forcerenew_receiver: mpsc::UnboundedReceiver<Ipv4Addr>,
+    /// How the delayed authentication is treated.
+    authentication_mode: AuthenticationMode,
+    /// The delayed authentication shared keys.
+    authentication_keys: KeyStore,
+    /// The secret ID used to sign replies to clients which have not chosen a key yet.
+    authentication_secret_id: u32,
+    /// The last replay detection values of the clients by their identifiers.
+    replay_detection: ReplayDetection<Vec<u8>>,
     /// The asynchronous `netsh` processes used to work with ARP entries.
     #[cfg(target_os = "windows")]
     arp: Option<dhcp_arp::Arp>,
@@ -156,21 +361,8 @@ impl<S> Server<S>
 where
     S: Storage,
 {
-    /// Creates a server future.
-    #[allow(unused_variables)]
-    fn new(
-        server_ip_address: Ipv4Addr,
-        iface_name: String,
-        static_address_range: (Ipv4Addr, Ipv4Addr),
-        dynamic_address_range: (Ipv4Addr, Ipv4Addr),
-        storage: S,
-        subnet_mask: Ipv4Addr,
-        routers: Vec<Ipv4Addr>,
-        domain_name_servers: Vec<Ipv4Addr>,
-        static_routes: Vec<(Ipv4Addr, Ipv4Addr)>,
-        classless_static_routes: Vec<(Ipv4Addr, Ipv4Addr, Ipv4Addr)>,
-        bpf_num_threads_size: Option<usize>,
-    ) -> io::Result<Self> {
+    /// Creates a server future from the builder parameters.
+    fn new(builder: ServerBuilder<S>) -> io::Result<Self> {
         let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), DHCP_PORT_SERVER);
         let socket = UdpSocket::bind(&addr)?;
         socket.set_broadcast(true)?;
@@ -178,32 +370,66 @@ where
         let socket = DhcpFramed::new(socket)?;
         let hostname = hostname::get_hostname();
 
-        let builder = MessageBuilder::new(
-            server_ip_address,
+        #[cfg(any(target_os = "freebsd", target_os = "macos"))]
+        let bpf_data = BpfData::new(&builder.iface_name, builder.bpf_num_threads_size)?;
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        let iface_name = builder.iface_name;
+
+        let message_builder = MessageBuilder::new(MessageParameters {
+            server_ip_address: builder.server_ip_address,
             hostname,
-            subnet_mask,
-            routers,
-            domain_name_servers,
-            static_routes,
-            classless_static_routes,
-        );
+            subnet_mask: builder.subnet_mask,
+            routers: builder.routers,
+            domain_name_servers: builder.domain_name_servers,
+            static_routes: builder.static_routes,
+            classless_static_routes: builder.classless_static_routes,
+            client_fqdn_server_update: builder.client_fqdn_server_update,
+            domain_search: builder.domain_search,
+            sip_servers: builder.sip_servers,
+            ipv6_only_preferred: builder.ipv6_only_preferred,
+            captive_portal: builder.captive_portal,
+            vendor_specific: builder.vendor_specific,
+            boot_filenames: builder.boot_filenames,
+            user_class_boot_filenames: builder.user_class_boot_filenames,
+        });
 
-        let database = Database::new(static_address_range, dynamic_address_range, storage);
+        let database = Database::new(
+            builder.static_address_range,
+            builder.dynamic_address_range,
+            builder.storage,
+        );
+        let (forcerenew_sender, forcerenew_receiver) = mpsc::unbounded();
 
         Ok(Server {
             socket,
-            server_ip_address,
+            server_ip_address: builder.server_ip_address,
+            subnet_mask: builder.subnet_mask,
             #[cfg(any(target_os = "windows", target_os = "linux"))]
-            iface_name: iface_name.to_owned(),
-            builder,
+            iface_name,
+            builder: message_builder,
             database,
+            rapid_commit: builder.rapid_commit,
+            forcerenew: builder.forcerenew,
+            forcerenew_sender,
+            forcerenew_receiver,
+            authentication_mode: builder.authentication_mode,
+            authentication_keys: builder.authentication_keys,
+            authentication_secret_id: builder.authentication_secret_id,
+            replay_detection: ReplayDetection::new(),
             #[cfg(target_os = "windows")]
             arp: None,
             #[cfg(any(target_os = "freebsd", target_os = "macos"))]
-            bpf_data: BpfData::new(&iface_name, bpf_num_threads_size)?,
+            bpf_data,
         })
     }
 
+    /// Returns a handle to control the server after it is spawned.
+    pub fn handle(&self) -> ServerHandle {
+        ServerHandle {
+            forcerenew_sender: self.forcerenew_sender.clone(),
+        }
+    }
+
     /// Chooses the destination IP according to RFC 2131 rules.
     ///
     /// Performs the ARP query in hardware unicast cases and sets the `arp` field
@@ -218,14 +444,20 @@ where
             return (Ipv4Addr::new(255, 255, 255, 255), false);
         }
 
+        // the hardware unicast is only implemented for MAC-48 addresses
+        let client_hardware_address = match request.client_hardware_address.to_mac_address() {
+            Some(client_hardware_address) => client_hardware_address,
+            None => return (Ipv4Addr::new(255, 255, 255, 255), false),
+        };
+
         #[cfg(any(target_os = "linux", target_os = "windows"))]
         {
             info!(
                 "Injecting an ARP entry {} -> {}",
-                request.client_hardware_address, response.your_ip_address,
+                client_hardware_address, response.your_ip_address,
             );
             match dhcp_arp::add(
-                request.client_hardware_address,
+                client_hardware_address,
                 response.your_ip_address,
                 self.iface_name.to_owned(),
             ) {
@@ -247,19 +479,21 @@ where
         (preferably 0xffffffff) as the IP destination address and the link-
         layer broadcast address as the link-layer destination address.
 
-        Note: I don't know yet when unicasting is not possible.
+        Note: unicasting is not possible to clients with non-MAC-48 hardware addresses.
         */
     }
 
-    /// Sends a response using OS-specific features.
+    /// Signs a response if required and sends it using OS-specific features.
     #[allow(unused)]
     fn send_response(
         &mut self,
-        response: Message,
+        request: &Message,
+        mut response: Message,
         destination: Ipv4Addr,
         hw_unicast: bool,
         max_size: Option<u16>,
     ) -> io::Result<()> {
+        let key = self.authenticate(request, &mut response);
         log_send!(response, destination);
 
         #[cfg(any(target_os = "freebsd", target_os = "macos"))]
@@ -270,12 +504,130 @@ where
                     &destination,
                     response,
                     max_size,
+                    key,
                 );
             }
         }
 
         let destination = SocketAddr::new(IpAddr::V4(destination), DHCP_PORT_CLIENT);
-        start_send!(self.socket, destination, response, max_size);
+        start_send!(self.socket, destination, response, max_size, key);
+        Ok(())
+    }
+
+    /// Appends a reconfigure key to the `DHCPACK` if the client supports
+    /// the HMAC-MD5 reconfigure key authentication.
+    fn append_reconfigure_key(
+        &mut self,
+        request: &Message,
+        response: &mut Message,
+        client_id: &[u8],
+    ) {
+        // the option is occupied by the delayed authentication
+        if !self.forcerenew || self.is_authentication_requested(request) {
+            return;
+        }
+        match request.options.forcerenew_nonce_capable {
+            Some(ref algorithms) if algorithms.contains(&AUTHENTICATION_ALGORITHM_HMAC_MD5) => {}
+            _ => return,
+        }
+        match self.database.issue_reconfigure_key(client_id) {
+            Ok(key) => self.builder.append_reconfigure_key(response, &key),
+            Err(error) => warn!("Reconfigure key error: {}", error.to_string()),
+        }
+    }
+
+    /// Records the client hardware address to send it back in leasequery replies
+    /// and `DHCPFORCERENEW` messages.
+    fn record_hardware_address(&mut self, request: &Message, client_id: &[u8]) {
+        if let Err(error) = self.database.record_hardware_address(
+            client_id,
+            request.hardware_type,
+            request.client_hardware_address,
+        ) {
+            warn!("Hardware address recording error: {}", error.to_string());
+        }
+    }
+
+    /// Checks if the client message requests the delayed authentication of replies.
+    fn is_authentication_requested(&self, request: &Message) -> bool {
+        if let AuthenticationMode::Ignore = self.authentication_mode {
+            return false;
+        }
+        match request.options.authentication {
+            Some(ref authentication) => {
+                authentication.is_delayed_request() || authentication.delayed_secret_id().is_some()
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the delayed authentication option of the response if the client has requested it
+    /// and returns the key to sign the response with.
+    ///
+    /// The key is chosen by the client secret ID or the server one if the client
+    /// has not chosen a key yet.
+    fn authenticate(&self, request: &Message, response: &mut Message) -> Option<Vec<u8>> {
+        if !self.is_authentication_requested(request) {
+            return None;
+        }
+        let secret_id = request
+            .options
+            .authentication
+            .as_ref()
+            .and_then(|authentication| authentication.delayed_secret_id())
+            .unwrap_or(self.authentication_secret_id);
+        match self.authentication_keys.get(secret_id) {
+            Some(key) => {
+                response.options.authentication = Some(Authentication::delayed_hmac_md5(
+                    Authentication::monotonic_counter(),
+                    secret_id,
+                ));
+                Some(key.to_vec())
+            }
+            None => {
+                warn!("No authentication key for secret ID {}", secret_id);
+                None
+            }
+        }
+    }
+
+    /// Sends a `DHCPFORCERENEW` to the client the address is leased to.
+    ///
+    /// The message is unicast to the leased address (RFC 3203).
+    fn force_renew(&mut self, address: &Ipv4Addr) -> io::Result<()> {
+        let reconfigure = match self.database.reconfigure(address) {
+            Ok(reconfigure) => reconfigure,
+            Err(error) => {
+                warn!("Forcerenew error: {}", error.to_string());
+                return Ok(());
+            }
+        };
+        let response = self.builder.dhcp_forcerenew(&reconfigure);
+        log_send!(response, reconfigure.address);
+
+        let destination = SocketAddr::new(IpAddr::V4(reconfigure.address), DHCP_PORT_CLIENT);
+        start_send!(
+            self.socket,
+            destination,
+            response,
+            None,
+            Some(reconfigure.key)
+        );
+        Ok(())
+    }
+
+    /// Sends a leasequery reply to the requestor which is usually a relay agent
+    /// listening on the server port.
+    fn send_leasequery_reply(
+        &mut self,
+        response: Message,
+        max_size: Option<u16>,
+    ) -> io::Result<()> {
+        let destination = response.gateway_ip_address;
+        log_send!(response, destination);
+
+        let destination = SocketAddr::new(IpAddr::V4(destination), DHCP_PORT_SERVER);
+        start_send!(self.socket, destination, response, max_size, None);
         Ok(())
     }
 }
@@ -297,10 +649,40 @@ where
                 poll_arp!(self.arp);
             }
             poll_complete!(self.socket);
-            let (addr, request) = poll!(self.socket);
+            // a failed DHCPFORCERENEW must not stop serving the other clients
+            if let Ok(Async::Ready(Some(address))) = self.forcerenew_receiver.poll() {
+                if let Err(error) = self.force_renew(&address) {
+                    warn!("Forcerenew to {} failed: {}", address, error);
+                }
+                continue;
+            }
+            let (addr, (request, packet)) = poll!(self.socket);
             log_receive!(request, addr.ip());
             let dhcp_message_type = validate!(request, addr.ip());
 
+            let client_id = match request.options.client_id {
+                Some(ref client_id) => client_id.as_ref(),
+                None => request.client_hardware_address.as_bytes(),
+            };
+
+            match dhcp_message_type {
+                // leasequery requestors are relay agents which do not share the client keys
+                MessageType::DhcpLeaseQuery => {}
+                _ => {
+                    let last = self.replay_detection.last(&client_id.to_vec());
+                    if !request.is_authentic(
+                        &packet,
+                        self.authentication_mode,
+                        &self.authentication_keys,
+                        last,
+                    ) {
+                        warn!("Ignoring an unauthenticated message from {}", addr.ip());
+                        continue;
+                    }
+                    self.replay_detection.record(client_id.to_vec(), &request);
+                }
+            }
+
             if let Some(dhcp_server_id) = request.options.dhcp_server_id {
                 if dhcp_server_id != self.server_ip_address {
                     warn!("Ignoring a message destined for server {}", dhcp_server_id);
@@ -308,6 +690,16 @@ where
                 }
             }
 
+            // a client selecting an unserved subnet gets no response (RFC 3011 §3)
+            if let Some(subnet) = request.options.subnet_selection {
+                if dhcp_message_type != MessageType::DhcpLeaseQuery
+                    && !self.database.serves_subnet(&(subnet, self.subnet_mask))
+                {
+                    warn!("Ignoring a message selecting the unserved subnet {}", subnet);
+                    continue;
+                }
+            }
+
             /*
             RFC 2131 §4.1
             If the  'ciaddr' field is nonzero, then the server unicasts
@@ -320,11 +712,11 @@ where
             the server broadcasts any DHCPNAK messages to 0xffffffff.
             */
 
-            let client_id = match request.options.client_id {
-                Some(ref client_id) => client_id.as_ref(),
-                None => request.client_hardware_address.as_bytes(),
-            };
             let max_size = request.options.dhcp_max_message_size;
+            let subnet = request
+                .options
+                .subnet_selection
+                .map(|subnet| (subnet, self.subnet_mask));
 
             match dhcp_message_type {
                 MessageType::DhcpDiscover => {
@@ -336,15 +728,55 @@ where
                     the system administrator.
                     */
 
-                    match self.database.allocate(
+                    let offer = self.database.allocate_in_subnet(
                         client_id,
                         request.options.address_time,
                         request.options.address_request,
-                    ) {
+                        subnet,
+                    );
+
+                    // the address is committed at once for the Rapid Commit option (RFC 4039)
+                    if self.rapid_commit && request.options.rapid_commit.is_some() {
+                        let ack = offer.and_then(|offer| {
+                            self.database.assign_in_subnet(
+                                client_id,
+                                &offer.address,
+                                Some(offer.lease_time),
+                                subnet,
+                            )
+                        });
+                        match ack {
+                            Ok(ack) => {
+                                let mut response =
+                                    self.builder.dhcp_discover_to_ack(&request, &ack);
+                                self.record_hardware_address(&request, client_id);
+                                self.append_reconfigure_key(&request, &mut response, client_id);
+                                let (destination, hw_unicast) =
+                                    self.destination(&request, &response);
+                                self.send_response(
+                                    &request,
+                                    response,
+                                    destination,
+                                    hw_unicast,
+                                    max_size,
+                                )?;
+                            }
+                            Err(error) => warn!("Address commitment error: {}", error.to_string()),
+                        };
+                        continue;
+                    }
+
+                    match offer {
                         Ok(offer) => {
                             let response = self.builder.dhcp_discover_to_offer(&request, &offer);
                             let (destination, hw_unicast) = self.destination(&request, &response);
-                            self.send_response(response, destination, hw_unicast, max_size)?;
+                            self.send_response(
+                                &request,
+                                response,
+                                destination,
+                                hw_unicast,
+                                max_size,
+                            )?;
                         }
                         Err(error) => warn!("Address allocation error: {}", error.to_string()),
                     };
@@ -380,18 +812,39 @@ where
                         let address = expect!(request.options.address_request);
                         let lease_time = request.options.address_time;
 
-                        match self.database.assign(client_id, &address, lease_time) {
+                        match self
+                            .database
+                            .assign_in_subnet(client_id, &address, lease_time, subnet)
+                        {
                             Ok(ack) => {
-                                let response = self.builder.dhcp_request_to_ack(&request, &ack);
+                                let mut response = self.builder.dhcp_request_to_ack(&request, &ack);
+                                self.record_hardware_address(&request, client_id);
+                                self.append_reconfigure_key(&request, &mut response, client_id);
                                 let (destination, hw_unicast) =
                                     self.destination(&request, &response);
-                                self.send_response(response, destination, hw_unicast, max_size)?;
+                                self.send_response(
+                                    &request,
+                                    response,
+                                    destination,
+                                    hw_unicast,
+                                    max_size,
+                                )?;
+                            }
+                            // the address is not on the selected subnet, so remain silent
+                            Err(SubnetNotServed) => {
+                                warn!("Address assignment error: {}", SubnetNotServed);
                             }
                             Err(error) => {
                                 warn!("Address assignment error: {}", error.to_string());
                                 let response = self.builder.dhcp_request_to_nak(&request, &error);
                                 let destination = Ipv4Addr::new(255, 255, 255, 255);
-                                self.send_response(response, destination, false, max_size)?;
+                                self.send_response(
+                                    &request,
+                                    response,
+                                    destination,
+                                    false,
+                                    max_size,
+                                )?;
                             }
                         };
                         continue;
@@ -401,12 +854,20 @@ where
                     if request.client_ip_address.is_unspecified() {
                         let address = expect!(request.options.address_request);
 
-                        match self.database.check(client_id, &address) {
+                        match self.database.check_in_subnet(client_id, &address, subnet) {
                             Ok(ack) => {
-                                let response = self.builder.dhcp_request_to_ack(&request, &ack);
+                                let mut response = self.builder.dhcp_request_to_ack(&request, &ack);
+                                self.record_hardware_address(&request, client_id);
+                                self.append_reconfigure_key(&request, &mut response, client_id);
                                 let (destination, hw_unicast) =
                                     self.destination(&request, &response);
-                                self.send_response(response, destination, hw_unicast, max_size)?;
+                                self.send_response(
+                                    &request,
+                                    response,
+                                    destination,
+                                    hw_unicast,
+                                    max_size,
+                                )?;
                             }
                             Err(error) => {
                                 warn!("Address checking error: {}", error.to_string());
@@ -414,7 +875,13 @@ where
                                     let response =
                                         self.builder.dhcp_request_to_nak(&request, &error);
                                     let destination = Ipv4Addr::new(255, 255, 255, 255);
-                                    self.send_response(response, destination, false, max_size)?;
+                                    self.send_response(
+                                        &request,
+                                        response,
+                                        destination,
+                                        false,
+                                        max_size,
+                                    )?;
                                 }
                                 /*
                                 RFC 2131 §4.3.2
@@ -428,14 +895,24 @@ where
 
                     // the client is in the RENEWING or REBINDING state
                     let lease_time = request.options.address_time;
-                    match self
-                        .database
-                        .renew(client_id, &request.client_ip_address, lease_time)
-                    {
+                    match self.database.renew_in_subnet(
+                        client_id,
+                        &request.client_ip_address,
+                        lease_time,
+                        subnet,
+                    ) {
                         Ok(ack) => {
-                            let response = self.builder.dhcp_request_to_ack(&request, &ack);
+                            let mut response = self.builder.dhcp_request_to_ack(&request, &ack);
+                            self.record_hardware_address(&request, client_id);
+                            self.append_reconfigure_key(&request, &mut response, client_id);
                             let (destination, hw_unicast) = self.destination(&request, &response);
-                            self.send_response(response, destination, hw_unicast, max_size)?;
+                            self.send_response(
+                                &request,
+                                response,
+                                destination,
+                                hw_unicast,
+                                max_size,
+                            )?;
                         }
                         Err(error) => warn!("Address checking error: {}", error.to_string()),
                     }
@@ -486,10 +963,84 @@ where
                     );
                     let response = self.builder.dhcp_inform_to_ack(&request, "Accepted");
                     let (destination, hw_unicast) = self.destination(&request, &response);
-                    self.send_response(response, destination, hw_unicast, max_size)?;
+                    self.send_response(&request, response, destination, hw_unicast, max_size)?;
+                }
+                MessageType::DhcpLeaseQuery => {
+                    // the query is made by ciaddr, the client identifier or chaddr (RFC 4388 §6.4)
+
+                    let state = if !request.client_ip_address.is_unspecified() {
+                        self.database.query_address(&request.client_ip_address)
+                    } else if let Some(ref client_id) = request.options.client_id {
+                        self.database.query_client(&[client_id.to_owned()])
+                    } else {
+                        // the client might have sent the hardware address in its `client_id` option
+                        let hardware_address = request.client_hardware_address.as_bytes().to_vec();
+                        let mut client_id = vec![u8::from(request.hardware_type)];
+                        client_id.extend_from_slice(&hardware_address);
+                        self.database.query_client(&[hardware_address, client_id])
+                    };
+
+                    let response = match state {
+                        Ok(LeaseState::Active(binding)) => {
+                            self.builder.dhcp_leasequery_to_active(&request, &binding)
+                        }
+                        Ok(LeaseState::Unassigned) => {
+                            self.builder.dhcp_leasequery_to_unassigned(&request)
+                        }
+                        Ok(LeaseState::Unknown) => {
+                            self.builder.dhcp_leasequery_to_unknown(&request)
+                        }
+                        Err(error) => {
+                            warn!("Leasequery error: {}", error.to_string());
+                            continue;
+                        }
+                    };
+                    self.send_leasequery_reply(response, max_size)?;
                 }
                 _ => {}
             }
         }
     }
 }
+
+/// The handle to control a running server from other tasks.
+#[derive(Clone)]
+pub struct ServerHandle {
+    forcerenew_sender: mpsc::UnboundedSender<Ipv4Addr>,
+}
+
+impl ServerHandle {
+    /// Forces the client the address is leased to renew its lease (RFC 3203).
+    ///
+    /// The `DHCPFORCERENEW` is only sent if the client has got a reconfigure key,
+    /// so the server must be built with `ServerBuilder::with_forcerenew`.
+    ///
+    /// # Errors
+    /// `io::Error` if the server has been dropped.
+    pub fn force_renew(&self, address: Ipv4Addr) -> io::Result<()> {
+        self.forcerenew_sender
+            .unbounded_send(address)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "The server has been dropped"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handle_forwards_forcerenew_addresses() {
+        let (forcerenew_sender, mut forcerenew_receiver) = mpsc::unbounded();
+        let handle = ServerHandle { forcerenew_sender };
+        let address = Ipv4Addr::new(192, 168, 0, 100);
+
+        handle.force_renew(address).unwrap();
+        match forcerenew_receiver.poll() {
+            Ok(Async::Ready(Some(received))) => assert_eq!(received, address),
+            result => panic!("The forwarded address is {:?}", result),
+        }
+
+        drop(forcerenew_receiver);
+        assert!(handle.force_renew(address).is_err());
+    }
+}